@@ -1,13 +1,150 @@
-use fraction::GenericFraction;
+use fraction::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, GenericFraction, ToPrimitive};
+use std::f64::consts::PI;
+use std::fmt;
 use std::rc::Rc;
 
 use crate::structs::sqrt::Sqrt;
 
-use std::ops::{Add, Mul, Div, };
+use std::ops::{Add, Mul, Neg, Sub};
 
 
 pub type F = GenericFraction<u32>;
 
+/// Primes up to this are taken out of numbers by trial division, see `power_part`
+const MAX_FACTOR: i64 = 1 << 16;
+
+// Arithmetic on F that gives NaN when the numerator or denominator overflows a u32, see `Expr::overflowed`
+fn add(a: F, b: F) -> F {
+  a.checked_add(&b).unwrap_or_else(F::nan)
+}
+
+fn sub(a: F, b: F) -> F {
+  a.checked_sub(&b).unwrap_or_else(F::nan)
+}
+
+fn mul(a: F, b: F) -> F {
+  a.checked_mul(&b).unwrap_or_else(F::nan)
+}
+
+fn div(a: F, b: F) -> F {
+  a.checked_div(&b).unwrap_or_else(F::nan)
+}
+
+/// The fraction n/d, NaN if it does not fit
+pub fn frac(n: i64, d: i64) -> F {
+  div(F::from(n), F::from(d))
+}
+
+/// Numerator with its sign, and denominator of a fraction, None for NaN and infinities
+pub fn parts(f: &F) -> Option<(i64, i64)> {
+  let n = *f.numer()? as i64;
+  let d = *f.denom()? as i64;
+  Some((if f.is_sign_negative() { -n } else { n }, d))
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+  if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// Least common multiple of the denominators of the coefficients, None if it overflows or a coefficient is NaN
+fn common_denominator(terms: &[(F, Box<Expr>)]) -> Option<i64> {
+  terms.iter().try_fold(1i64, |l, (c, _)| {
+    let d = parts(c)?.1;
+    (l/gcd(l, d)).checked_mul(d)
+  })
+}
+
+/// The k-th root of x ≥ 0 if it is an integer
+fn exact_root(x: i64, k: u32) -> Option<i64> {
+  let r = (x as f64).powf(1f64/k as f64).round() as i64;
+  (r.max(1) - 1..=r + 1).find(|r| r.checked_pow(k) == Some(x))
+}
+
+/// Largest s with s² dividing n, for n > 0, see `power_part`
+fn square_part(n: i64) -> i64 {
+  power_part(n, 2)
+}
+
+/// Largest s with s^k dividing n, for n > 0, by trial division up to `MAX_FACTOR`
+/// Beyond that only a k-th power of what is left is found, so the powers of two large primes stay in, which is not simplest but still right
+fn power_part(n: i64, k: u32) -> i64 {
+  if n <= 1 {
+    return 1;
+  }
+  let (mut rest, mut s, mut p) = (n, 1, 2);
+  while p <= MAX_FACTOR && p*p <= rest {
+    let mut e = 0u32;
+    while rest % p == 0 {
+      rest /= p;
+      e += 1;
+    }
+    s *= p.pow(e/k);
+    p += 1;
+  }
+  s*exact_root(rest, k).unwrap_or(1)
+}
+
+/// Smallest k with l dividing k^n, for l > 0, except that factors of l beyond `MAX_FACTOR` go in k once
+fn root_denominator(l: i64, n: u32) -> i64 {
+  let (mut rest, mut k, mut p) = (l, 1, 2);
+  while p <= MAX_FACTOR && p*p <= rest {
+    let mut e = 0u32;
+    while rest % p == 0 {
+      rest /= p;
+      e += 1;
+    }
+    k *= p.pow(e.div_ceil(n));
+    p += 1;
+  }
+  k*rest
+}
+
+/// The square root of a fraction, if it is a fraction
+fn rational_sqrt(f: F) -> Option<F> {
+  let (n, d) = parts(&f)?;
+  if n < 0 {
+    return None;
+  }
+  Some(frac(exact_root(n, 2)?, exact_root(d, 2)?))
+}
+
+/// For the sum X of the terms, s/k and Y with integer coefficients and X = (s/k)^n·Y, to take out of an n-th root
+/// None if the numbers overflow
+fn take_out(terms: Vec<(F, Box<Expr>)>, n: u32) -> Option<(F, Expr)> {
+  let l = common_denominator(&terms)?;
+  let k = root_denominator(l, n);
+  let kn = k.checked_pow(n)?;
+  let nums = terms.iter().map(|(c, _)| {
+    let (a, d) = parts(c)?;
+    a.checked_mul(kn/d)
+  }).collect::<Option<Vec<i64>>>()?;
+  let s = power_part(nums.iter().fold(0, |g, a| gcd(g, *a)), n);
+  let inner = Expr::from_terms(terms.into_iter().zip(&nums).map(|((_, e), a)| (F::from(a/s.pow(n)), e)).collect());
+  if inner.overflowed() {
+    return None;
+  }
+  Some((frac(s, k), inner))
+}
+
+/// a, b and D for terms that make up a + b√D, with D an integer
+fn quadratic(terms: &[(F, Box<Expr>)]) -> Option<(F, F, i64)> {
+  let (mut a, mut b, mut radicand) = (F::from(0), F::from(0), None);
+  for (c, e) in terms {
+    match &**e {
+      Expr::Val(v) if *v == F::from(1) => a = add(a, *c),
+      Expr::Sqrt(Sqrt(VOrRef::Val(r))) if radicand.is_none() || radicand == Some(*r) => {
+        b = add(b, *c);
+        radicand = Some(*r);
+      },
+      _ => return None,
+    }
+  }
+  match parts(&radicand?)? {
+    (n, 1) => Some((a, b, n)),
+    _ => None,
+  }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum VOrRef {
   Val(F),
@@ -24,7 +161,7 @@ pub enum Expr {
   /// Just a number
   Val(F),
   /// Sum of terms
-  /// Note that
+  /// Note that a number c is the term c·1, and that terms are kept merged, see `Expr::from_terms`
   Sum {
     terms: Vec<(F,Box<Expr>)>,
   },
@@ -42,15 +179,320 @@ pub enum Expr {
   }
 }
 
+impl Expr {
+  pub fn int(k: i64) -> Expr {
+    Expr::Val(F::from(k))
+  }
+
+  /// 𝛑
+  pub fn pi() -> Expr {
+    Expr::Const { ch: '𝛑', f64: PI }
+  }
+
+  pub fn is_zero(&self) -> bool {
+    matches!(self, Expr::Val(v) if *v == F::from(0))
+  }
+
+  /// Whether a fraction in the expression overflowed while working it out, which leaves NaN in its place and in `to_f64`
+  pub fn overflowed(&self) -> bool {
+    let bad = |f: &F| f.is_nan() || f.is_infinite();
+    match self {
+      Expr::Const { .. } => false,
+      Expr::Val(v) => bad(v),
+      Expr::Sum { terms } => terms.iter().any(|(c, e)| bad(c) || e.overflowed()),
+      Expr::Prod { factors } => factors.iter().any(|(e, p)| bad(p) || e.overflowed()),
+      Expr::Sqrt(Sqrt(VOrRef::Val(v))) => bad(v),
+      Expr::Sqrt(Sqrt(VOrRef::Ref(r))) => r.overflowed(),
+      Expr::Cos { v } | Expr::Sin { v } => v.overflowed(),
+    }
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    match self {
+      Expr::Const { f64, .. } => *f64,
+      Expr::Val(v) => v.to_f64().unwrap_or(f64::NAN),
+      Expr::Sum { terms } => terms.iter().map(|(c, e)| c.to_f64().unwrap_or(f64::NAN)*e.to_f64()).sum(),
+      Expr::Prod { factors } => factors.iter().map(|(e, p)| {
+        let ((n, d), b) = match parts(p) {
+          Some(nd) => (nd, e.to_f64()),
+          None => return f64::NAN,
+        };
+        // odd roots of negative numbers are real
+        match (b < 0f64, d % 2) {
+          (true, 1) => (-b).powf(n as f64/d as f64)*if n % 2 == 0 { 1f64 } else { -1f64 },
+          _ => b.powf(n as f64/d as f64),
+        }
+      }).product(),
+      Expr::Sqrt(s) => s.to_f64(),
+      Expr::Cos { v } => v.to_f64().cos(),
+      Expr::Sin { v } => v.to_f64().sin(),
+    }
+  }
+
+  /// The expression as a sum of terms c·e with c a fraction, a number c being c·1
+  pub fn terms(&self) -> Vec<(F, Box<Expr>)> {
+    match self {
+      Expr::Val(v) => vec![(*v, Box::new(Expr::int(1)))],
+      Expr::Sum { terms } => terms.clone(),
+      e => vec![(F::from(1), Box::new(e.clone()))],
+    }
+  }
+
+  /// The expression as a product of powers
+  pub fn factors(&self) -> Vec<(Box<Expr>, F)> {
+    match self {
+      Expr::Prod { factors } => factors.clone(),
+      e => vec![(Box::new(e.clone()), F::from(1))],
+    }
+  }
+
+  /// The sum of the terms, with nested sums flattened, equal terms merged and zeros dropped, and the number first
+  /// A sum of one term is that term, e.g. 3·1 -> 3, 1·√2 -> √2
+  pub fn from_terms(terms: Vec<(F, Box<Expr>)>) -> Expr {
+    let mut flat: Vec<(F, Box<Expr>)> = Vec::new();
+    for (c, e) in terms {
+      match *e {
+        Expr::Sum { terms: inner } => flat.extend(inner.into_iter().map(|(d, f)| (mul(c, d), f))),
+        Expr::Val(v) => flat.push((mul(c, v), Box::new(Expr::int(1)))),
+        e => flat.push((c, Box::new(e))),
+      }
+    }
+    let mut merged: Vec<(F, Box<Expr>)> = Vec::new();
+    for (c, e) in flat {
+      match merged.iter_mut().find(|(_, f)| *f == e) {
+        Some((d, _)) => *d = add(*d, c),
+        None => merged.push((c, e)),
+      }
+    }
+    merged.retain(|(c, _)| *c != F::from(0));
+    merged.sort_by_key(|(_, e)| **e != Expr::int(1));
+    match merged.len() {
+      0 => Expr::int(0),
+      1 if *merged[0].1 == Expr::int(1) => Expr::Val(merged[0].0),
+      1 if merged[0].0 == F::from(1) => *merged.pop().unwrap().1,
+      _ => Expr::Sum { terms: merged },
+    }
+  }
+
+  /// Square root, denested where possible:
+  /// √(n/d) = s√r/d with r free of squares, √(a+b√D) = √((a+e)/2)±√((a-e)/2) if e = √(a²-b²D) is a fraction,
+  /// otherwise √X = s√Y/k with k²X = s²Y, Y with integer coefficients
+  /// e.g. √(3/4) -> √3/2, √((3+√5)/2) -> (1+√5)/2, √((5+√5)/8) -> √(10+2√5)/4
+  /// Where the numbers for that overflow, the root is left as it is, e.g. √(70000+√2)
+  pub fn sqrt(&self) -> Expr {
+    let plain = || Expr::Sqrt(Sqrt(match self {
+      Expr::Val(v) => VOrRef::Val(*v),
+      e => VOrRef::Ref(Rc::new(e.clone())),
+    }));
+    if self.overflowed() {
+      return plain();
+    }
+    if let Expr::Val(v) = self {
+      let (nd, d) = match parts(v) {
+        Some((n, d)) if n >= 0 => match n.checked_mul(d) {
+          Some(nd) => (nd, d),
+          None => return plain(),
+        },
+        _ => return plain(),
+      };
+      let s = square_part(nd);
+      let r = nd/(s*s);
+      let root = if r == 1 { Expr::int(1) } else { Expr::Sqrt(Sqrt(VOrRef::Val(F::from(r)))) };
+      return Expr::from_terms(vec![(frac(s, d), Box::new(root))]);
+    }
+    let terms = self.terms();
+    if let Some((a, b, d)) = quadratic(&terms) {
+      if let Some(e) = rational_sqrt(sub(mul(a, a), mul(mul(b, b), F::from(d)))).filter(|e| a >= *e) {
+        let (x, y) = (Expr::Val(div(add(a, e), F::from(2))).sqrt(), Expr::Val(div(sub(a, e), F::from(2))).sqrt());
+        let root = if b < F::from(0) { x - y } else { x + y };
+        if !root.overflowed() {
+          return root;
+        }
+      }
+    }
+    match take_out(terms, 2) {
+      // Y has smaller numbers, so denesting may work for it where it overflowed for X
+      Some((c, inner)) if inner != *self => Expr::Val(c)*inner.sqrt(),
+      Some((c, inner)) => Expr::from_terms(vec![(c, Box::new(Expr::Sqrt(Sqrt(VOrRef::Ref(Rc::new(inner))))))]),
+      None => plain(),
+    }
+  }
+
+  /// 1/x, with the denominator made rational for x = a+b√D, e.g. 1/(1+√2) -> -1+√2, otherwise the power -1
+  pub fn recip(&self) -> Expr {
+    match self {
+      Expr::Val(v) => Expr::Val(div(F::from(1), *v)),
+      Expr::Prod { factors } => Expr::Prod { factors: factors.iter().map(|(e, p)| (e.clone(), -*p)).collect() },
+      _ => match quadratic(&self.terms()) {
+        Some((a, b, d)) => {
+          let norm = sub(mul(a, a), mul(mul(b, b), F::from(d)));
+          (Expr::Val(a) - Expr::Val(b)*Expr::int(d).sqrt())*Expr::Val(div(F::from(1), norm))
+        },
+        None => Expr::Prod { factors: vec![(Box::new(self.clone()), frac(-1, 1))] },
+      },
+    }
+  }
+
+  /// x^p, worked out by `recip` and `sqrt` for p = -1, 1/2 and -1/2 where they can, otherwise the power p
+  pub fn pow(&self, p: F) -> Expr {
+    match parts(&p) {
+      Some((1, 1)) => self.clone(),
+      Some((-1, 1)) => self.recip(),
+      Some((1, 2)) => self.sqrt(),
+      Some((-1, 2)) => match self.recip() {
+        Expr::Prod { .. } => Expr::Prod { factors: vec![(Box::new(self.clone()), p)] },
+        r => r.sqrt(),
+      },
+      _ => Expr::Prod { factors: vec![(Box::new(self.clone()), p)] },
+    }
+  }
+
+  /// n-th root, a fraction for n-th powers of fractions, e.g. ∛(27/8) -> 3/2, otherwise the power 1/n
+  /// with the denominator and n-th powers taken out as for `Expr::sqrt`, e.g. ∛(19/27+√33/9) -> ∛(19+3√33)/3
+  pub fn root(&self, n: u32) -> Expr {
+    if n == 2 {
+      return self.sqrt();
+    }
+    let plain = Expr::Prod { factors: vec![(Box::new(self.clone()), frac(1, n as i64))] };
+    if self.overflowed() {
+      return plain;
+    }
+    if let Expr::Val(v) = self {
+      if let Some((a, b)) = parts(v) {
+        if let (Some(p), Some(q)) = (exact_root(a.abs(), n), exact_root(b, n)) {
+          if a >= 0 || n % 2 == 1 {
+            return Expr::Val(frac(a.signum()*p, q));
+          }
+        }
+      }
+    }
+    match take_out(self.terms(), n) {
+      Some((c, inner)) => {
+        let root = Expr::Prod { factors: vec![(Box::new(inner), frac(1, n as i64))] };
+        Expr::from_terms(vec![(c, Box::new(root))])
+      },
+      None => plain,
+    }
+  }
+
+  /// The expression as a factor: sums, products other than a single root, negative numbers and fractions in brackets
+  fn factor_string(&self) -> String {
+    match self {
+      Expr::Prod { factors } if factors.len() == 1 && parts(&factors[0].1) == Some((1, 3)) => self.to_string(),
+      Expr::Sum { .. } | Expr::Prod { .. } => format!("({})", self),
+      Expr::Val(v) if !matches!(parts(v), Some((n, 1)) if n >= 0) => format!("({})", self),
+      _ => self.to_string(),
+    }
+  }
+}
+
+impl fmt::Display for Expr {
+  /// Sums are written over a common denominator
+  /// e.g. 3/2, (1+√5)/2, √(10+2√5)/4, -√3/3, ∛(19+3√33), 2cos(𝛑/7)
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Expr::Const { ch, .. } => write!(f, "{}", ch),
+      Expr::Val(v) => write!(f, "{}", v),
+      Expr::Sqrt(s) => match s.radicand() {
+        Expr::Val(v) if matches!(parts(&v), Some((n, 1)) if n >= 0) => write!(f, "√{}", v),
+        r => write!(f, "√({})", r),
+      },
+      Expr::Sum { terms } => {
+        let l = match common_denominator(terms) {
+          Some(l) if terms.iter().all(|(c, _)| parts(c).is_some_and(|(n, d)| n.checked_mul(l/d).is_some())) => l,
+          // each term with its own fraction
+          _ => {
+            let s: Vec<String> = terms.iter().map(|(c, e)| format!("({}){}", c, e.factor_string())).collect();
+            return write!(f, "{}", s.join("+"));
+          }
+        };
+        let mut s = String::new();
+        for (i, (c, e)) in terms.iter().enumerate() {
+          let (n, d) = parts(c).unwrap();
+          let k = n*(l/d);
+          s.push_str(if k < 0 { "-" } else if i == 0 { "" } else { "+" });
+          match (k.abs(), &**e) {
+            (k, Expr::Val(_)) => s.push_str(&k.to_string()),
+            (1, e) => s.push_str(&e.factor_string()),
+            (k, e) => s.push_str(&format!("{}{}", k, e.factor_string())),
+          }
+        }
+        match (l, terms.len()) {
+          (1, _) => write!(f, "{}", s),
+          (l, 1) => write!(f, "{}/{}", s, l),
+          (l, _) => write!(f, "({})/{}", s, l),
+        }
+      },
+      Expr::Prod { factors } => {
+        let powers: Vec<String> = factors.iter().map(|(e, p)| {
+          match parts(p) {
+            Some((1, 1)) => e.factor_string(),
+            Some((1, 3)) => format!("∛{}", e.factor_string()),
+            Some((n, 1)) => format!("{}^{}", e.factor_string(), n),
+            Some((n, d)) => format!("{}^({}/{})", e.factor_string(), n, d),
+            None => format!("{}^({})", e.factor_string(), p),
+          }
+        }).collect();
+        write!(f, "{}", powers.join("·"))
+      },
+      Expr::Cos { v } => write!(f, "cos({})", v),
+      Expr::Sin { v } => write!(f, "sin({})", v),
+    }
+  }
+}
+
+impl Add for Expr {
+  type Output = Self;
+  fn add(self, rhs: Self) -> Self::Output {
+    Expr::from_terms([self.terms(), rhs.terms()].concat())
+  }
+}
+
+impl Neg for Expr {
+  type Output = Self;
+  fn neg(self) -> Self::Output {
+    Expr::from_terms(self.terms().into_iter().map(|(c, e)| (-c, e)).collect())
+  }
+}
+
+impl Sub for Expr {
+  type Output = Self;
+  fn sub(self, rhs: Self) -> Self::Output {
+    self + -rhs
+  }
+}
+
 impl Mul for Expr {
   type Output = Self;
   fn mul(self, rhs: Self) -> Self::Output {
       match (self, rhs) {
           (Expr::Val(vs), Expr::Val(vr)) => {
-            Expr::Val(vs*vr)
+            Expr::Val(mul(vs, vr))
           },
-          _ => {
-            todo!("implement multiplication for other types")
+          (Expr::Val(v), e) | (e, Expr::Val(v)) => {
+            Expr::from_terms(e.terms().into_iter().map(|(c, f)| (mul(c, v), f)).collect())
+          },
+          (Expr::Sum { terms }, e) | (e, Expr::Sum { terms }) => {
+            terms.into_iter().fold(Expr::int(0), |acc, (c, f)| acc + Expr::Val(c)*(*f*e.clone()))
+          },
+          (Expr::Sqrt(a), Expr::Sqrt(b)) => {
+            (*(a*b)).clone()
+          },
+          (a, b) => {
+            // powers of equal factors are added, e.g. ∛2·∛2 = 2^(2/3)
+            let mut factors = a.factors();
+            for (e, p) in b.factors() {
+              match factors.iter_mut().find(|(f, _)| *f == e) {
+                Some((_, q)) => *q = add(*q, p),
+                None => factors.push((e, p)),
+              }
+            }
+            factors.retain(|(_, p)| *p != F::from(0));
+            match factors.len() {
+              0 => Expr::int(1),
+              1 if factors[0].1 == F::from(1) => *factors.pop().unwrap().0,
+              _ => Expr::Prod { factors },
+            }
           }
       }
   }
@@ -59,24 +501,69 @@ impl Mul for Expr {
 
 impl Mul<Expr> for &mut Rc<Expr> {
   type Output = Rc<Expr>;
+  /// Multiplies in place if there are no other references
   fn mul(self, rhs: Expr) -> Self::Output {
-    match Rc::<Expr>::get_mut(self) {
-        Some(s) => {
-          
-        },
-        None => {
-          match (&*self, rhs) {
-            (Expr::Val(vs), Expr::Val(vr)) => {
-              let m = Rc::<Expr>::get_mut(self);
-              Rc::new(Expr::Val(vs*vr))
-            },
-            _ => {
-              todo!("Implement multiplication for other Rc types")
-            }
-        }
-        }
-    }
-      
+    let e = Rc::make_mut(self);
+    *e = e.clone()*rhs;
+    self.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sqrt(k: i64) -> Expr {
+    Expr::int(k).sqrt()
+  }
+
+  #[test]
+  fn simplify() {
+    assert_eq!(Expr::Val(frac(3, 4)).sqrt().to_string(), "√3/2");
+    assert_eq!(Expr::Val(frac(1, 4)).sqrt().to_string(), "1/2");
+    assert_eq!(sqrt(12).to_string(), "2√3");
+    // φ = √((3+√5)/2)
+    let phi2 = Expr::Val(frac(3, 2)) + Expr::Val(frac(1, 2))*sqrt(5);
+    assert_eq!(phi2.sqrt().to_string(), "(1+√5)/2");
+    // icosahedron: √((5+√5)/8)
+    let r2 = Expr::Val(frac(5, 8)) + Expr::Val(frac(1, 8))*sqrt(5);
+    assert_eq!(r2.sqrt().to_string(), "√(10+2√5)/4");
+    assert!((r2.sqrt().to_f64() - ((5f64 + 5f64.sqrt())/8f64).sqrt()).abs() < 1e-12);
+    assert_eq!((-sqrt(5)*Expr::Val(frac(1, 5))).to_string(), "-√5/5");
+    assert_eq!(Expr::int(65521*65521).sqrt().to_string(), "65521");
+    assert_eq!((sqrt(2)*sqrt(2)).to_string(), "2");
+    assert_eq!((sqrt(2)*sqrt(3)).to_string(), "√6");
+    assert_eq!((sqrt(2) + sqrt(2) - Expr::int(1)).to_string(), "-1+2√2");
+  }
+
+  #[test]
+  fn overflow() {
+    let tiny = Expr::Val(frac(1, 70000));
+    assert!(!tiny.overflowed() && (tiny.clone()*tiny).overflowed());
+    // a² - b²D overflows, so √(70000+√2) is not denested
+    let big = Expr::int(70000) + sqrt(2);
+    assert_eq!(big.sqrt().to_string(), "√(70000+√2)");
+    assert!((big.sqrt().to_f64() - (70000f64 + 2f64.sqrt()).sqrt()).abs() < 1e-9);
+    // but (22070-9870√5)·225/16 has a² too large, and 22070-9870√5 denests
+    let x = (Expr::int(22070) - Expr::int(9870)*sqrt(5))*Expr::Val(frac(225, 16));
+    assert_eq!(x.sqrt().to_string(), "(-1575+705√5)/4");
+    // n·d overflows an i64
+    let r = Expr::Val(frac(4294967291, 4294967279)).sqrt();
+    assert!(!r.overflowed() && (r.to_f64() - (4294967291f64/4294967279f64).sqrt()).abs() < 1e-12);
+  }
+
+  #[test]
+  fn roots() {
+    assert_eq!(Expr::Val(frac(-27, 8)).root(3).to_string(), "-3/2");
+    let t = Expr::int(19) + Expr::int(3)*sqrt(33);
+    assert_eq!(t.root(3).to_string(), "∛(19+3√33)");
+    assert!((t.root(3).to_f64() - (19f64 + 3f64*33f64.sqrt()).cbrt()).abs() < 1e-12);
+    let u = Expr::Val(frac(19, 27)) + Expr::Val(frac(1, 9))*sqrt(33);
+    assert_eq!((Expr::Val(frac(1, 3)) + u.root(3)).to_string(), "(1+∛(19+3√33))/3");
+    assert_eq!((Expr::int(1) + sqrt(2)).recip().to_string(), "-1+√2");
+    assert_eq!((Expr::int(3) + sqrt(5)).pow(frac(-1, 2)).to_string(), "(√10-√2)/4");
+    let cos = Expr::Cos { v: Box::new(Expr::Val(frac(1, 7))*Expr::pi()) };
+    assert_eq!((Expr::int(2)*cos).to_string(), "2cos(𝛑/7)");
   }
 }
 
@@ -1,8 +1,10 @@
-use std::collections::{HashSet, hash_map::RandomState};
 pub mod enums;
 pub mod structs;
 pub mod traits;
 
+pub use enums::Expr;
+
+// use std::collections::{HashSet, hash_map::RandomState};
 // pub struct Ring(HashSet<ExprLayer, RandomState>);
 
 // pub enum ExprLayer<A>
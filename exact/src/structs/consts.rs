@@ -1,9 +1,4 @@
-use std::ops::Mul;
-use std::rc::Rc;
-
-use crate::structs::*;
-use crate::enums::*;
-
+#[allow(dead_code)]
 struct Const {
   ch: char,
   f64: f64,
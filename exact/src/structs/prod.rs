@@ -1,7 +1,7 @@
 
 use crate::enums::{Expr};
 use std::rc::Rc;
-use std::ops::{Add, Mul};
+#[allow(dead_code)]
 struct Prod {
   factors: Vec<(Rc<Expr>, i64)>
 }
@@ -1,11 +1,25 @@
 use std::rc::Rc;
-use std::ops::{Mul, Add};
+use std::ops::Mul;
 
-use crate::enums::{F, Expr, VOrRef};
+use crate::enums::{Expr, VOrRef};
 
+/// Square root of a number or an expression, see `Expr::sqrt` for making one in simplest form
 #[derive(Debug, PartialEq, Clone)]
-pub struct Sqrt(VOrRef);
+pub struct Sqrt(pub VOrRef);
 
+impl Sqrt {
+  /// The number under the root
+  pub fn radicand(&self) -> Expr {
+    match &self.0 {
+      VOrRef::Val(v) => Expr::Val(*v),
+      VOrRef::Ref(r) => (**r).clone(),
+    }
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    self.radicand().to_f64().sqrt()
+  }
+}
 
 impl Mul for Sqrt {
   type Output = Rc<Expr>;
@@ -22,19 +36,8 @@ impl Mul for Sqrt {
             }
           }
         },
-        (Sqrt(VOrRef::Val(v_s)), Sqrt(VOrRef::Val(v_r))) => {
-          Rc::new(Expr::Sqrt(Sqrt(VOrRef::Val(v_s*v_r))))
-        },
-        (Sqrt::Val(v_s), Sqrt::Ref(r_r)) => {
-          // 
-          Rc::new(Expr::Sqrt(Sqrt::Ref(r_r*Expr::Val(v_s))))
-        },
-        (Sqrt::Ref(r_s), Sqrt::Val(v_r)) => {
-          Rc::new(Expr::Sqrt(Sqrt{v:Sqrt::Ref(r_s*Expr::Val(v_r))}))
-        },
-        (Sqrt::Ref(r_s), Sqrt::Ref(r_r)) => {
-          todo!("ref sqrt calc");
-          // Expr::Val(F::from(5))
+        (s, r) => {
+          Rc::new((s.radicand()*r.radicand()).sqrt())
         }
       }
   }
@@ -42,6 +45,7 @@ impl Mul for Sqrt {
 
 #[test]
 fn sqrt_mul() {
-  let s = Sqrt{v:Sqrt::Val(F::from(5))};
+  use crate::enums::F;
+  let s = Sqrt(VOrRef::Val(F::from(5)));
   assert_eq!(&*(s.clone()*s), &Expr::Val(F::from(5)));
-}
\ No newline at end of file
+}
@@ -4,12 +4,14 @@
 /// Name of a Value. Should return char
 /// e.g. Sqrt<T> -> √
 /// 
+#[allow(dead_code)]
 trait Char {
   fn ch(&self) -> char;
 }
 
 /// Representation. Should return a string that results in the same valeu
 /// e.g. assert_eq!(exact::from(some_exact::repr()), some_exact)
+#[allow(dead_code)]
 trait Repr {
     fn repr(&self) -> String;
 }
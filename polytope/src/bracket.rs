@@ -0,0 +1,353 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use petgraph::graph::NodeIndex;
+
+use crate::{components, mark, CoxEdge, CoxGraph, CoxGroup, CoxNode};
+use crate::parse::{Scanner, SyntaxError};
+
+/// A symmetry group in Coxeter bracket notation
+/// The graph only contains unringed nodes, the subgroup and extension markers are kept separately:
+/// [5,3]+    -> rotational subgroup
+/// [3+,4]    -> ionic subgroup, the edges carrying a '+' are stored as node pairs
+/// [[3,3,3]] -> extended by a diagram automorphism, one level per extra pair of brackets
+/// The extension is kept per connected component, in the order of `components`, so [[3,3,3]]×[ ] only extends the first
+#[derive(Debug, Clone)]
+pub struct Bracket<T: Clone> {
+  pub graph: CoxGraph<T>,
+  pub rotational: bool,
+  pub ionic: Vec<(usize, usize)>,
+  pub extension: Vec<u32>,
+}
+
+impl<T: Clone> Bracket<T> {
+  pub fn into_group(self) -> CoxGroup<T> {
+    CoxGroup { graph: self.graph, polys: Vec::new() }
+  }
+}
+
+/// Minimal parser for bracket notation based on the following BNF:
+/// <name> ::= <factor> {"×" <factor>} ["+"]
+/// <factor> ::= "[" <factor> "]" | "[" <body> "]"
+/// <body> ::= [" "] | <item> {"," <item>} | "(" <mark> {"," <mark>} ")" | <mark> "^[" <n> "]"
+/// <item> ::= <mark> ["+"] | <mark> "^" <n> | <mark> "^{" <n> {"," <n>} "}"
+///
+/// e.g. [4,3,3], [3^{1,1,1}], [4,3^{1,1}], [5,3]+, [[3,3,3]], [3+,4], [(3,3,4)], [3^[3]], [4,3]×[ ]
+/// A mark of 2 adds a node without an edge, so [4,3,2] = [4,3]×[ ]
+impl Scanner {
+  pub fn parse_bracket<T: Debug + Clone + From<f64>>(&mut self) -> Result<Bracket<T>, SyntaxError> {
+    let mut bracket = Bracket::<T> {
+      graph: CoxGraph::<T>::default(),
+      rotational: false,
+      ionic: Vec::new(),
+      extension: Vec::new(),
+    };
+    // the first node of every factor and its extension
+    let mut factors: Vec<(usize, u32)> = Vec::new();
+    loop {
+      let start = bracket.graph.node_count();
+      factors.push((start, self.bracket_factor(&mut bracket)?));
+      if !self.take(&'×') {
+        break;
+      }
+    }
+    bracket.extension = components(&bracket.graph).iter().map(|c| {
+      factors.iter().rev().find(|(start, _)| *start <= c[0]).unwrap().1
+    }).collect();
+    bracket.rotational = self.take(&'+');
+    if !self.is_done() {
+      return Err(SyntaxError::new_parse_error(format!("Not at the end of the string at {}", self.cursor())));
+    }
+    Ok(bracket)
+  }
+
+  /// Reads one factor of a product, returns its number of extra pairs of brackets
+  fn bracket_factor<T: Debug + Clone + From<f64>>(&mut self, bracket: &mut Bracket<T>) -> Result<u32, SyntaxError> {
+    if !self.take(&'[') {
+      return Err(SyntaxError::new_parse_error(format!("Expected '[' at {}", self.cursor())));
+    }
+    let extension = if self.peek() == Some('[') {
+      self.bracket_factor(bracket)? + 1
+    } else {
+      self.bracket_body(bracket)?;
+      0
+    };
+    if !self.take(&']') {
+      return Err(SyntaxError::new_parse_error(format!("Expected ']' at {}", self.cursor())));
+    }
+    Ok(extension)
+  }
+
+  fn bracket_body<T: Debug + Clone + From<f64>>(&mut self, bracket: &mut Bracket<T>) -> Result<(), SyntaxError> {
+    self.take(&' ');
+    let graph = &mut bracket.graph;
+    let mut cur = self.bracket_node(graph)?;
+    if self.peek() == Some(']') {
+      // [ ]: a single mirror
+      return Ok(());
+    }
+    if self.take(&'(') {
+      // cyclic: [(p,q,r)]
+      let first = cur;
+      loop {
+        let e = self.read_mark()?;
+        if self.take(&')') {
+          add_mark(graph, cur, first, e);
+          return Ok(());
+        }
+        let n = self.bracket_node(graph)?;
+        add_mark(graph, cur, n, e);
+        cur = n;
+        if !self.take(&',') {
+          return Err(SyntaxError::new_parse_error(format!("Expected ',' or ')' at {}", self.cursor())));
+        }
+      }
+    }
+    loop {
+      let e = self.read_mark()?;
+      if self.take(&'^') {
+        if self.take(&'{') {
+          // branch: arms of the given lengths start from the current node
+          let center = cur;
+          loop {
+            let mut arm = center;
+            for _ in 0..self.read_number()? {
+              let n = self.bracket_node(graph)?;
+              add_mark(graph, arm, n, e);
+              arm = n;
+            }
+            if self.take(&'}') {
+              break;
+            }
+            if !self.take(&',') {
+              return Err(SyntaxError::new_parse_error(format!("Expected ',' or '}}' at {}", self.cursor())));
+            }
+          }
+          // nothing can follow a branch
+          return Ok(());
+        } else if self.take(&'[') {
+          // cycle of n nodes: [3^[n]]
+          let first = cur;
+          let len = self.read_number()?;
+          if len < 3 || !self.take(&']') {
+            return Err(SyntaxError::new_parse_error(format!("Not a cycle at {}", self.cursor())));
+          }
+          for i in 1..len {
+            let n = self.bracket_node(graph)?;
+            add_mark(graph, cur, n, e);
+            cur = n;
+            if i == len - 1 {
+              add_mark(graph, cur, first, e);
+            }
+          }
+          return Ok(());
+        } else {
+          // repetition: [3^3] = [3,3,3]
+          for _ in 0..self.read_number()? {
+            let n = self.bracket_node(graph)?;
+            add_mark(graph, cur, n, e);
+            cur = n;
+          }
+        }
+      } else {
+        let n = self.bracket_node(graph)?;
+        add_mark(graph, cur, n, e);
+        if self.take(&'+') {
+          bracket.ionic.push((cur, n));
+        }
+        cur = n;
+      }
+      if !self.take(&',') {
+        return Ok(());
+      }
+    }
+  }
+
+  /// Adds an unringed node to the graph, returns its index
+  fn bracket_node<T: Debug + Clone + From<f64>>(&mut self, graph: &mut CoxGraph<T>) -> Result<usize, SyntaxError> {
+    let i = graph.node_count();
+    let n = graph.add_node(CoxNode::<T> {
+      name: (b'a' + (i % 26) as u8) as char,
+      repr: "o".to_string(),
+      val: self.eval::<T>(&'o')?,
+      mats: Vec::new(),
+      cart_vec: None,
+    });
+    Ok(n.index())
+  }
+}
+
+/// Adds the edge between nodes a and b, 2-edges are implicit
+fn add_mark<T: Clone>(graph: &mut CoxGraph<T>, a: usize, b: usize, e: CoxEdge) {
+  if e != CoxEdge::Int(2) {
+    graph.add_edge(NodeIndex::new(a), NodeIndex::new(b), e);
+  }
+}
+
+/// Name of a set of connected nodes
+/// Chains are written with the larger marks first: [4,3,3] rather than [3,3,4]
+fn component_name<T: Clone>(graph: &CoxGraph<T>, comp: &[usize], ionic: &[(usize, usize)]) -> Option<String> {
+  if comp.len() == 1 {
+    return Some("[ ]".to_string());
+  }
+  let neighbors = |i: usize| -> Vec<usize> {
+    graph.neighbors(NodeIndex::new(i)).map(|n| n.index()).collect()
+  };
+  let edges: usize = comp.iter().map(|i| neighbors(*i).len()).sum::<usize>() / 2;
+  let degrees: Vec<usize> = comp.iter().map(|i| neighbors(*i).len()).collect();
+  let is_ionic = |a: usize, b: usize| ionic.contains(&(a, b)) || ionic.contains(&(b, a));
+
+  // walks from `start` via `next`, returns the visited nodes
+  let walk = |start: usize, next: usize| -> Vec<usize> {
+    let mut path = vec![start, next];
+    loop {
+      let last = path[path.len() - 1];
+      match neighbors(last).into_iter().find(|n| !path.contains(n)) {
+        Some(n) => path.push(n),
+        None => return path,
+      }
+    }
+  };
+  let marks = |path: &[usize], closed: bool| -> Vec<(f64, String)> {
+    let mut pairs: Vec<(usize, usize)> = path.windows(2).map(|w| (w[0], w[1])).collect();
+    if closed {
+      pairs.push((path[path.len() - 1], path[0]));
+    }
+    pairs.iter().map(|(a, b)| {
+      let e = mark(graph, *a, *b);
+      let plus = if is_ionic(*a, *b) { "+" } else { "" };
+      (f64::from(&e), format!("{}{}", e, plus))
+    }).collect()
+  };
+  let larger = |a: Vec<(f64, String)>, b: Vec<(f64, String)>| -> Vec<(f64, String)> {
+    let ka: Vec<f64> = a.iter().map(|m| m.0).collect();
+    let kb: Vec<f64> = b.iter().map(|m| m.0).collect();
+    if kb > ka { b } else { a }
+  };
+  let join = |ms: Vec<(f64, String)>| ms.into_iter().map(|m| m.1).collect::<Vec<_>>().join(",");
+
+  if edges == comp.len() - 1 && degrees.iter().all(|d| *d <= 2) {
+    // chain
+    let ends: Vec<usize> = comp.iter().zip(&degrees).filter(|(_, d)| **d == 1).map(|(i, _)| *i).collect();
+    let path = walk(ends[0], neighbors(ends[0])[0]);
+    let fwd = marks(&path, false);
+    let mut bwd = fwd.clone();
+    bwd.reverse();
+    return Some(format!("[{}]", join(larger(fwd, bwd))));
+  }
+  if edges == comp.len() && degrees.iter().all(|d| *d == 2) {
+    // cycle: pick the rotation and direction that gives the largest sequence
+    let mut best: Option<Vec<(f64, String)>> = None;
+    for start in comp {
+      for next in neighbors(*start) {
+        let ms = marks(&walk(*start, next), true);
+        best = Some(match best {
+          Some(b) => larger(b, ms),
+          None => ms,
+        });
+      }
+    }
+    let best = best.unwrap();
+    if best.iter().all(|m| m.1 == "3") {
+      return Some(format!("[3^[{}]]", comp.len()));
+    }
+    return Some(format!("[({})]", join(best)));
+  }
+  if edges == comp.len() - 1 && ionic.is_empty() {
+    // [p^{a,b,c}]: a single branch node with arms of equal marks,
+    // optionally led in by a chain of other marks: [4,3^{1,1}]
+    let centers: Vec<usize> = comp.iter().zip(&degrees).filter(|(_, d)| **d > 2).map(|(i, _)| *i).collect();
+    if centers.len() != 1 {
+      return None;
+    }
+    let c = centers[0];
+    let arms: Vec<Vec<(f64, String)>> = neighbors(c).into_iter().map(|n| marks(&walk(c, n), false)).collect();
+    let uniform = |arm: &Vec<(f64, String)>, e: &str| arm.iter().all(|m| m.1 == e);
+    let mut leads: Vec<Option<usize>> = vec![None];
+    leads.extend((0..arms.len()).map(Some));
+    for lead in leads {
+      let rest: Vec<&Vec<(f64, String)>> = arms.iter().enumerate().filter(|(i, _)| Some(*i) != lead).map(|(_, a)| a).collect();
+      let e = rest[0][0].1.clone();
+      if !rest.iter().all(|a| uniform(a, &e)) {
+        continue;
+      }
+      let mut lens: Vec<usize> = rest.iter().map(|a| a.len()).collect();
+      lens.sort_by(|a, b| b.cmp(a));
+      let lens: Vec<String> = lens.iter().map(|a| a.to_string()).collect();
+      let prefix = match lead {
+        Some(l) => {
+          let mut ms = arms[l].clone();
+          ms.reverse();
+          format!("{},", join(ms))
+        },
+        None => String::new(),
+      };
+      return Some(format!("[{}{}^{{{}}}]", prefix, e, lens.join(",")));
+    }
+  }
+  None
+}
+
+/// Names of the components joined by ×, each wrapped in as many extra brackets as its extension, missing extensions are 0
+fn graph_name<T: Clone>(graph: &CoxGraph<T>, ionic: &[(usize, usize)], extension: &[u32]) -> Option<String> {
+  if graph.node_count() == 0 {
+    return None;
+  }
+  let names: Option<Vec<String>> = components(graph).iter().enumerate().map(|(i, c)| {
+    let ext = *extension.get(i).unwrap_or(&0) as usize;
+    component_name(graph, c, ionic).map(|n| format!("{}{}{}", "[".repeat(ext), n, "]".repeat(ext)))
+  }).collect();
+  names.map(|n| n.join("×"))
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Returns the name of the symmetry group in bracket notation, e.g. x4o3o -> [4,3]
+  /// Disconnected diagrams are written as products: x4o3o2x -> [4,3]×[ ]
+  /// None if some component is not a chain, cycle or single branch of equal marks
+  pub fn bracket(&self) -> Option<String> {
+    graph_name(&self.graph, &[], &[])
+  }
+}
+
+impl<T: Clone> Display for Bracket<T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let name = graph_name(&self.graph, &self.ionic, &self.extension).unwrap_or("[?]".to_string());
+    let plus = if self.rotational { "+" } else { "" };
+    f.write_fmt(format_args!("{}{}", name, plus))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn round_trip(s: &str) -> String {
+    let mut sc = Scanner::new(s);
+    sc.parse_bracket::<f64>().unwrap().to_string()
+  }
+
+  #[test]
+  fn bracket_round_trip() {
+    for s in ["[4,3,3]", "[3^{1,1,1}]", "[5,3]+", "[[3,3,3]]", "[4,3+]", "[3^{2,2,1}]", "[4,3]×[ ]", "[3^[3]]", "[(4,3,3)]", "[5,5/2]"] {
+      assert_eq!(round_trip(s), s);
+    }
+    assert_eq!(round_trip("[3,3,4]"), "[4,3,3]");
+    assert_eq!(round_trip("[4,3^2]"), "[4,3,3]");
+    assert_eq!(round_trip("[4,3,2]"), "[4,3]×[ ]");
+    assert_eq!(round_trip("[3+,4]"), "[4,3+]");
+    assert_eq!(round_trip("[4,3^{1,1}]"), "[4,3^{1,1}]");
+    // the extension belongs to its own factor
+    assert_eq!(round_trip("[[3,3,3]]×[ ]"), "[[3,3,3]]×[ ]");
+    assert_eq!(round_trip("[4,3]×[[3,3]]"), "[4,3]×[[3,3]]");
+  }
+
+  #[test]
+  fn bracket_graph() {
+    // E8 has 8 nodes and 7 edges
+    let mut sc = Scanner::new("[3^{4,2,1}]");
+    let b = sc.parse_bracket::<f64>().unwrap();
+    assert_eq!(b.graph.node_count(), 8);
+    assert_eq!(b.graph.edge_count(), 7);
+    assert!(Scanner::new("[4,3").parse_bracket::<f64>().is_err());
+    assert!(Scanner::new("[4,3]]").parse_bracket::<f64>().is_err());
+  }
+}
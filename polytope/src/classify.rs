@@ -0,0 +1,383 @@
+use std::f64::consts::PI;
+use std::fmt::{Display, Formatter};
+
+use ndarray::Array2;
+use ndarray_linalg::{EigValsh, UPLO};
+use petgraph::graph::NodeIndex;
+
+use crate::invariants::underlying;
+use crate::{components, mark, subgraph, CoxEdge, CoxGraph, CoxGroup};
+
+/// Eigenvalues closer to zero than this are counted as zero
+const EPS: f64 = 1e-9;
+
+/// The Gram (Schläfli) matrix of the mirror normals
+/// G_ii = 1, G_ij = -cos(𝛑/m_ij), with 𝛑q/p as the angle for a rational mark p/q,
+/// so unconnected nodes (m=2) give 0
+pub fn gram<T: Clone>(graph: &CoxGraph<T>) -> Array2<f64> {
+  let n = graph.node_count();
+  let mut g = Array2::<f64>::eye(n);
+  for e in graph.edge_indices() {
+    let (a, b) = graph.edge_endpoints(e).unwrap();
+    let c = -(PI/f64::from(&graph[e])).cos();
+    g[[a.index(), b.index()]] = c;
+    g[[b.index(), a.index()]] = c;
+  }
+  g
+}
+
+/// Signature of a symmetric matrix: (positive, zero, negative) eigenvalues
+pub fn signature(m: &Array2<f64>) -> (usize, usize, usize) {
+  if m.nrows() == 0 {
+    return (0, 0, 0);
+  }
+  let eig = m.eigvalsh(UPLO::Lower).unwrap();
+  let pos = eig.iter().filter(|e| **e > EPS).count();
+  let neg = eig.iter().filter(|e| **e < -EPS).count();
+  (pos, eig.len() - pos - neg, neg)
+}
+
+/// Kind of the space the group acts on, determined by the signature of the Gram matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+  /// Positive definite: a finite group, tiling the sphere
+  Spherical,
+  /// Every component positive definite or positive semidefinite with a one-dimensional kernel: tiling flat space
+  Euclidean,
+  /// Connected, signature (n-1,0,1) and all proper subdiagrams spherical: compact fundamental simplex
+  CompactHyperbolic,
+  /// Connected, signature (n-1,0,1) and all proper subdiagrams spherical or Euclidean: ideal vertices
+  ParacompactHyperbolic,
+  /// Anything else
+  Indefinite,
+}
+
+/// Irreducible components of finite and affine groups
+/// The affine groups have one more node than their index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoxType {
+  A(usize),
+  B(usize),
+  D(usize),
+  E(usize),
+  F4,
+  H(usize),
+  I2(CoxEdge),
+  AffineA(usize),
+  AffineB(usize),
+  AffineC(usize),
+  AffineD(usize),
+  AffineE(usize),
+  AffineF4,
+  AffineG2,
+}
+
+impl Display for CoxType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CoxType::A(n) => f.write_fmt(format_args!("A{}", n)),
+      CoxType::B(n) => f.write_fmt(format_args!("B{}", n)),
+      CoxType::D(n) => f.write_fmt(format_args!("D{}", n)),
+      CoxType::E(n) => f.write_fmt(format_args!("E{}", n)),
+      CoxType::F4 => f.write_str("F4"),
+      CoxType::H(n) => f.write_fmt(format_args!("H{}", n)),
+      CoxType::I2(p) => f.write_fmt(format_args!("I2({})", p)),
+      CoxType::AffineA(n) => f.write_fmt(format_args!("A\u{303}{}", n)),
+      CoxType::AffineB(n) => f.write_fmt(format_args!("B\u{303}{}", n)),
+      CoxType::AffineC(n) => f.write_fmt(format_args!("C\u{303}{}", n)),
+      CoxType::AffineD(n) => f.write_fmt(format_args!("D\u{303}{}", n)),
+      CoxType::AffineE(n) => f.write_fmt(format_args!("E\u{303}{}", n)),
+      CoxType::AffineF4 => f.write_str("F\u{303}4"),
+      CoxType::AffineG2 => f.write_str("G\u{303}2"),
+    }
+  }
+}
+
+/// Result of classifying a diagram: the kind of the whole diagram,
+/// and for each component its nodes, its kind and its type if it is a known finite or affine group
+#[derive(Debug, Clone)]
+pub struct Classification {
+  pub kind: Kind,
+  pub components: Vec<(Vec<usize>, Kind, Option<CoxType>)>,
+}
+
+impl Display for Classification {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let names: Vec<String> = self.components.iter().map(|(_, k, t)| match t {
+      Some(t) => t.to_string(),
+      None => format!("{:?}", k),
+    }).collect();
+    f.write_fmt(format_args!("{:?}: {}", self.kind, names.join("×")))
+  }
+}
+
+/// The branch node of a tree and its arms as (length, marks from the branch node outwards)
+type Arms = (usize, Vec<(usize, Vec<CoxEdge>)>);
+
+/// Shape of a connected tree: the arms of its branch node as (length, marks from the branch node outwards),
+/// shortest first. A chain is reported as a single arm from one of its ends, None for more than one branch node
+fn arms<T: Clone>(graph: &CoxGraph<T>) -> Option<Arms> {
+  let n = graph.node_count();
+  let degree = |i: usize| graph.neighbors(NodeIndex::new(i)).count();
+  if graph.edge_count() != n - 1 {
+    return None;
+  }
+  let branch: Vec<usize> = (0..n).filter(|i| degree(*i) > 2).collect();
+  let center = match branch.len() {
+    0 => (0..n).find(|i| degree(*i) <= 1).unwrap(),
+    1 => branch[0],
+    _ => return None,
+  };
+  let mut out = Vec::new();
+  for next in graph.neighbors(NodeIndex::new(center)) {
+    let mut path = vec![center, next.index()];
+    loop {
+      let last = path[path.len() - 1];
+      match graph.neighbors(NodeIndex::new(last)).map(|x| x.index()).find(|x| !path.contains(x)) {
+        Some(x) => path.push(x),
+        None => break,
+      }
+    }
+    let marks: Vec<CoxEdge> = path.windows(2).map(|w| mark(graph, w[0], w[1])).collect();
+    out.push((marks.len(), marks));
+  }
+  out.sort_by_key(|a| a.0);
+  Some((center, out))
+}
+
+/// Identifies a connected finite or affine diagram by its shape and marks
+fn identify<T: Clone>(graph: &CoxGraph<T>, kind: Kind) -> Option<CoxType> {
+  let n = graph.node_count();
+  let three = CoxEdge::Int(3);
+  let all3 = |ms: &[CoxEdge]| ms.iter().all(|m| *m == three);
+  if n == 1 {
+    return Some(CoxType::A(1));
+  }
+  if n == 2 && kind == Kind::Spherical {
+    return Some(match mark(graph, 0, 1) {
+      CoxEdge::Int(3) => CoxType::A(2),
+      CoxEdge::Int(4) => CoxType::B(2),
+      e => CoxType::I2(e),
+    });
+  }
+  let (_, shape) = match arms(graph) {
+    Some(s) => s,
+    None => {
+      // the only finite or affine diagram with a cycle is Ã(n-1)
+      let cycle = graph.edge_count() == n && (0..n).all(|i| graph.neighbors(NodeIndex::new(i)).count() == 2);
+      let marks: Vec<CoxEdge> = graph.edge_weights().cloned().collect();
+      if cycle && all3(&marks) {
+        return Some(CoxType::AffineA(n - 1));
+      }
+      // D̃n has two branch nodes
+      let branch = (0..n).filter(|i| graph.neighbors(NodeIndex::new(*i)).count() == 3).count();
+      if kind == Kind::Euclidean && graph.edge_count() == n - 1 && branch == 2 && all3(&marks) {
+        return Some(CoxType::AffineD(n - 1));
+      }
+      return None;
+    }
+  };
+  let lens: Vec<usize> = shape.iter().map(|a| a.0).collect();
+  // marks read from the branch node (or chain end) outwards
+  let chain: Vec<CoxEdge> = match shape.len() {
+    1 => shape[0].1.clone(),
+    _ => Vec::new(),
+  };
+  let rev: Vec<CoxEdge> = chain.iter().rev().cloned().collect();
+  let is = |ms: &[CoxEdge], pattern: &[u32]| ms.len() == pattern.len() && ms.iter().zip(pattern).all(|(m, p)| *m == CoxEdge::Int(*p));
+  let chain_is = |pattern: &[u32]| is(&chain, pattern) || is(&rev, pattern);
+  let threes = |k: usize| vec![3u32; k];
+  let with = |first: u32, k: usize, last: Option<u32>| {
+    let mut v = vec![first];
+    v.extend(threes(k));
+    if let Some(l) = last {
+      v.push(l);
+    }
+    v
+  };
+
+  match kind {
+    Kind::Spherical => {
+      if shape.len() == 1 {
+        if all3(&chain) {
+          return Some(CoxType::A(n));
+        }
+        if chain_is(&with(4, n - 2, None)) {
+          return Some(CoxType::B(n));
+        }
+        if chain_is(&[3, 4, 3]) {
+          return Some(CoxType::F4);
+        }
+        if chain_is(&with(5, n - 2, None)) && n <= 4 {
+          return Some(CoxType::H(n));
+        }
+        return None;
+      }
+      if shape.len() == 3 && shape.iter().all(|a| all3(&a.1)) {
+        return match lens.as_slice() {
+          [1, 1, k] => Some(CoxType::D(k + 3)),
+          [1, 2, 2] | [1, 2, 3] | [1, 2, 4] => Some(CoxType::E(n)),
+          _ => None,
+        };
+      }
+      None
+    },
+    Kind::Euclidean => {
+      if shape.len() == 1 {
+        if n >= 3 && chain_is(&with(4, n - 3, Some(4))) {
+          return Some(CoxType::AffineC(n - 1));
+        }
+        if chain_is(&[3, 3, 4, 3]) {
+          return Some(CoxType::AffineF4);
+        }
+        if chain_is(&[6, 3]) {
+          return Some(CoxType::AffineG2);
+        }
+        return None;
+      }
+      let arms3 = shape.iter().filter(|a| all3(&a.1)).count();
+      if shape.len() == 4 && arms3 == 4 && lens == [1, 1, 1, 1] {
+        return Some(CoxType::AffineD(4));
+      }
+      if shape.len() == 3 && arms3 == 3 {
+        return match lens.as_slice() {
+          [2, 2, 2] => Some(CoxType::AffineE(6)),
+          [1, 3, 3] => Some(CoxType::AffineE(7)),
+          [1, 2, 5] => Some(CoxType::AffineE(8)),
+          _ => None,
+        };
+      }
+      // B̃n: a fork of two single nodes, with a 4 at the end of the remaining arm
+      if shape.len() == 3 && arms3 == 2 {
+        let odd = shape.iter().find(|a| !all3(&a.1)).unwrap();
+        let mut pattern = threes(odd.0 - 1);
+        pattern.push(4);
+        if shape.iter().filter(|a| all3(&a.1)).all(|a| a.0 == 1) && is(&odd.1, &pattern) {
+          return Some(CoxType::AffineB(n - 1));
+        }
+      }
+      None
+    },
+    _ => None,
+  }
+}
+
+/// Kind of a connected diagram, from its signature and those of its subdiagrams
+fn connected_kind<T: Clone>(graph: &CoxGraph<T>) -> Kind {
+  let n = graph.node_count();
+  match signature(&gram(graph)) {
+    (_, 0, 0) => Kind::Spherical,
+    (_, 1, 0) => Kind::Euclidean,
+    (_, 0, 1) => {
+      let mut paracompact = false;
+      for i in 0..n {
+        let rest: Vec<usize> = (0..n).filter(|j| *j != i).collect();
+        match diagram_kind(&subgraph(graph, &rest)) {
+          Kind::Spherical => {},
+          Kind::Euclidean => paracompact = true,
+          _ => return Kind::Indefinite,
+        }
+      }
+      if paracompact { Kind::ParacompactHyperbolic } else { Kind::CompactHyperbolic }
+    },
+    _ => Kind::Indefinite,
+  }
+}
+
+/// Kind of a possibly disconnected diagram
+fn diagram_kind<T: Clone>(graph: &CoxGraph<T>) -> Kind {
+  classify(graph).kind
+}
+
+/// Classifies a diagram structurally by the signature of its Gram matrix,
+/// and identifies its irreducible components
+/// e.g. x3o3o5o -> Spherical: H4, x4o3o3o4o -> Euclidean: C̃4, x5o3o3o3o -> CompactHyperbolic
+pub fn classify<T: Clone>(graph: &CoxGraph<T>) -> Classification {
+  let mut comps = Vec::new();
+  for c in components(graph) {
+    let sub = subgraph(graph, &c);
+    let kind = connected_kind(&sub);
+    let t = match kind {
+      Kind::Spherical | Kind::Euclidean => identify(&sub, kind),
+      _ => None,
+    };
+    comps.push((c, kind, t));
+  }
+  let kinds: Vec<Kind> = comps.iter().map(|c| c.1).collect();
+  let kind = if kinds.iter().all(|k| *k == Kind::Spherical) {
+    Kind::Spherical
+  } else if kinds.iter().all(|k| *k == Kind::Spherical || *k == Kind::Euclidean) {
+    Kind::Euclidean
+  } else if kinds.len() == 1 {
+    kinds[0]
+  } else {
+    Kind::Indefinite
+  };
+  Classification { kind, components: comps }
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Classifies the diagram as `classify` does, and gives the spherical components with rational marks
+  /// the finite group they generate, e.g. x5/2o3o -> Spherical: H3
+  pub fn classify(&self) -> Classification {
+    let mut class = classify(&self.graph);
+    for (nodes, kind, t) in class.components.iter_mut() {
+      if *kind == Kind::Spherical && t.is_none() {
+        if let Some([u]) = underlying(&subgraph(&self.graph, nodes)).as_deref() {
+          *t = Some(*u);
+        }
+      }
+    }
+    class
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::parse::Scanner;
+
+  fn class(s: &str) -> String {
+    let mut sc = Scanner::new(s);
+    sc.parse_tope::<f64>().unwrap().classify().to_string()
+  }
+
+  #[test]
+  fn spherical() {
+    assert_eq!(class("x3o3o3o"), "Spherical: A4");
+    assert_eq!(class("o3o3o4x"), "Spherical: B4");
+    assert_eq!(class("x3o3o *b3o"), "Spherical: D4");
+    assert_eq!(class("x3o3o3o3o *c3o"), "Spherical: E6");
+    assert_eq!(class("x3o3o3o *b3o3o3o3o"), "Spherical: E8");
+    assert_eq!(class("x3o4o3o"), "Spherical: F4");
+    assert_eq!(class("x5o3o3o"), "Spherical: H4");
+    assert_eq!(class("x5o x3o"), "Spherical: I2(5)×A2");
+    assert_eq!(class("x6f2o"), "Spherical: I2(6)×A1");
+    // rational marks: the group they generate
+    assert_eq!(class("x5/2o3o"), "Spherical: H3");
+    assert_eq!(class("x5/2o5o"), "Spherical: H3");
+    assert_eq!(class("x3/2o3o"), "Spherical: A3");
+    assert_eq!(class("x5/2o3o3o x5/3o"), "Spherical: H4×I2(5/3)");
+  }
+
+  #[test]
+  fn euclidean() {
+    assert_eq!(class("x4o3o4o"), "Euclidean: C\u{303}3");
+    assert_eq!(class("x3o3o *a"), "Euclidean: A\u{303}2");
+    assert_eq!(class("x6o3o"), "Euclidean: G\u{303}2");
+    assert_eq!(class("x4o3o *b3o"), "Euclidean: B\u{303}3");
+    assert_eq!(class("x3o3o3o4o *b3o"), "Euclidean: B\u{303}5");
+    assert_eq!(class("x3o3o *b3o *b3o"), "Euclidean: D\u{303}4");
+    assert_eq!(class("x3o3o3o *b3o *c3o"), "Euclidean: D\u{303}5");
+    assert_eq!(class("x3o3o3o3o *c3o3o"), "Euclidean: E\u{303}6");
+    assert_eq!(class("x3o3o4o3o"), "Euclidean: F\u{303}4");
+    assert_eq!(class("x4o4o x"), "Euclidean: C\u{303}2×A1");
+  }
+
+  #[test]
+  fn hyperbolic() {
+    assert_eq!(class("x5o3o5o"), "CompactHyperbolic: CompactHyperbolic");
+    assert_eq!(class("x6o3o3o"), "ParacompactHyperbolic: ParacompactHyperbolic");
+    assert_eq!(class("x7o3o"), "CompactHyperbolic: CompactHyperbolic");
+    assert_eq!(class("x3o3o3o3o3o3o3o3o3o *c3o"), "Indefinite: Indefinite");
+  }
+}
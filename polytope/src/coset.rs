@@ -0,0 +1,265 @@
+use petgraph::graph::NodeIndex;
+
+use crate::{mark, CoxEdge, CoxGraph, CoxGroup};
+
+/// Enumeration gives up after defining this many cosets, which means the index is infinite or too large
+pub const MAX_COSETS: usize = 2_000_000;
+
+/// A relator or subgroup generator: a word in the generators s_0, s_1, ..., which are all involutions
+pub type Word = Vec<usize>;
+
+/// Result of a coset enumeration: table[c][i] is the coset c·s_i
+/// Coset 0 is the subgroup itself, the other cosets are numbered in order of definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CosetTable {
+  pub table: Vec<Vec<usize>>,
+}
+
+impl CosetTable {
+  /// Number of cosets, the index of the subgroup
+  pub fn len(&self) -> usize {
+    self.table.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.table.is_empty()
+  }
+
+  /// The coset c·w
+  pub fn act(&self, c: usize, word: &[usize]) -> usize {
+    word.iter().fold(c, |c, s| self.table[c][*s])
+  }
+}
+
+/// Coxeter relators (s_i s_j)^m of the diagram, one for every pair of nodes
+/// s_i² = 1 holds because all generators are treated as involutions
+/// For a mark p/q only (s_i s_j)^p is used, so the presented group can be larger than the group the mirrors generate
+pub fn relators<T: Clone>(graph: &CoxGraph<T>) -> Vec<Word> {
+  let n = graph.node_count();
+  let mut rels = Vec::new();
+  for i in 0..n {
+    for j in i+1..n {
+      let m = match mark(graph, i, j) {
+        CoxEdge::Int(m) => m,
+        CoxEdge::Frac(f) => *f.numer().unwrap(),
+      };
+      rels.push([i, j].repeat(m as usize));
+    }
+  }
+  rels
+}
+
+/// State of a running Todd–Coxeter enumeration (HLT strategy with coincidence processing)
+struct Enumeration {
+  gens: usize,
+  table: Vec<Vec<Option<usize>>>,
+  /// p[c] == c for live cosets, otherwise a smaller coset that c was found to be equal to
+  p: Vec<usize>,
+  limit: usize,
+  overflow: bool,
+}
+
+impl Enumeration {
+  fn define(&mut self, c: usize, s: usize) {
+    if self.table.len() >= self.limit {
+      self.overflow = true;
+      return;
+    }
+    let d = self.table.len();
+    self.table.push(vec![None; self.gens]);
+    self.p.push(d);
+    self.table[c][s] = Some(d);
+    self.table[d][s] = Some(c);
+  }
+
+  fn rep(&mut self, c: usize) -> usize {
+    let mut r = c;
+    while self.p[r] != r {
+      r = self.p[r];
+    }
+    let mut c = c;
+    while self.p[c] != r {
+      let next = self.p[c];
+      self.p[c] = r;
+      c = next;
+    }
+    r
+  }
+
+  fn merge(&mut self, a: usize, b: usize, queue: &mut Vec<usize>) {
+    let (a, b) = (self.rep(a), self.rep(b));
+    if a != b {
+      let (lo, hi) = (a.min(b), a.max(b));
+      self.p[hi] = lo;
+      queue.push(hi);
+    }
+  }
+
+  /// Makes cosets a and b equal, and everything that follows from that
+  fn coincidence(&mut self, a: usize, b: usize) {
+    let mut queue = Vec::new();
+    self.merge(a, b, &mut queue);
+    let mut i = 0;
+    while i < queue.len() {
+      let e = queue[i];
+      i += 1;
+      for s in 0..self.gens {
+        if let Some(f) = self.table[e][s] {
+          self.table[f][s] = None;
+          let (e1, f1) = (self.rep(e), self.rep(f));
+          if let Some(g) = self.table[e1][s] {
+            self.merge(f1, g, &mut queue);
+          } else if let Some(g) = self.table[f1][s] {
+            self.merge(e1, g, &mut queue);
+          } else {
+            self.table[e1][s] = Some(f1);
+            self.table[f1][s] = Some(e1);
+          }
+        }
+      }
+    }
+  }
+
+  /// Traces the word from both ends starting at c, defining new cosets until it closes
+  fn scan_and_fill(&mut self, c: usize, word: &[usize]) {
+    if word.is_empty() {
+      return;
+    }
+    let (mut f, mut b) = (c, c);
+    let (mut i, mut j) = (0, word.len() as isize - 1);
+    loop {
+      while i as isize <= j {
+        match self.table[f][word[i]] {
+          Some(d) => { f = d; i += 1; },
+          None => break,
+        }
+      }
+      if i as isize > j {
+        if f != b {
+          self.coincidence(f, b);
+        }
+        return;
+      }
+      while j >= i as isize {
+        match self.table[b][word[j as usize]] {
+          Some(d) => { b = d; j -= 1; },
+          None => break,
+        }
+      }
+      if j < i as isize {
+        self.coincidence(f, b);
+        return;
+      }
+      if i as isize == j {
+        // deduction: the gap is a single generator
+        self.table[f][word[i]] = Some(b);
+        self.table[b][word[i]] = Some(f);
+        return;
+      }
+      self.define(f, word[i]);
+      if self.overflow {
+        return;
+      }
+    }
+  }
+}
+
+/// Enumerates the cosets of the subgroup generated by the words `subgroup` in the group with `gens` involutive generators
+/// and the given relators, None if more than `limit` cosets are needed
+pub fn enumerate(gens: usize, relators: &[Word], subgroup: &[Word], limit: usize) -> Option<CosetTable> {
+  let mut e = Enumeration { gens, table: vec![vec![None; gens]], p: vec![0], limit, overflow: false };
+  for w in subgroup {
+    e.scan_and_fill(0, w);
+  }
+  let mut c = 0;
+  while c < e.table.len() {
+    for r in relators {
+      if e.p[c] != c {
+        break;
+      }
+      e.scan_and_fill(c, r);
+    }
+    for s in 0..gens {
+      if e.p[c] == c && e.table[c][s].is_none() {
+        e.define(c, s);
+      }
+    }
+    if e.overflow {
+      return None;
+    }
+    c += 1;
+  }
+  // renumber the live cosets
+  let live: Vec<usize> = (0..e.table.len()).filter(|c| e.p[*c] == *c).collect();
+  let mut index = vec![0; e.table.len()];
+  for (k, c) in live.iter().enumerate() {
+    index[*c] = k;
+  }
+  let table = live.iter().map(|c| {
+    e.table[*c].iter().map(|d| index[d.unwrap()]).collect()
+  }).collect();
+  Some(CosetTable { table })
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Coset table of the subgroup generated by the nodes `subgroup` (indices into the graph),
+  /// in the group presented by the diagram with the additional relators `extra`
+  pub fn cosets(&self, subgroup: &[usize], extra: &[Word]) -> Option<CosetTable> {
+    let mut rels = relators(&self.graph);
+    rels.extend(extra.iter().cloned());
+    let sub: Vec<Word> = subgroup.iter().map(|i| vec![*i]).collect();
+    enumerate(self.graph.node_count(), &rels, &sub, MAX_COSETS)
+  }
+
+  /// Number of vertices of the Wythoffian polytope, |W|/|W_J| where J are the unringed nodes
+  /// None if the group is infinite or too large
+  pub fn vertex_count(&self) -> Option<usize> {
+    let unringed: Vec<usize> = self.graph.node_indices()
+      .filter(|i| self.graph[*i].repr == "o")
+      .map(NodeIndex::index)
+      .collect();
+    self.cosets(&unringed, &[]).map(|t| t.len())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::Scanner;
+
+  #[test]
+  fn group_orders() {
+    for (s, order) in [("x3o3o", 24), ("x5o3o", 120), ("x4o3o3o", 384), ("x3o4o3o", 1152), ("x3o3o *b3o", 192), ("x5o x3o", 60)] {
+      let mut sc = Scanner::new(s);
+      let g = sc.parse_tope::<f64>().unwrap();
+      let t = g.cosets(&[], &[]).unwrap();
+      assert_eq!(t.len(), order, "{}", s);
+      // every generator is an involution and the relators hold on every coset
+      for c in 0..t.len() {
+        for r in relators(&g.graph) {
+          assert_eq!(t.act(c, &r), c);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn vertex_counts() {
+    for (s, n) in [("x3o3o5o", 120), ("x5o3o3o", 600), ("x3x3o", 12), ("x4o3x", 24), ("o5x3o", 30)] {
+      let mut sc = Scanner::new(s);
+      assert_eq!(sc.parse_tope::<f64>().unwrap().vertex_count(), Some(n), "{}", s);
+    }
+    // the cubic honeycomb has infinitely many vertices
+    let mut sc = Scanner::new("x4o3o4o");
+    let g = sc.parse_tope::<f64>().unwrap();
+    assert_eq!(enumerate(4, &relators(&g.graph), &[vec![1], vec![2], vec![3]], 10_000), None);
+  }
+
+  #[test]
+  fn extra_relators() {
+    // s_0 = s_1 collapses the hexagonal group to a group of order 2
+    let mut sc = Scanner::new("x6o");
+    let g = sc.parse_tope::<f64>().unwrap();
+    assert_eq!(g.cosets(&[], &[vec![0, 1]]).unwrap().len(), 2);
+  }
+}
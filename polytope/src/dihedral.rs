@@ -0,0 +1,346 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use exact::Expr;
+use fraction::GenericFraction;
+use ndarray::Array1;
+use rusqlite::{params, Connection};
+
+use crate::dual::hyperplane;
+use crate::polydb::{self, fail};
+use crate::product::Polytope;
+use crate::radius::{cartan_inverse, descend, exact_values, radical};
+use crate::roots::{gcd, Cyc, CycFrac};
+use crate::{is_face, subgraph, CoxEdge, CoxGroup, Failed};
+
+/// Largest denominator q tried when recognizing an angle as p𝛑/q
+const MAX_DENOM: u32 = 60;
+
+/// Tolerance for matching measured angles and distances to the exact ones
+const EPS: f64 = 1e-9;
+
+/// The dihedral angle at one kind of ridge of a Wythoffian polytope, see `CoxGroup::dihedrals`
+#[derive(Debug, Clone)]
+pub struct Dihedral {
+  /// diagram of the ridge, e.g. x for the edges of the cube
+  pub ridge: String,
+  /// diagrams of the two facets that meet at the ridge
+  pub facets: (String, String),
+  /// the cosine of the angle
+  pub cos: Expr,
+  /// the angle in radians
+  pub angle: f64,
+  /// the angle as p𝛑/q, if it is a rational multiple of 𝛑
+  pub pi: Option<(u32, u32)>,
+}
+
+impl Dihedral {
+  /// The exact angle, e.g. 𝛑/2, 3𝛑/5 or arccos(-√5/5)
+  pub fn exact(&self) -> String {
+    match self.pi {
+      Some((p, q)) => {
+        let p = if p == 1 { String::new() } else { p.to_string() };
+        if q == 1 { format!("{}𝛑", p) } else { format!("{}𝛑/{}", p, q) }
+      },
+      None => format!("arccos({})", self.cos),
+    }
+  }
+}
+
+impl fmt::Display for Dihedral {
+  /// e.g. x5o, x5o at x: arccos(-√5/5) = 2.0344439357957027
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}, {} at {}: {} = {}", self.facets.0, self.facets.1, self.ridge, self.exact(), self.angle)
+  }
+}
+
+/// p and q with angle = p𝛑/q, checked exactly against cos² using 2cos(p𝛑/q) = C_p(2cos(𝛑/q))
+fn rational_angle(angle: f64, square: &CycFrac, negative: bool) -> Option<(u32, u32)> {
+  let (p, q) = (1..=MAX_DENOM).flat_map(|q| (1..q).map(move |p| (p, q)))
+    .find(|(p, q)| (angle - PI*(*p as f64)/(*q as f64)).abs() < 1e-9)?;
+  let t = Cyc::from_edge(&CoxEdge::Frac(GenericFraction::new(q, p)));
+  let m = square.num.m();
+  let lcm = q/gcd(q as i64, m as i64) as u32*m;
+  let t = CycFrac { num: t.lift(lcm), den: 1 };
+  let four = &square.lift(lcm)*&CycFrac::int(4);
+  if (&(&t*&t) - &four).is_zero() && (t.to_f64() < 0f64) == negative { Some((p, q)) } else { None }
+}
+
+impl Polytope {
+  /// The dihedral angle at every ridge, in the order of faces[rank-2]: the interior angle between the two facets at the ridge,
+  /// from their outward normals a, b as arccos(-a·b/|a||b|)
+  /// None where the ridge is not in exactly two facets, or a facet goes through the centre, like the hexagons of the octahemioctahedron
+  /// For polyhedra these are the angles at the edges, for polygons the angles at the vertices
+  pub fn dihedrals(&self) -> Vec<Option<f64>> {
+    let rank = self.rank();
+    if rank < 2 {
+      return Vec::new();
+    }
+    let hasse = self.hasse();
+    let normals: Vec<Option<_>> = hasse.faces[rank-1].iter().map(|f| hyperplane(&self.verts, f)).collect();
+    hasse.sup[rank-2].iter().map(|facets| {
+      match facets.as_slice() {
+        [a, b] => {
+          let (a, b) = (normals[*a].as_ref()?, normals[*b].as_ref()?);
+          Some((-a.dot(b)/(a.dot(a)*b.dot(b)).sqrt()).clamp(-1f64, 1f64).acos())
+        },
+        _ => None,
+      }
+    }).collect()
+  }
+}
+
+impl CoxGroup<f64> {
+  /// Exact dihedral angles of the Wythoffian polytope of a finite diagram, one for every kind of ridge, from the Gram matrix
+  /// A ridge is the face of all nodes but i and j, it lies in the facets of all nodes but j and of all nodes but i, if these are faces
+  /// The facet of all nodes but j lies in the hyperplane ω_j·x = ω_j·s for the seed s, where ω are the basis vectors,
+  /// so with A as in `cartan_inverse` the angle between the facets has cos = -σ_iσ_j(A⁻¹)_ij/√((A⁻¹)_ii(A⁻¹)_jj),
+  /// σ the signs of ω·s. If only one of them is a face, j is an unringed node that commutes with the ridge,
+  /// the other facet is its mirror image in j, and cos = 1/(A⁻¹)_jj - 1
+  /// e.g. x4o3o -> x4o, x4o at x: 𝛑/2; x3o5o -> x3o, x3o at x: arccos(-√5/3)
+  pub fn dihedrals(&self) -> Result<Vec<Dihedral>, Failed> {
+    let graph = &self.graph;
+    let (values, m) = exact_values(graph)?;
+    let n = graph.node_count();
+    let inv = cartan_inverse(graph, m);
+    // ω_k·s = (A⁻¹v)_k/2
+    let sides: Vec<CycFrac> = (0..n).map(|k| (0..n).fold(CycFrac::int(0), |acc, l| {
+      &acc + &(&inv[k][l]*&CycFrac { num: values[l].lift(m), den: 1 })
+    })).collect();
+    let is_face = |nodes: &[usize]| is_face(graph, nodes, |i| !values[i].is_zero());
+    let dynkin = |nodes: &[usize]| CoxGroup { graph: subgraph(graph, nodes), polys: Vec::new() }.dynkin();
+    let without = |skip: &[usize]| (0..n).filter(|k| !skip.contains(k)).collect::<Vec<_>>();
+    let mut dihedrals = Vec::new();
+    for i in 0..n {
+      for j in i+1..n {
+        let ridge = without(&[i, j]);
+        if !is_face(&ridge) {
+          continue;
+        }
+        let (fi, fj) = (without(&[j]), without(&[i]));
+        let (facets, square, negative) = match (is_face(&fi), is_face(&fj)) {
+          (true, true) => {
+            if sides[i].is_zero() || sides[j].is_zero() {
+              println!("A facet at the ridge {} goes through the centre", dynkin(&ridge));
+              continue;
+            }
+            let square = &(&inv[i][j]*&inv[i][j])*&(&inv[i][i]*&inv[j][j]).inverse().unwrap();
+            let sign = (sides[i].to_f64() < 0f64) == (sides[j].to_f64() < 0f64);
+            ((dynkin(&fi), dynkin(&fj)), square, sign == (inv[i][j].to_f64() > 0f64))
+          },
+          (true, false) | (false, true) => {
+            let (k, facet) = if is_face(&fi) { (j, dynkin(&fi)) } else { (i, dynkin(&fj)) };
+            let c = &inv[k][k].inverse().unwrap() - &CycFrac::int(1);
+            ((facet.clone(), facet), &c*&c, c.to_f64() < 0f64)
+          },
+          (false, false) => continue,
+        };
+        let square = descend(square);
+        let negative = negative && !square.is_zero();
+        let value = square.to_f64().max(0f64).sqrt();
+        let angle = (if negative { -value } else { value }).clamp(-1f64, 1f64).acos();
+        let pi = rational_angle(angle, &square, negative);
+        let cos = if negative { -radical(square) } else { radical(square) };
+        dihedrals.push(Dihedral { ridge: dynkin(&ridge), facets, cos, angle, pi });
+      }
+    }
+    Ok(dihedrals)
+  }
+
+  /// Writes the polyhedron of the diagram to polydb.sqlite3 as the polyhedron `poly`, which must have no rows yet, e.g. a new id from `polydb::polyhedron`
+  /// The rows of a polyhedron refer to each other by index, so they are only written all together, in one transaction:
+  /// - Vertex: the coordinates, at the scale of the diagram, and the number of edges at the vertex
+  /// - Face: the number of vertices and the plane n·x + w = 0, with n the outward normal scaled so its first nonzero coordinate is ±1, as the other rows have it
+  /// - Edge: the two faces and the dihedral angle between them in degrees
+  /// - Polygon: the vertices of every face in order, counterclockwise seen from outside
+  /// - Lattice: the vertices of every edge and face and the edges of every face
+  /// - Deg2Edge: the numbers of vertices of the two faces at every edge, the smaller first
+  ///
+  /// The exact distance of every face from the centre and the exact dihedral angles go in the text columns Face.dist_exact and
+  /// Edge.dihedral_exact, which are added if needed, e.g. 1/2 and 𝛑/2 for the cube; they are NULL if the node values are not recognized
+  pub fn save_polyhedron(&self, conn: &Connection, poly: i64) -> Result<(), Failed> {
+    let p = self.polytope()?;
+    if p.rank() != 3 || p.verts[0].len() != 3 {
+      println!("Only polyhedra in three dimensions fit the tables of the database");
+      return Err(Failed);
+    }
+    let count = |table: &str| -> Result<i64, Failed> {
+      conn.query_row(&format!("SELECT COUNT(*) FROM {} WHERE {}=?1", table, if table == "Polyhedron" { "id" } else { "poly" }), params![poly], |row| row.get(0))
+        .map_err(fail)
+    };
+    if count("Polyhedron")? == 0 {
+      println!("There is no polyhedron {} in the database", poly);
+      return Err(Failed);
+    }
+    if count("Vertex")? + count("Face")? + count("Edge")? > 0 {
+      println!("The polyhedron {} is already in the database", poly);
+      return Err(Failed);
+    }
+    let hasse = p.hasse();
+    let mut planes = Vec::new();
+    for face in &hasse.faces[2] {
+      match hyperplane(&p.verts, face) {
+        Some(a) => planes.push(a),
+        None => {
+          println!("The face {:?} is not flat or goes through the centre", face);
+          return Err(Failed);
+        }
+      }
+    }
+    let exact = self.dihedrals().ok();
+    let distances: Option<Vec<Expr>> = self.radii().ok().map(|r| r.facets.into_iter().map(|(_, d)| d).collect());
+    let tx = conn.unchecked_transaction().map_err(fail)?;
+    polydb::column(&tx, "Face", "dist_exact VARCHAR")?;
+    polydb::column(&tx, "Edge", "dihedral_exact VARCHAR")?;
+    for (id, v) in p.verts.iter().enumerate() {
+      tx.execute("INSERT INTO Vertex(poly, id, degree, x, y, z) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![poly, id, hasse.sup[0][id].len(), v[0], v[1], v[2]]).map_err(fail)?;
+    }
+    for (id, (face, a)) in hasse.faces[2].iter().zip(&planes).enumerate() {
+      // the face is at a·x = 1, so at distance 1/|a|
+      let first = a.iter().find(|x| x.abs() > EPS).unwrap().abs();
+      let dist = 1f64/a.dot(a).sqrt();
+      let dist_exact = distances.as_ref().and_then(|ds| ds.iter().find(|d| (d.to_f64() - dist).abs() < EPS)).map(|d| d.to_string());
+      tx.execute("INSERT INTO Face(poly, id, degree, norm_x, norm_y, norm_z, norm_w, dist_exact) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![poly, id, face.len(), a[0]/first, a[1]/first, a[2]/first, -1f64/first, dist_exact]).map_err(fail)?;
+      for (idx, v) in cycle(&p, &hasse.faces[1], &hasse.sub[2][id], a).iter().enumerate() {
+        tx.execute("INSERT INTO Polygon(poly, face, vertex, idx) VALUES (?1, ?2, ?3, ?4)", params![poly, id, v, idx]).map_err(fail)?;
+      }
+      for v in face {
+        tx.execute("INSERT INTO Lattice(poly, dim1, id1, dim2, id2) VALUES (?1, 0, ?2, 2, ?3)", params![poly, v, id]).map_err(fail)?;
+      }
+    }
+    for (id, (faces, angle)) in hasse.sup[1].iter().zip(p.dihedrals()).enumerate() {
+      let (f1, f2) = match faces.as_slice() {
+        [a, b] => (*a, *b),
+        _ => {
+          println!("The edge {:?} is not in two faces", hasse.faces[1][id]);
+          return Err(Failed);
+        }
+      };
+      let angle_exact = angle.and_then(|a| exact.as_ref()?.iter().find(|d| (d.angle - a).abs() < EPS)).map(|d| d.exact());
+      tx.execute("INSERT INTO Edge(poly, id, dihedral, face1, face2, dihedral_exact) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![poly, id, angle.map(|a| a.to_degrees()), f1, f2, angle_exact]).map_err(fail)?;
+      for v in &hasse.faces[1][id] {
+        tx.execute("INSERT INTO Lattice(poly, dim1, id1, dim2, id2) VALUES (?1, 0, ?2, 1, ?3)", params![poly, v, id]).map_err(fail)?;
+      }
+      for f in [f1, f2] {
+        tx.execute("INSERT INTO Lattice(poly, dim1, id1, dim2, id2) VALUES (?1, 1, ?2, 2, ?3)", params![poly, id, f]).map_err(fail)?;
+      }
+      let (d1, d2) = (hasse.faces[2][f1].len(), hasse.faces[2][f2].len());
+      tx.execute("INSERT INTO Deg2Edge(deg1, deg2, poly, edge) VALUES (?1, ?2, ?3, ?4)", params![d1.min(d2), d1.max(d2), poly, id]).map_err(fail)?;
+    }
+    tx.commit().map_err(fail)
+  }
+}
+
+/// The vertices of a polygon in the order of its edges, counterclockwise seen from the side that the normal a points to
+fn cycle(p: &Polytope, edges: &[Vec<usize>], face: &[usize], a: &Array1<f64>) -> Vec<usize> {
+  let mut order = vec![edges[face[0]][0]];
+  let mut last = face[0];
+  while order.len() < face.len() {
+    let v = order[order.len() - 1];
+    let next = face.iter().find(|e| **e != last && edges[**e].contains(&v)).unwrap();
+    order.push(*edges[*next].iter().find(|w| **w != v).unwrap());
+    last = *next;
+  }
+  // the sum of v_i × v_(i+1) is twice the area vector, counted with the density for star polygons
+  let area = (0..order.len()).fold(0f64, |acc, i| {
+    let (v, w) = (&p.verts[order[i]], &p.verts[order[(i + 1) % order.len()]]);
+    acc + a[0]*(v[1]*w[2] - v[2]*w[1]) + a[1]*(v[2]*w[0] - v[0]*w[2]) + a[2]*(v[0]*w[1] - v[1]*w[0])
+  });
+  if area < 0f64 {
+    order.reverse();
+  }
+  order
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  fn exact(s: &str) -> Vec<String> {
+    group(s).dihedrals().unwrap().iter().map(|d| d.exact()).collect()
+  }
+
+  #[test]
+  fn exact_angles() {
+    assert_eq!(group("x4o3o").dihedrals().unwrap()[0].to_string(), format!("x4o, x4o at x: 𝛑/2 = {}", PI/2f64));
+    for (s, a) in [("x3o3o", "arccos(1/3)"), ("x3o4o", "arccos(-1/3)"), ("x5o3o", "arccos(-√5/5)"), ("x3o5o", "arccos(-√5/3)"),
+                   ("x5o", "3𝛑/5"), ("x5/2o", "𝛑/5"), ("x4o3o3o", "𝛑/2"), ("x3o4o3o", "2𝛑/3"), ("x5o5/2o", "arccos(√5/5)")] {
+      assert_eq!(exact(s), vec![a], "{}", s);
+    }
+    // truncated cube: octagons meet at arccos(0), octagons and triangles at arccos(-√3/3)
+    let mut tic = exact("x4x3o");
+    tic.sort();
+    assert_eq!(tic, vec!["arccos(-√3/3)", "𝛑/2"]);
+    // triangular prism: squares at 𝛑/3, squares and triangles at 𝛑/2
+    let mut trip = exact("x3o x");
+    trip.sort();
+    assert_eq!(trip, vec!["𝛑/2", "𝛑/3"]);
+  }
+
+  #[test]
+  fn save_cube() {
+    let conn = polydb::memory().unwrap();
+    let id = polydb::polyhedron(&conn, "x4o3o", "cube").unwrap();
+    let cube = group("x4o3o");
+    cube.save_polyhedron(&conn, id).unwrap();
+    assert!(cube.save_polyhedron(&conn, id).is_err());
+    let edges: Vec<(f64, String)> = conn.prepare("SELECT dihedral, dihedral_exact FROM Edge WHERE poly=?1").unwrap()
+      .query_map([id], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|e| e.unwrap()).collect();
+    assert_eq!(edges.len(), 12);
+    assert!(edges.iter().all(|(a, e)| (a - 90f64).abs() < 1e-9 && e == "𝛑/2"));
+    let faces: Vec<(usize, [f64; 4], String)> = conn.prepare("SELECT degree, norm_x, norm_y, norm_z, norm_w, dist_exact FROM Face").unwrap()
+      .query_map([], |row| Ok((row.get(0)?, [row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?], row.get(5)?))).unwrap()
+      .map(|f| f.unwrap()).collect();
+    assert_eq!(faces.len(), 6);
+    for (degree, [x, y, z, w], exact) in faces {
+      assert_eq!((degree, exact.as_str()), (4, "1/2"));
+      assert!((x*x + y*y + z*z - 1f64).abs() < 1e-9 && (w + 0.5).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn save_to_polydb() {
+    // a copy of the shipped database, whose cube has edges of length 2
+    let path = std::env::temp_dir().join(format!("polydb-{}.sqlite3", std::process::id()));
+    std::fs::copy(concat!(env!("CARGO_MANIFEST_DIR"), "/../polydb.sqlite3"), &path).unwrap();
+    let conn = polydb::open(path.to_str().unwrap()).unwrap();
+    let rows = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+    let before = rows("SELECT COUNT(*) FROM Face") + rows("SELECT COUNT(*) FROM Polygon") + rows("SELECT COUNT(*) FROM Lattice");
+    let cube = polydb::polyhedron(&conn, "C100", "cube").unwrap();
+    assert!(group("x4o3o").save_polyhedron(&conn, cube).is_err());
+    assert_eq!(rows("SELECT COUNT(*) FROM Face WHERE poly=5 AND norm_w=-1.0"), 6);
+    // the great dodecahedron is not in it yet
+    let gad = polydb::polyhedron(&conn, "x5/2o5o", "great dodecahedron").unwrap();
+    group("x5/2o5o").save_polyhedron(&conn, gad).unwrap();
+    let after = rows("SELECT COUNT(*) FROM Face") + rows("SELECT COUNT(*) FROM Polygon") + rows("SELECT COUNT(*) FROM Lattice");
+    assert_eq!(after - before, 12 + 60 + 60 + 60 + 60);
+    assert_eq!(rows(&format!("SELECT COUNT(*) FROM Deg2Edge WHERE poly={} AND deg1=5 AND deg2=5", gad)), 30);
+    // the faces of every edge have both its vertices, and every vertex of a polygon lies in the plane of its face
+    assert_eq!(rows(&format!("SELECT COUNT(*) FROM Edge e JOIN Lattice l ON l.poly=e.poly AND l.dim1=0 AND l.dim2=1 AND l.id2=e.id
+      JOIN Polygon p ON p.poly=e.poly AND p.vertex=l.id1 AND p.face IN (e.face1, e.face2) WHERE e.poly={}", gad)), 30*4);
+    let off: f64 = conn.query_row(&format!("SELECT MAX(ABS(f.norm_x*v.x + f.norm_y*v.y + f.norm_z*v.z + f.norm_w)) FROM Polygon p
+      JOIN Face f ON f.poly=p.poly AND f.id=p.face JOIN Vertex v ON v.poly=p.poly AND v.id=p.vertex WHERE p.poly={}", gad), [], |row| row.get(0)).unwrap();
+    assert!(off < 1e-9);
+    let angle: String = conn.query_row(&format!("SELECT DISTINCT dihedral_exact FROM Edge WHERE poly={}", gad), [], |row| row.get(0)).unwrap();
+    assert_eq!(angle, group("x5/2o5o").dihedrals().unwrap()[0].exact());
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn against_vertices() {
+    for s in ["x4x3o", "x3o5x", "x5/2o5o", "o3x5/2o", "x5/3o3x", "x3x3o *a5/2*c", "x3o3o5o", "o3x3o4o", "x3o x4o", "x5/2o3o3o", "f3x4o"] {
+      let g = group(s);
+      let exact: Vec<f64> = g.dihedrals().unwrap().iter().map(|d| d.angle).collect();
+      let floats = g.polytope().unwrap().dihedrals();
+      assert!(!floats.is_empty());
+      for a in floats {
+        let a = a.unwrap();
+        assert!(exact.iter().any(|b| (a - b).abs() < 1e-9), "{} {} {:?}", s, a, exact);
+      }
+    }
+  }
+}
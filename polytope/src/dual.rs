@@ -0,0 +1,219 @@
+use std::fmt;
+
+use exact::Expr;
+use ndarray::{Array1, Array2};
+use ndarray_linalg::Solve;
+use rusqlite::Connection;
+
+use crate::orbit::PointSet;
+use crate::polydb;
+use crate::product::Polytope;
+use crate::radius::radical;
+use crate::{CoxGroup, Failed};
+
+/// Tolerance for facets through the centre and for edge midpoints on one sphere
+const EPS: f64 = 1e-9;
+
+/// A dual polytope, recorded with the diagram of the polytope it is the dual of
+#[derive(Debug, Clone)]
+pub struct Dual {
+  /// Dynkin string of the original, e.g. x4o3o for the octahedron as the dual of the cube
+  pub dynkin: String,
+  /// the midradius of the original it was reciprocated about, None if the node values have no exact form
+  pub midradius: Option<Expr>,
+  /// distance of the vertices of the dual from the centre, midradius²/(distance of the facet), for every kind of facet
+  /// of the original with its diagram, e.g. x4o at 1 for the octahedron; empty without an exact midradius
+  pub vertex_radii: Vec<(String, Expr)>,
+  pub polytope: Polytope,
+}
+
+impl Dual {
+  /// Adds the dual to the `Polyhedron` table of polydb.sqlite3 with the dual flag set, e.g. as "dual x4o3o", and gives its id
+  pub fn save(&self, conn: &Connection) -> Result<i64, Failed> {
+    polydb::dual(conn, &self.dynkin, &format!("dual {}", self.dynkin))
+  }
+}
+
+impl fmt::Display for Dual {
+  /// e.g. dual x4o3o: 6 vertices, [6, 12, 8, 1] faces
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let counts: Vec<usize> = self.polytope.faces.iter().map(|f| f.len()).collect();
+    write!(f, "dual {}: {} vertices, {:?} faces", self.dynkin, self.polytope.verts.len(), counts)
+  }
+}
+
+/// The vector a with a·v = 1 for all vertices v of the face, so the face lies in the hyperplane a·x = 1
+/// None if the hyperplane goes through the origin, or if the vertices do not lie in one hyperplane
+pub fn hyperplane(verts: &[Array1<f64>], face: &[usize]) -> Option<Array1<f64>> {
+  let dims = verts[0].len();
+  let mut m = Array2::<f64>::zeros((face.len(), dims));
+  for (i, v) in face.iter().enumerate() {
+    m.row_mut(i).assign(&verts[*v]);
+  }
+  // least squares, exact when the face is flat and misses the origin
+  let a = m.t().dot(&m).solve(&m.t().dot(&Array1::<f64>::ones(face.len()))).ok()?;
+  if m.dot(&a).iter().any(|x| (x - 1f64).abs() > EPS) {
+    return None;
+  }
+  Some(a)
+}
+
+impl Polytope {
+  /// Radius of the sphere that touches all edges, if there is one
+  /// The edges of uniform polytopes touch it at their midpoints, those of their duals need not
+  /// e.g. √2/2 for the cube with unit edges
+  pub fn midradius(&self) -> Option<f64> {
+    let radii: Vec<f64> = self.faces.get(1)?.iter().map(|e| {
+      // the point of the line through the edge closest to the centre
+      let (a, d) = (&self.verts[e[0]], &self.verts[e[1]] - &self.verts[e[0]]);
+      let p = a - &(&d*(a.dot(&d)/d.dot(&d)));
+      p.dot(&p).sqrt()
+    }).collect();
+    let r = *radii.first()?;
+    if radii.iter().all(|s| (s - r).abs() < EPS) { Some(r) } else { None }
+  }
+
+  /// Reciprocation about the sphere of the given radius around the origin
+  /// Every facet in the hyperplane a·x = 1 becomes the vertex radius²·a, and every k-face becomes the (n-1-k)-face
+  /// spanned by the new vertices of the facets that contain it, so the face lattice is turned upside down
+  /// Works for star polytopes as well, as long as no facet lies in a hyperplane through the centre
+  /// (like the squares of the tetrahemihexahedron) and no two facets lie in the same hyperplane
+  pub fn reciprocal(&self, radius: f64) -> Result<Polytope, Failed> {
+    let rank = self.rank();
+    if rank < 2 || self.verts[0].len() != rank {
+      println!("Only polytopes of rank 2 and up that fill their space can be reciprocated");
+      return Err(Failed);
+    }
+    let mut verts = PointSet::default();
+    for facet in &self.faces[rank - 1] {
+      let a = match hyperplane(&self.verts, facet) {
+        Some(a) => a,
+        None => {
+          println!("The facet {:?} is not flat or goes through the centre", facet);
+          return Err(Failed);
+        }
+      };
+      if !verts.insert(a*radius*radius).1 {
+        println!("Two facets lie in the same hyperplane");
+        return Err(Failed);
+      }
+    }
+    let hasse = self.hasse();
+    let mut faces: Vec<Vec<Vec<usize>>> = (0..rank).map(|k| {
+      (0..self.faces[rank - 1 - k].len()).map(|i| hasse.incident(rank - 1 - k, i, rank - 1)).collect()
+    }).collect();
+    faces.push(vec![(0..verts.len()).collect()]);
+    Ok(Polytope { verts: verts.points, faces })
+  }
+
+  /// The dual: the reciprocal about the midsphere, so the edges of the dual cross those of the original where they touch it
+  /// e.g. cube -> octahedron, great dodecahedron -> small stellated dodecahedron, tesseract -> 16-cell
+  /// Fails if there is no midsphere, i.e. the edges are not all at the same distance from the centre
+  pub fn dual(&self) -> Result<Polytope, Failed> {
+    match self.midradius() {
+      Some(r) if r > EPS => self.reciprocal(r),
+      _ => {
+        println!("The edges do not all touch one sphere around the centre");
+        Err(Failed)
+      }
+    }
+  }
+}
+
+impl CoxGroup<f64> {
+  /// The dual of the polytope of the diagram, see `Polytope::dual`
+  /// When the node values are recognized, the reciprocation is about the exact midradius of `CoxGroup::radii`
+  /// instead of the one measured from the vertices; the edges of all ringed nodes must then be at that distance
+  /// The vertex of the dual for a facet at distance r is its centre scaled by midradius²/r², so it lies at midradius²/r
+  pub fn dual(&self) -> Result<Dual, Failed> {
+    let polytope = self.polytope()?;
+    let squares = self.squared_radii().ok().and_then(|r| {
+      let (_, first) = r.midradii.first()?;
+      if r.midradii.iter().all(|(_, m)| (m - first).is_zero()) { Some((first.clone(), r.facets)) } else { None }
+    });
+    let (midradius, vertex_radii) = match squares {
+      Some((m2, facets)) => {
+        let m4 = &m2*&m2;
+        let vertex_radii = facets.into_iter().filter_map(|(d, r2)| Some((d, radical(&m4*&r2.inverse()?)))).collect();
+        (Some(radical(m2)), vertex_radii)
+      }
+      None => (None, Vec::new()),
+    };
+    let polytope = match &midradius {
+      Some(r) => polytope.reciprocal(r.to_f64())?,
+      None => polytope.dual()?,
+    };
+    Ok(Dual { dynkin: self.dynkin(), midradius, vertex_radii, polytope })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  fn counts(p: &Polytope) -> Vec<usize> {
+    p.faces.iter().map(|f| f.len()).collect()
+  }
+
+  #[test]
+  fn duals() {
+    let octahedron = group("x4o3o").dual().unwrap();
+    assert_eq!(octahedron.to_string(), "dual x4o3o: 6 vertices, [6, 12, 8, 1] faces");
+    assert_eq!(octahedron.midradius.as_ref().unwrap().to_string(), "√2/2");
+    // the faces of the cube are at 1/2, its edges at √2/2, so the vertices of the dual are at 1
+    assert_eq!(octahedron.vertex_radii.iter().map(|(d, r)| format!("{} {}", d, r)).collect::<Vec<_>>(), vec!["x4o 1"]);
+    for v in &octahedron.polytope.verts {
+      assert!((v.dot(v) - 1f64).abs() < EPS);
+    }
+    assert_eq!(counts(&group("x4o3o3o").dual().unwrap().polytope), vec![8, 24, 32, 16, 1]);
+    assert_eq!(counts(&group("x3o3o3o").dual().unwrap().polytope), vec![5, 10, 10, 5, 1]);
+    // rhombic dodecahedron
+    assert_eq!(counts(&group("o4x3o").dual().unwrap().polytope), vec![14, 24, 12, 1]);
+    // the vertices of the dual lie at the exact distances, e.g. two for the rhombic dodecahedron
+    for s in ["o4x3o", "x4o3x", "x3o5o", "x5/2o5o"] {
+      let dual = group(s).dual().unwrap();
+      let mut radii: Vec<f64> = dual.vertex_radii.iter().map(|(_, r)| r.to_f64()).collect();
+      radii.sort_by(|a, b| a.partial_cmp(b).unwrap());
+      radii.dedup_by(|a, b| (*a - *b).abs() < EPS);
+      let mut found: Vec<f64> = dual.polytope.verts.iter().map(|v| v.dot(v).sqrt()).collect();
+      found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+      found.dedup_by(|a, b| (*a - *b).abs() < EPS);
+      assert_eq!(radii.len(), found.len(), "{}", s);
+      assert!(radii.iter().zip(&found).all(|(a, b)| (a - b).abs() < EPS), "{}", s);
+    }
+  }
+
+  #[test]
+  fn star_duals() {
+    // small stellated dodecahedron -> great dodecahedron
+    let p = group("x5/2o5o").dual().unwrap().polytope;
+    assert_eq!(counts(&p), vec![12, 30, 12, 1]);
+    assert_eq!(p.density(), Some(3));
+    assert_eq!(p.hasse().faces[2].iter().map(|f| f.len()).collect::<Vec<_>>(), vec![5; 12]);
+  }
+
+  #[test]
+  fn dual_of_dual() {
+    let p = group("x4x3o").polytope().unwrap();
+    let q = p.dual().unwrap().dual().unwrap();
+    assert_eq!(counts(&q), counts(&p));
+    let mut set = PointSet::default();
+    for v in &p.verts {
+      set.insert(v.clone());
+    }
+    assert!(q.verts.iter().all(|v| set.find(v).is_some()));
+    // a prism with bases of edge φ has no midsphere
+    assert!(group("x3o f").polytope().unwrap().dual().is_err());
+  }
+
+  #[test]
+  fn save_dual() {
+    let conn = polydb::memory().unwrap();
+    let cube = polydb::polyhedron(&conn, "x4o3o", "cube").unwrap();
+    let id = group("x4o3o").dual().unwrap().save(&conn).unwrap();
+    assert_ne!(id, cube);
+    let (name, dual): (String, bool) = conn.query_row("SELECT longname, dual FROM Polyhedron WHERE id=?1", [id], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+    assert_eq!((name.as_str(), dual), ("dual x4o3o", true));
+  }
+}
@@ -0,0 +1,148 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use petgraph::graph::NodeIndex;
+
+use crate::lattice::{wythoffian, Hasse};
+use crate::orbit::{normals, reflect, PointSet};
+use crate::roots::Cyc;
+use crate::{determine_cartesian, CoxGroup, Failed};
+
+/// Tolerance for comparing edge lengths
+const EPS: f64 = 1e-9;
+
+/// The edges of a Wythoffian polytope that are bisected by copies of the mirror of one ringed node
+#[derive(Debug, Clone)]
+pub struct EdgeClass {
+  pub node: NodeIndex,
+  /// length of the edges, the value of the node
+  pub length: f64,
+  /// the length as an exact number, when the node is one of the named values
+  pub exact: Option<Cyc>,
+  pub count: usize,
+  /// number of vertices of each of the 2-faces that meet at an edge of the class, sorted
+  pub faces: Vec<usize>,
+}
+
+/// Exact value of a node letter, the inverse of `parse::eval` for the letters it knows
+/// All of them are in some Z[2cos(𝛑/m)]: √2 = 2cos(𝛑/4), φ = 2cos(𝛑/5), √3 = 2cos(𝛑/6), √(2+√2) = 2cos(𝛑/8)
+pub fn exact_value(repr: &str) -> Option<Cyc> {
+  let phi = Cyc::gen(5);
+  match repr {
+    "o" => Some(Cyc::int(0)),
+    "x" | "s" => Some(Cyc::int(1)),
+    "u" => Some(Cyc::int(2)),
+    "q" => Some(Cyc::gen(4)),
+    "h" => Some(Cyc::gen(6)),
+    "k" => Some(Cyc::gen(8)),
+    "f" => Some(phi),
+    "v" => Some(&phi - &Cyc::int(1)),
+    "F" => Some(&phi + &Cyc::int(1)),
+    _ => None,
+  }
+}
+
+impl fmt::Display for EdgeClass {
+  /// e.g. 12 edges of length 1 at node 1, between faces 3 6
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let length = match &self.exact {
+      Some(e) => e.to_string(),
+      None => self.length.to_string(),
+    };
+    let faces: Vec<String> = self.faces.iter().map(|n| n.to_string()).collect();
+    write!(f, "{} edges of length {} at node {}, between faces {}", self.count, length, self.node.index(), faces.join(" "))
+  }
+}
+
+impl CoxGroup<f64> {
+  /// The edge classes of the Wythoffian polytope, one for every ringed node
+  /// e.g. x3x3o -> 6 edges between two hexagons and 12 edges between a hexagon and a triangle, all of length 1
+  pub fn edge_classes(&self) -> Result<Vec<EdgeClass>, Failed> {
+    let mut symm = self.graph.clone();
+    determine_cartesian(&mut symm)?;
+    let normals = normals(&symm);
+    let tope = wythoffian(&self.graph)?;
+    let hasse: Hasse = tope.hasse();
+    let mut set = PointSet::default();
+    for v in &tope.verts {
+      set.insert(v.clone());
+    }
+    let perms: Vec<Vec<usize>> = normals.iter().map(|n| {
+      tope.verts.iter().map(|v| set.find(&reflect(v, n)).unwrap()).collect()
+    }).collect();
+
+    let mut classes = Vec::new();
+    for i in self.graph.node_indices() {
+      let node = &self.graph[i];
+      if node.val == 0f64 {
+        continue;
+      }
+      let other = perms[i.index()][0];
+      let edge = vec![0, other];
+      // all images of the edge
+      let mut seen: HashSet<Vec<usize>> = HashSet::from([edge.clone()]);
+      let mut queue = VecDeque::from(vec![edge.clone()]);
+      while let Some(e) = queue.pop_front() {
+        for p in &perms {
+          let mut img = vec![p[e[0]], p[e[1]]];
+          img.sort();
+          if seen.insert(img.clone()) {
+            queue.push_back(img);
+          }
+        }
+      }
+      let mut faces: Vec<usize> = match hasse.faces.get(1).and_then(|es| es.iter().position(|e| *e == edge)) {
+        Some(k) if hasse.rank() >= 2 => hasse.sup[1][k].iter().map(|f| hasse.faces[2][*f].len()).collect(),
+        _ => Vec::new(),
+      };
+      faces.sort();
+      classes.push(EdgeClass {
+        node: i,
+        length: (&tope.verts[0] - &tope.verts[other]).dot(&(&tope.verts[0] - &tope.verts[other])).sqrt(),
+        exact: exact_value(&node.repr),
+        count: seen.len(),
+        faces,
+      });
+    }
+    Ok(classes)
+  }
+
+  /// Whether all edges have the same length
+  pub fn is_uniform(&self) -> Result<bool, Failed> {
+    let classes = self.edge_classes()?;
+    Ok(classes.iter().all(|c| (c.length - classes[0].length).abs() < EPS))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::Scanner;
+
+  fn classes(s: &str) -> Vec<EdgeClass> {
+    let mut sc = Scanner::new(s);
+    sc.parse_tope::<f64>().unwrap().edge_classes().unwrap()
+  }
+
+  #[test]
+  fn truncated_tetrahedron() {
+    let c = classes("x3x3o");
+    assert_eq!(c.len(), 2);
+    assert_eq!((c[0].count, c[0].faces.clone()), (6, vec![6, 6]));
+    assert_eq!((c[1].count, c[1].faces.clone()), (12, vec![3, 6]));
+    assert!(c.iter().all(|e| (e.length - 1f64).abs() < EPS && e.exact == Some(Cyc::int(1))));
+    let mut sc = Scanner::new("x3x3o");
+    assert_eq!(sc.parse_tope::<f64>().unwrap().is_uniform(), Ok(true));
+  }
+
+  #[test]
+  fn two_edge_lengths() {
+    let c = classes("x3f3o");
+    let phi = (1f64 + 5f64.sqrt())/2f64;
+    assert!((c[1].length - phi).abs() < EPS);
+    assert_eq!(c[1].exact, Some(Cyc::gen(5)));
+    assert_eq!(c[1].to_string(), "12 edges of length φ at node 1, between faces 3 6");
+    let mut sc = Scanner::new("x3f3o");
+    assert_eq!(sc.parse_tope::<f64>().unwrap().is_uniform(), Ok(false));
+  }
+}
@@ -0,0 +1,273 @@
+use std::fmt;
+
+use ndarray::Array2;
+
+use crate::{mark, CoxEdge, CoxGraph, CoxGroup};
+
+/// Tolerance for the sign of root coordinates
+const EPS: f64 = 1e-9;
+
+/// The Tits (geometric) representation of the abstract Coxeter group of a diagram:
+/// gens[i] is the matrix of s_i acting on the basis of simple roots
+/// A mark p/q counts as p here, so this is the group (s_i s_j)^p = 1 and not the group the star mirrors generate
+#[derive(Debug, Clone)]
+pub struct Tits {
+  pub gens: Vec<Array2<f64>>,
+}
+
+/// An element of a Coxeter group, stored as its shortlex normal form:
+/// the lexicographically smallest reduced word in the generators
+#[derive(Debug, Clone)]
+pub struct CoxElement<'a> {
+  tits: &'a Tits,
+  word: Vec<usize>,
+  /// matrices of the element and of its inverse in the Tits representation
+  mat: Array2<f64>,
+  inv: Array2<f64>,
+}
+
+impl Tits {
+  pub fn new<T: Clone>(graph: &CoxGraph<T>) -> Tits {
+    let n = graph.node_count();
+    let mut b = Array2::<f64>::eye(n);
+    for i in 0..n {
+      for j in 0..n {
+        if i != j {
+          let m = match mark(graph, i, j) {
+            CoxEdge::Int(m) => m,
+            CoxEdge::Frac(f) => *f.numer().unwrap(),
+          };
+          b[[i, j]] = -(std::f64::consts::PI/m as f64).cos();
+        }
+      }
+    }
+    let gens = (0..n).map(|i| {
+      let mut s = Array2::<f64>::eye(n);
+      for k in 0..n {
+        s[[i, k]] -= 2f64*b[[i, k]];
+      }
+      s
+    }).collect();
+    Tits { gens }
+  }
+
+  pub fn rank(&self) -> usize {
+    self.gens.len()
+  }
+
+  pub fn identity(&self) -> CoxElement<'_> {
+    CoxElement { tits: self, word: Vec::new(), mat: Array2::eye(self.rank()), inv: Array2::eye(self.rank()) }
+  }
+
+  /// The element s_0 s_1 ... for the word [0, 1, ...], which does not have to be reduced
+  pub fn element(&self, word: &[usize]) -> CoxElement<'_> {
+    let mut inv = Array2::eye(self.rank());
+    for s in word {
+      inv = self.gens[*s].dot(&inv);
+    }
+    CoxElement::normalize(self, inv)
+  }
+
+  /// Minimal length representatives of the cosets wW_J with length at most `max_length`, ordered by length
+  /// These are the elements without right descents in J; for a finite group with large enough `max_length`,
+  /// there are |W|/|W_J| of them
+  pub fn min_coset_reps(&self, subgroup: &[usize], max_length: usize) -> Vec<CoxElement<'_>> {
+    let mut reps = vec![self.identity()];
+    let mut start = 0;
+    for _ in 0..max_length {
+      let end = reps.len();
+      for i in start..end {
+        for s in 0..self.rank() {
+          if reps[i].left_descents().contains(&s) {
+            continue;
+          }
+          let w = self.element(&[s]).mul(&reps[i]);
+          if w.right_descents().iter().all(|d| !subgroup.contains(d)) && !reps[end..].contains(&w) {
+            reps.push(w);
+          }
+        }
+      }
+      if reps.len() == end {
+        break;
+      }
+      start = end;
+    }
+    reps
+  }
+}
+
+/// Whether a root in the basis of simple roots is negative
+fn negative(mat: &Array2<f64>, col: usize) -> bool {
+  mat.column(col).iter().any(|x| *x < -EPS)
+}
+
+impl<'a> CoxElement<'a> {
+  /// Finds the shortlex normal form of the element whose inverse has matrix `inv`,
+  /// by repeatedly splitting off the smallest left descent s: w⁻¹(α_s) < 0
+  fn normalize(tits: &'a Tits, inv: Array2<f64>) -> CoxElement<'a> {
+    let mut word = Vec::new();
+    let mut rest = inv;
+    while let Some(s) = (0..tits.rank()).find(|s| negative(&rest, *s)) {
+      word.push(s);
+      rest = rest.dot(&tits.gens[s]);
+    }
+    // recompute the matrices from the word to avoid drift
+    let mut mat = Array2::eye(tits.rank());
+    let mut inv = Array2::eye(tits.rank());
+    for s in &word {
+      mat = mat.dot(&tits.gens[*s]);
+      inv = tits.gens[*s].dot(&inv);
+    }
+    CoxElement { tits, word, mat, inv }
+  }
+
+  /// The reduced word in shortlex normal form
+  pub fn word(&self) -> &[usize] {
+    &self.word
+  }
+
+  pub fn matrix(&self) -> &Array2<f64> {
+    &self.mat
+  }
+
+  /// Length: the number of letters in a reduced word
+  pub fn length(&self) -> usize {
+    self.word.len()
+  }
+
+  pub fn is_identity(&self) -> bool {
+    self.word.is_empty()
+  }
+
+  pub fn mul(&self, other: &CoxElement<'a>) -> CoxElement<'a> {
+    CoxElement::normalize(self.tits, other.inv.dot(&self.inv))
+  }
+
+  pub fn inverse(&self) -> CoxElement<'a> {
+    CoxElement::normalize(self.tits, self.mat.clone())
+  }
+
+  /// Generators s with l(ws) < l(w), i.e. w(α_s) < 0
+  pub fn right_descents(&self) -> Vec<usize> {
+    (0..self.tits.rank()).filter(|s| negative(&self.mat, *s)).collect()
+  }
+
+  /// Generators s with l(sw) < l(w), i.e. w⁻¹(α_s) < 0
+  pub fn left_descents(&self) -> Vec<usize> {
+    (0..self.tits.rank()).filter(|s| negative(&self.inv, *s)).collect()
+  }
+
+  /// Bruhat order: self ≤ other if a reduced word of self is a subword of a reduced word of other
+  /// Uses that for a right descent s of w, u ≤ w iff min(u, us) ≤ ws
+  pub fn bruhat_le(&self, other: &CoxElement<'a>) -> bool {
+    if self.length() > other.length() {
+      return false;
+    }
+    match other.right_descents().first() {
+      None => self.is_identity(),
+      Some(s) => {
+        let s = self.tits.element(&[*s]);
+        let ws = other.mul(&s);
+        if self.right_descents().contains(&s.word[0]) {
+          self.mul(&s).bruhat_le(&ws)
+        } else {
+          self.bruhat_le(&ws)
+        }
+      }
+    }
+  }
+
+  /// The shortest element of the coset wW_J
+  pub fn min_coset_rep(&self, subgroup: &[usize]) -> CoxElement<'a> {
+    let mut w = self.clone();
+    while let Some(s) = w.right_descents().into_iter().find(|s| subgroup.contains(s)) {
+      w = w.mul(&self.tits.element(&[s]));
+    }
+    w
+  }
+}
+
+impl PartialEq for CoxElement<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    self.word == other.word
+  }
+}
+
+impl fmt::Display for CoxElement<'_> {
+  /// e.g. s0s1s0, or e for the identity
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.word.is_empty() {
+      return write!(f, "e");
+    }
+    for s in &self.word {
+      write!(f, "s{}", s)?;
+    }
+    Ok(())
+  }
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Tits representation for computing with the elements of the group
+  pub fn tits(&self) -> Tits {
+    Tits::new(&self.graph)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::Scanner;
+
+  fn tits(s: &str) -> Tits {
+    let mut sc = Scanner::new(s);
+    sc.parse_tope::<f64>().unwrap().tits()
+  }
+
+  #[test]
+  fn words() {
+    let t = tits("x3o3o");
+    let w = t.element(&[1, 0, 1]);
+    assert_eq!(w.word(), &[0, 1, 0]);
+    assert_eq!(t.element(&[0, 0]), t.identity());
+    assert_eq!(t.element(&[2, 0]).word(), &[0, 2]);
+    let u = t.element(&[0, 1, 2]);
+    assert!(u.mul(&u.inverse()).is_identity());
+    assert_eq!(u.right_descents(), vec![2]);
+    assert_eq!(u.left_descents(), vec![0]);
+    // longest element of A3 has length 6 and every generator as descent
+    let all = t.min_coset_reps(&[], 10);
+    assert_eq!(all.len(), 24);
+    let w0 = all.last().unwrap();
+    assert_eq!(w0.length(), 6);
+    assert_eq!(w0.right_descents(), vec![0, 1, 2]);
+    assert_eq!(w0.to_string(), "s0s1s0s2s1s0");
+  }
+
+  #[test]
+  fn bruhat() {
+    let t = tits("x3o");
+    let (a, b) = (t.element(&[0]), t.element(&[1]));
+    let (ab, ba) = (t.element(&[0, 1]), t.element(&[1, 0]));
+    assert!(t.identity().bruhat_le(&a));
+    assert!(a.bruhat_le(&ab) && a.bruhat_le(&ba) && b.bruhat_le(&ab));
+    assert!(!ab.bruhat_le(&ba) && !ba.bruhat_le(&ab));
+    assert!(!a.bruhat_le(&b));
+    let t = tits("x4o3o");
+    let w0 = t.min_coset_reps(&[], 20).pop().unwrap();
+    assert!(t.min_coset_reps(&[], 20).iter().all(|w| w.bruhat_le(&w0)));
+  }
+
+  #[test]
+  fn cosets() {
+    // vertices of the dodecahedron, cuboctahedron and the 120-cell
+    assert_eq!(tits("x5o3o").min_coset_reps(&[1, 2], 100).len(), 20);
+    assert_eq!(tits("o4x3o").min_coset_reps(&[0, 2], 100).len(), 12);
+    assert_eq!(tits("x5o3o3o").min_coset_reps(&[1, 2, 3], 100).len(), 600);
+    let t = tits("x4o3o");
+    let w = t.element(&[0, 1, 2, 1]);
+    let m = w.min_coset_rep(&[1, 2]);
+    assert_eq!(m.word(), &[0]);
+    // affine groups are infinite, but the reps can still be listed up to a length
+    assert_eq!(tits("x4o4o").min_coset_reps(&[], 2).len(), 1 + 3 + 5);
+  }
+}
@@ -0,0 +1,167 @@
+use ndarray::Array1;
+
+use crate::product::Polytope;
+use crate::uniform::polygram;
+use crate::{CoxGroup, Failed};
+
+/// Directions shorter than this do not add a dimension to an affine span
+const EPS: f64 = 1e-9;
+
+/// An orthonormal basis of the directions from the first point to the others, by Gram-Schmidt,
+/// with the indices of the points that added a direction, so these with the first point span the same affine space as all of them
+pub fn affine_basis(points: &[&Array1<f64>]) -> (Vec<Array1<f64>>, Vec<usize>) {
+  let mut basis: Vec<Array1<f64>> = Vec::new();
+  let mut spanning = Vec::new();
+  for (i, p) in points.iter().enumerate().skip(1) {
+    let mut d = *p - points[0];
+    for b in &basis {
+      d = &d - &(b*b.dot(&d));
+    }
+    let len = d.dot(&d).sqrt();
+    if len > EPS {
+      basis.push(d/len);
+      spanning.push(i);
+    }
+  }
+  (basis, spanning)
+}
+
+/// Coordinates of the points in an orthonormal basis of their affine span, with the point of the span closest to `apex` as origin
+/// For a vertex figure the apex is the vertex, and the origin is the centre of the figure if its vertices are all as far from the apex
+fn span_coordinates(points: &[Array1<f64>], apex: &Array1<f64>) -> Vec<Array1<f64>> {
+  let (basis, _) = affine_basis(&points.iter().collect::<Vec<_>>());
+  let mut origin = apex - &points[0];
+  for b in &basis {
+    origin = &origin - &(b*b.dot(&origin));
+  }
+  let origin = apex - &origin;
+  points.iter().map(|p| basis.iter().map(|b| b.dot(&(p - &origin))).collect()).collect()
+}
+
+impl Polytope {
+  /// The vertex figure at vertex v: a vertex for every edge at v, at its other end, and a (k-1)-face for every k-face through v,
+  /// made of the edges at v that lie in it, so e.g. the squares through a vertex of the cube become the edges of a triangle
+  /// The coordinates are taken in the affine span of the new vertices, so a figure of rank n-1 has n-1 coordinates if it is flat,
+  /// as it is for polytopes with all vertices on a sphere and all edges as long
+  /// e.g. cube -> triangle with edges √2, 600-cell -> icosahedron
+  pub fn vertex_figure(&self, v: usize) -> Polytope {
+    let rank = self.rank();
+    if rank == 0 {
+      return Polytope::default();
+    }
+    let hasse = self.hasse();
+    let edges = hasse.incident(0, v, 1);
+    let ends: Vec<Array1<f64>> = edges.iter().map(|e| {
+      let w = hasse.faces[1][*e].iter().find(|w| **w != v).unwrap_or(&v);
+      self.verts[*w].clone()
+    }).collect();
+    let faces = (1..=rank).map(|k| {
+      hasse.incident(0, v, k).iter().map(|f| {
+        let inner = hasse.incident(k, *f, 1);
+        edges.iter().enumerate().filter(|(_, e)| inner.contains(e)).map(|(i, _)| i).collect()
+      }).collect()
+    }).collect();
+    Polytope { verts: span_coordinates(&ends, &self.verts[v]), faces }
+  }
+
+  /// The faces around vertex v of a polyhedron in cyclic order, as n or n/d for {n/d}, e.g. 3.4.3.4 for the cuboctahedron
+  /// Of the rotations and reflections of the cycle the one with the smallest sequence of faces is taken, so e.g. 3.8.8 and not 8.3.8
+  /// None if the polytope is not a polyhedron in three dimensions, or the faces around v do not form a single cycle
+  pub fn vertex_configuration(&self, v: usize) -> Option<String> {
+    if self.rank() != 3 || self.verts[0].len() != 3 {
+      return None;
+    }
+    let hasse = self.hasse();
+    let faces = hasse.incident(0, v, 2);
+    let edges = hasse.incident(0, v, 1);
+    // walk around the vertex, from a face through one of its edges at v to the other face at that edge
+    let mut cycle = vec![faces[0]];
+    let mut edge = *hasse.sub[2][faces[0]].iter().find(|e| edges.contains(e))?;
+    loop {
+      let next = match hasse.sup[1][edge].as_slice() {
+        [a, b] => if *a == *cycle.last().unwrap() { *b } else { *a },
+        _ => return None,
+      };
+      if next == cycle[0] {
+        break;
+      }
+      if cycle.contains(&next) {
+        return None;
+      }
+      cycle.push(next);
+      edge = *hasse.sub[2][next].iter().find(|e| **e != edge && edges.contains(e))?;
+    }
+    if cycle.len() != faces.len() {
+      return None;
+    }
+    let types: Vec<(usize, usize)> = cycle.iter().map(|f| {
+      let sides: Vec<Vec<usize>> = hasse.sub[2][*f].iter().map(|e| hasse.faces[1][*e].clone()).collect();
+      polygram(self, &sides, &hasse.faces[2][*f])
+    }).collect();
+    let n = types.len();
+    let best = (0..n).flat_map(|s| {
+      let forward: Vec<(usize, usize)> = (0..n).map(|i| types[(s + i) % n]).collect();
+      let backward: Vec<(usize, usize)> = (0..n).map(|i| types[(s + n - i) % n]).collect();
+      [forward, backward]
+    }).min()?;
+    Some(best.iter().map(|(n, d)| if *d == 1 { n.to_string() } else { format!("{}/{}", n, d) }).collect::<Vec<_>>().join("."))
+  }
+}
+
+impl CoxGroup<f64> {
+  /// The vertex figure of the polytope of the diagram, see `Polytope::vertex_figure`
+  /// All vertices of a Wythoffian polytope are alike, so the first one is taken
+  pub fn vertex_figure(&self) -> Result<Polytope, Failed> {
+    Ok(self.polytope()?.vertex_figure(0))
+  }
+
+  /// The vertex configuration of the polyhedron of the diagram, see `Polytope::vertex_configuration`
+  pub fn vertex_configuration(&self) -> Result<String, Failed> {
+    match self.polytope()?.vertex_configuration(0) {
+      Some(s) => Ok(s),
+      None => {
+        println!("The faces around a vertex do not form a cycle of a polyhedron");
+        Err(Failed)
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  fn counts(p: &Polytope) -> Vec<usize> {
+    p.faces.iter().map(|f| f.len()).collect()
+  }
+
+  #[test]
+  fn figures() {
+    for (s, c) in [("x4o3o", vec![3, 3, 1]), ("x4o3o3o", vec![4, 6, 4, 1]), ("x3o3o5o", vec![12, 30, 20, 1]),
+                   ("o3x4o", vec![4, 4, 1]), ("x3o3o3o3o *c3o", vec![16, 80, 160, 120, 26, 1]), ("x5o", vec![2, 1])] {
+      let f = group(s).vertex_figure().unwrap();
+      assert_eq!(counts(&f), c, "{}", s);
+      assert_eq!(f.verts[0].len(), c.len() - 1, "{}", s);
+    }
+    // the vertex figure of the cube is a triangle with edges √2 around its centre
+    let f = group("x4o3o").vertex_figure().unwrap();
+    for e in &f.faces[1] {
+      let d = &f.verts[e[0]] - &f.verts[e[1]];
+      assert!((d.dot(&d) - 2f64).abs() < EPS);
+    }
+    assert_eq!(f.density(), Some(1));
+    // the small stellated dodecahedron has pentagons as vertex figures, the great dodecahedron pentagrams
+    assert_eq!(group("x5/2o5o").vertex_figure().unwrap().density(), Some(1));
+    assert_eq!(group("x5o5/2o").vertex_figure().unwrap().density(), Some(2));
+  }
+
+  #[test]
+  fn configurations() {
+    for (s, c) in [("x4o3o", "4.4.4"), ("o3x4o", "3.4.3.4"), ("x4x3o", "3.8.8"), ("x4o3x", "3.4.4.4"), ("x4x3x", "4.6.8"),
+                   ("x3o5x", "3.4.5.4"), ("x5/2o5o", "5/2.5/2.5/2.5/2.5/2"), ("o3x5/2o", "3.5/2.3.5/2"), ("x3o x", "3.4.4")] {
+      assert_eq!(group(s).vertex_configuration().unwrap(), c, "{}", s);
+    }
+    assert!(group("x4o3o3o").vertex_configuration().is_err());
+  }
+}
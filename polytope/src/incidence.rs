@@ -0,0 +1,558 @@
+use std::fmt;
+
+use petgraph::graph::NodeIndex;
+
+use crate::parse::{Scanner, SyntaxError};
+use crate::roots::gcd;
+use crate::{components, is_face, mark, subgraph, CoxEdge, CoxGraph, CoxGroup, Failed};
+
+/// An orbit of elements of a Wythoffian polytope: all images of the face of a set of nodes, see `CoxGroup::element_orbits`
+#[derive(Debug, Clone)]
+pub struct ElementOrbit {
+  /// the nodes of the face, in the order of the diagram
+  pub nodes: Vec<usize>,
+  /// rank of the elements, the number of nodes
+  pub rank: usize,
+  /// diagram of the elements, e.g. x3o x for triangular prisms
+  pub dynkin: String,
+  /// e.g. triangular prism, None if no naming rule applies
+  pub name: Option<String>,
+  /// number of elements in the orbit, |W|/|W_K×W_L| for the nodes K of the face and the unringed nodes L that commute with them
+  pub count: u64,
+}
+
+impl ElementOrbit {
+  /// The name, or the diagram if there is none
+  pub fn label(&self) -> &str {
+    self.name.as_deref().unwrap_or(&self.dynkin)
+  }
+}
+
+/// All element orbits of a polytope, see `CoxGroup::element_orbits`
+#[derive(Debug, Clone)]
+pub struct Elements {
+  pub orbits: Vec<ElementOrbit>,
+}
+
+impl Elements {
+  /// Number of elements of every rank, e.g. [8, 12, 6, 1] for the cube
+  pub fn counts(&self) -> Vec<u64> {
+    let rank = self.orbits.iter().map(|o| o.rank).max().unwrap_or(0);
+    (0..=rank).map(|k| self.orbits.iter().filter(|o| o.rank == k).map(|o| o.count).sum()).collect()
+  }
+
+  /// The orbits of rank k as text, e.g. 120 dodecahedra, 600 tetrahedra, or 24 x3x3o for an orbit without a name
+  pub fn of_rank(&self, k: usize) -> String {
+    self.orbits.iter().filter(|o| o.rank == k).map(|o| match &o.name {
+      Some(name) if o.count != 1 => format!("{} {}", o.count, plural(name)),
+      _ => format!("{} {}", o.count, o.label()),
+    }).collect::<Vec<_>>().join(", ")
+  }
+}
+
+impl fmt::Display for Elements {
+  /// One line per rank, from the vertices up, e.g. for the cuboctahedron
+  /// 0: 12 vertices
+  /// 1: 24 edges
+  /// 2: 8 triangles, 6 squares
+  /// 3: 1 cuboctahedron
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let lines: Vec<String> = (0..self.counts().len()).map(|k| format!("{}: {}", k, self.of_rank(k))).collect();
+    write!(f, "{}", lines.join("\n"))
+  }
+}
+
+/// Plural of an element name, e.g. dodecahedra, vertices, triangular prisms
+fn plural(name: &str) -> String {
+  if let Some(stem) = name.strip_suffix("hedron") {
+    format!("{}hedra", stem)
+  } else if let Some(stem) = name.strip_suffix("choron") {
+    format!("{}chora", stem)
+  } else if let Some(stem) = name.strip_suffix("ex") {
+    format!("{}ices", stem)
+  } else {
+    format!("{}s", name)
+  }
+}
+
+/// Noun for the regular n/d-gon, e.g. pentagon, pentagram
+fn polygon_name(n: usize, d: usize) -> Option<String> {
+  let gon = match n {
+    3 => "triangle",
+    4 => "square",
+    5 => "pentagon",
+    6 => "hexagon",
+    7 => "heptagon",
+    8 => "octagon",
+    9 => "enneagon",
+    10 => "decagon",
+    12 => "dodecagon",
+    _ => return None,
+  };
+  match d {
+    1 => Some(gon.to_string()),
+    _ if n >= 5 => Some(gon.replace("gon", "gram")),
+    _ => None,
+  }
+}
+
+/// Type {n/d} of the polygon of a connected diagram of two nodes with mark p/q: {p/q} with one ringed node, {2p/q} with two
+/// None unless the ringed nodes have the same value, or if the polygon winds around a compound
+fn polygon_type(graph: &CoxGraph<f64>) -> Option<(usize, usize)> {
+  let (p, q) = match mark(graph, 0, 1) {
+    CoxEdge::Int(m) => (m as usize, 1),
+    CoxEdge::Frac(f) => (*f.numer().unwrap() as usize, *f.denom().unwrap() as usize),
+  };
+  let values: Vec<f64> = graph.node_weights().map(|n| n.val).filter(|v| *v != 0f64).collect();
+  if values.iter().any(|v| (v - values[0]).abs() > 1e-9) {
+    return None;
+  }
+  let (n, d) = if values.len() == 2 { (2*p, q) } else { (p, q) };
+  let d = d.min(n - d);
+  if gcd(n as i64, d as i64) != 1 { None } else { Some((n, d)) }
+}
+
+/// Schläfli symbol {p,q,...} of a connected diagram that is a path with only one of its ends ringed, i.e. of a regular polytope
+fn schlafli(graph: &CoxGraph<f64>) -> Option<Vec<(usize, usize)>> {
+  let n = graph.node_count();
+  let ringed: Vec<usize> = (0..n).filter(|i| graph[NodeIndex::new(*i)].val != 0f64).collect();
+  if ringed.len() != 1 || graph.edge_count() != n - 1 {
+    return None;
+  }
+  let mut path = vec![ringed[0]];
+  while path.len() < n {
+    let last = *path.last().unwrap();
+    let next = (0..n).find(|j| !path.contains(j) && graph.find_edge(NodeIndex::new(last), NodeIndex::new(*j)).is_some())?;
+    path.push(next);
+  }
+  Some(path.windows(2).map(|w| match mark(graph, w[0], w[1]) {
+    CoxEdge::Int(m) => (m as usize, 1),
+    CoxEdge::Frac(f) => (*f.numer().unwrap() as usize, *f.denom().unwrap() as usize),
+  }).collect())
+}
+
+/// Name of the regular polytope with Schläfli symbol s, for the infinite families and the regular polychora
+fn regular_name(s: &[(usize, usize)]) -> Option<String> {
+  let marks: Vec<usize> = s.iter().map(|(p, q)| if *q == 1 { *p } else { 0 }).collect();
+  let rank = s.len() + 1;
+  let name = match marks.as_slice() {
+    [3, 3, 3] => "pentachoron",
+    [4, 3, 3] => "tesseract",
+    [3, 3, 4] => "hexadecachoron",
+    [3, 4, 3] => "icositetrachoron",
+    [5, 3, 3] => "hecatonicosachoron",
+    [3, 3, 5] => "hexacosichoron",
+    m if m.iter().all(|p| *p == 3) => return Some(format!("{}-simplex", rank)),
+    [4, rest @ ..] if rest.iter().all(|p| *p == 3) => return Some(format!("{}-cube", rank)),
+    [rest @ .., 4] if rest.iter().all(|p| *p == 3) => return Some(format!("{}-orthoplex", rank)),
+    _ => return None,
+  };
+  Some(name.to_string())
+}
+
+/// Name of the polytope of a diagram, by these rules:
+/// - points and segments are vertices and edges
+/// - polygons by their type, e.g. x5/2o pentagram, x3x hexagon
+/// - polyhedra by their name as uniform polyhedra, e.g. x3o x triangular prism, see `CoxGroup::uniform`
+/// - products of segments are cubes, products of a named polytope and a segment are prisms, products of two polygons duoprisms,
+///   e.g. x x square, x3o3o x tetrahedral prism, x3o x5o 3-5 duoprism
+/// - regular polytopes of rank 4 and up by their Schläfli symbol, e.g. x3o3o5o hexacosichoron, x4o3o3o3o 5-cube
+///
+/// All ringed nodes need the same value, as otherwise the polytope has edges of different lengths
+pub fn element_name(graph: &CoxGraph<f64>) -> Option<String> {
+  let values: Vec<f64> = graph.node_weights().map(|n| n.val).filter(|v| *v != 0f64).collect();
+  if values.iter().any(|v| (v - values[0]).abs() > 1e-9) {
+    return None;
+  }
+  let rank = graph.node_count();
+  match rank {
+    0 => return Some("vertex".to_string()),
+    1 => return Some("edge".to_string()),
+    _ => {},
+  }
+  let comps = components(graph);
+  if comps.len() == 1 {
+    return match rank {
+      2 => polygon_type(graph).and_then(|(n, d)| polygon_name(n, d)),
+      3 => CoxGroup { graph: graph.clone(), polys: Vec::new() }.uniform()?.name,
+      _ => schlafli(graph).and_then(|s| regular_name(&s)),
+    };
+  }
+  if rank == 3 {
+    return CoxGroup { graph: graph.clone(), polys: Vec::new() }.uniform()?.name;
+  }
+  let (segments, others): (Vec<&Vec<usize>>, Vec<&Vec<usize>>) = comps.iter().partition(|c| c.len() == 1);
+  let symbol = |(n, d): (usize, usize)| if d == 1 { n.to_string() } else { format!("{}/{}", n, d) };
+  match (segments.len(), others.as_slice()) {
+    (2, []) => Some("square".to_string()),
+    (4, []) => Some("tesseract".to_string()),
+    (k, []) => Some(format!("{}-cube", k)),
+    (1, [c]) => {
+      let base = subgraph(graph, c);
+      let adjective = match (c.len(), element_name(&base)?.as_str()) {
+        (3, "tetrahedron") => "tetrahedral".to_string(),
+        (3, "cube") => "cubic".to_string(),
+        (3, "octahedron") => "octahedral".to_string(),
+        (3, "dodecahedron") => "dodecahedral".to_string(),
+        (3, "icosahedron") => "icosahedral".to_string(),
+        (_, name) => name.to_string(),
+      };
+      Some(format!("{} prism", adjective))
+    },
+    (0, [a, b]) if a.len() == 2 && b.len() == 2 => {
+      let (p, q) = (polygon_type(&subgraph(graph, a))?, polygon_type(&subgraph(graph, b))?);
+      Some(format!("{}-{} duoprism", symbol(p), symbol(q)))
+    },
+    (2, [c]) if c.len() == 2 => Some(format!("{}-4 duoprism", symbol(polygon_type(&subgraph(graph, c))?))),
+    _ => None,
+  }
+}
+
+impl CoxGroup<f64> {
+  /// The orbits of elements of the Wythoffian polytope of a finite diagram, from the diagram alone
+  /// A set of nodes K gives an orbit of |K|-faces if every component of its diagram has a ringed node,
+  /// and its stabilizer is generated by K and the unringed nodes that commute with all of K
+  /// The counts come from the group orders, so they are those of the Wythoffian before any coinciding elements are merged
+  /// e.g. x3o3o5x has 120 dodecahedra, 600 tetrahedra, 720 pentagonal prisms and 1200 triangular prisms as facets
+  pub fn element_orbits(&self) -> Result<Elements, Failed> {
+    let graph = &self.graph;
+    let n = graph.node_count();
+    let order = |nodes: &[usize]| CoxGroup { graph: subgraph(graph, nodes), polys: Vec::new() }.invariants().map(|i| i.order);
+    let total = match order(&(0..n).collect::<Vec<_>>()) {
+      Some(o) => o,
+      None => {
+        println!("Only finite groups have element orbits that can be counted");
+        return Err(Failed);
+      }
+    };
+    let ringed = |i: usize| graph[NodeIndex::new(i)].val != 0f64;
+    let mut orbits = Vec::new();
+    for mask in 0..1usize << n {
+      let nodes: Vec<usize> = (0..n).filter(|i| mask & 1 << i != 0).collect();
+      let sub = subgraph(graph, &nodes);
+      if !is_face(graph, &nodes, ringed) {
+        continue;
+      }
+      let others: Vec<usize> = (0..n).filter(|j| !nodes.contains(j) && !ringed(*j))
+        .filter(|j| nodes.iter().all(|i| graph.find_edge(NodeIndex::new(*i), NodeIndex::new(*j)).is_none()))
+        .collect();
+      let stabilizer = order(&nodes).unwrap()*order(&others).unwrap();
+      orbits.push(ElementOrbit {
+        rank: nodes.len(),
+        dynkin: CoxGroup { graph: sub.clone(), polys: Vec::new() }.dynkin(),
+        name: element_name(&sub),
+        count: total/stabilizer,
+        nodes,
+      });
+    }
+    orbits.sort_by_key(|o| o.rank);
+    Ok(Elements { orbits })
+  }
+}
+
+/// A lace prism in Klitzing's notation: two layers with the same symmetry, stacked and joined by lacing edges,
+/// e.g. xo3ox&#x, the octahedron as a triangular antiprism, or ox3oo&#x, the tetrahedron as a pyramid over a triangle
+#[derive(Debug, Clone)]
+pub struct LacePrism {
+  /// the diagram of every layer, with the same nodes and marks
+  pub layers: Vec<CoxGroup<f64>>,
+  /// the value of the lacing edges, e.g. x
+  pub lacing: String,
+}
+
+impl Scanner {
+  /// Parses a lace prism like xo3ox&#x: every node has one value per layer, the layers are read off as xo3ox -> x3o and o3x
+  /// Node references like *c keep their letter; only lace prisms of two layers are known, not towers (&#xt) or lace cities
+  pub fn parse_lace(&mut self) -> Result<LacePrism, SyntaxError> {
+    let mut text = String::new();
+    while let Some(c) = self.pop() {
+      text.push(c);
+    }
+    let (diagram, lacing) = match text.split_once("&#") {
+      Some((d, l)) if l.chars().count() == 1 && l.chars().all(|c| c.is_ascii_alphabetic()) => (d, l),
+      _ => return Err(SyntaxError::new_parse_error(format!("A lace prism ends in &# and the value of the lacing edges, found {}", text))),
+    };
+    // split the diagram into node groups, one letter per layer, and the rest
+    let mut parts: Vec<(bool, String)> = Vec::new();
+    let mut reference = false;
+    for c in diagram.chars() {
+      let value = c.is_ascii_alphabetic() && !reference;
+      match parts.last_mut() {
+        Some((true, group)) if value => group.push(c),
+        Some((false, rest)) if !value => rest.push(c),
+        _ => parts.push((value, c.to_string())),
+      }
+      reference = c == '*';
+    }
+    let layers = parts.iter().filter(|(v, _)| *v).map(|(_, g)| g.chars().count()).max().unwrap_or(0);
+    if layers != 2 || parts.iter().any(|(v, g)| *v && g.chars().count() != layers) {
+      return Err(SyntaxError::new_parse_error(format!("Every node of a lace prism needs a value for each of the two layers in {}", diagram)));
+    }
+    let layers = (0..layers).map(|k| {
+      let layer: String = parts.iter().map(|(v, g)| if *v { g.chars().nth(k).unwrap().to_string() } else { g.clone() }).collect();
+      Scanner::new(&layer).parse_tope::<f64>()
+    }).collect::<Result<Vec<_>, _>>()?;
+    Ok(LacePrism { layers, lacing: lacing.to_string() })
+  }
+}
+
+/// Klitzing's incidence matrix of a polytope: a row for every orbit of proper elements, sorted by rank,
+/// with the number of elements on the diagonal, and off it the number of elements of the column orbit
+/// that an element of the row orbit contains (left of the diagonal) or is contained in (right of it)
+#[derive(Debug, Clone)]
+pub struct IncidenceMatrix {
+  /// e.g. x . . for the edges of the cube, o.3o. for the vertices of the first layer of a lace prism
+  pub labels: Vec<String>,
+  pub ranks: Vec<usize>,
+  pub entries: Vec<Vec<u64>>,
+}
+
+impl IncidenceMatrix {
+  /// Number of elements of every rank below the top, the sums of the diagonal, e.g. [8, 12, 6] for the cube
+  pub fn counts(&self) -> Vec<u64> {
+    let top = self.ranks.iter().max().map_or(0, |r| r + 1);
+    (0..top).map(|k| (0..self.ranks.len()).filter(|i| self.ranks[*i] == k).map(|i| self.entries[i][i]).sum()).collect()
+  }
+}
+
+impl fmt::Display for IncidenceMatrix {
+  /// In Klitzing's layout, with bars between the ranks and * between different orbits of the same rank, e.g. for the cube
+  /// . . . | 8 |  3 | 3
+  /// ------+---+----+--
+  /// x . . | 2 | 12 | 2
+  /// ------+---+----+--
+  /// x4o . | 4 |  4 | 6
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let n = self.labels.len();
+    let cell = |i: usize, j: usize| if i != j && self.ranks[i] == self.ranks[j] { "*".to_string() } else { self.entries[i][j].to_string() };
+    let widths: Vec<usize> = (0..n).map(|j| (0..n).map(|i| cell(i, j).len()).max().unwrap()).collect();
+    let label_width = self.labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let mut lines = Vec::new();
+    for i in 0..n {
+      if i > 0 && self.ranks[i] != self.ranks[i-1] {
+        let mut line = "-".repeat(label_width + 1);
+        for (j, w) in widths.iter().enumerate() {
+          line += if j == 0 { "+-" } else if self.ranks[j] != self.ranks[j-1] { "-+-" } else { "-" };
+          line += &"-".repeat(*w);
+        }
+        lines.push(line);
+      }
+      let mut line = format!("{:<w$} ", self.labels[i], w = label_width);
+      for (j, w) in widths.iter().enumerate() {
+        line += if j == 0 { "| " } else if self.ranks[j] != self.ranks[j-1] { " | " } else { " " };
+        line += &format!("{:>w$}", cell(i, j), w = *w);
+      }
+      lines.push(line);
+    }
+    write!(f, "{}", lines.join("\n"))
+  }
+}
+
+/// Where an element lies: in one of the layers, or across them as the hull of the same face in every layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+  Layer(usize),
+  Lace,
+}
+
+/// The incidence matrix of the layers with the given ringed nodes over one diagram, by the orders of the subgroups alone
+/// An element is a set of nodes K in one layer, with a ringed node of that layer in every component of K, or the hull of the K-faces
+/// of all layers, with a ringed node of some layer in every component; its stabilizer in W_J for J ⊇ K is generated by K
+/// and the nodes of J that are unringed where the element lies and commute with all of K
+fn incidences(graph: &CoxGraph<f64>, ringed: &[Vec<bool>], label: impl Fn(Part, usize) -> String) -> Result<IncidenceMatrix, Failed> {
+  let n = graph.node_count();
+  let full = (1usize << n) - 1;
+  // |W_J| for every subset J of the nodes, as a bit mask
+  let mut orders = Vec::with_capacity(1 << n);
+  for mask in 0..=full {
+    let nodes: Vec<usize> = (0..n).filter(|i| mask & 1 << i != 0).collect();
+    match (CoxGroup { graph: subgraph(graph, &nodes), polys: Vec::new() }).invariants() {
+      Some(i) => orders.push(i.order),
+      None => {
+        println!("Only finite groups have incidence matrices that can be counted");
+        return Err(Failed);
+      }
+    }
+  }
+  let adjacent = |i: usize, j: usize| graph.find_edge(NodeIndex::new(i), NodeIndex::new(j)).is_some();
+  let ringed_in = |part: Part, i: usize| match part {
+    Part::Layer(t) => ringed[t][i],
+    Part::Lace => ringed.iter().any(|r| r[i]),
+  };
+  // order of the stabilizer in W_within of the element of `part` with nodes k
+  let stabilizer = |part: Part, k: usize, within: usize| {
+    let others = (0..n).filter(|j| within & !k & 1 << j != 0 && !ringed_in(part, *j))
+      .filter(|j| (0..n).all(|i| k & 1 << i == 0 || !adjacent(i, *j)))
+      .fold(0, |m, j| m | 1 << j);
+    orders[k]*orders[others]
+  };
+  let mut parts: Vec<Part> = (0..ringed.len()).map(Part::Layer).collect();
+  if ringed.len() > 1 {
+    parts.push(Part::Lace);
+  }
+  // rank of the polytope itself, which is left out
+  let top = n + if ringed.len() > 1 { 1 } else { 0 };
+  let mut rows: Vec<(usize, Part, usize)> = Vec::new();
+  for part in parts {
+    for k in 0..=full {
+      let nodes: Vec<usize> = (0..n).filter(|i| k & 1 << i != 0).collect();
+      if is_face(graph, &nodes, |i| ringed_in(part, i)) {
+        let rank = nodes.len() + if part == Part::Lace { 1 } else { 0 };
+        if rank < top {
+          rows.push((rank, part, k));
+        }
+      }
+    }
+  }
+  rows.sort_by_key(|(rank, _, k)| (*rank, k.count_ones() as usize != *rank));
+  let counts: Vec<u64> = rows.iter().map(|(_, part, k)| orders[full]/stabilizer(*part, *k, full)).collect();
+  // the elements of orbit c in an element of orbit r
+  let contained = |r: usize, c: usize| {
+    let ((_, rp, rk), (_, cp, ck)) = (rows[r], rows[c]);
+    if ck & !rk != 0 || (rp != Part::Lace && cp != rp) {
+      return 0;
+    }
+    orders[rk]/stabilizer(cp, ck, rk)
+  };
+  let entries = (0..rows.len()).map(|r| (0..rows.len()).map(|c| {
+    match rows[c].0.cmp(&rows[r].0) {
+      std::cmp::Ordering::Less => contained(r, c),
+      std::cmp::Ordering::Equal => if r == c { counts[r] } else { 0 },
+      // by counting the pairs both ways
+      std::cmp::Ordering::Greater => counts[c]*contained(c, r)/counts[r],
+    }
+  }).collect()).collect();
+  Ok(IncidenceMatrix {
+    labels: rows.iter().map(|(_, part, k)| label(*part, *k)).collect(),
+    ranks: rows.iter().map(|(rank, _, _)| *rank).collect(),
+    entries,
+  })
+}
+
+/// The label of an element: the symbols of its nodes, . for the other nodes, and the marks between neighbours in the string
+/// that are both shown; `symbol` gives the symbol of a node of the element
+fn label(graph: &CoxGraph<f64>, shown: impl Fn(usize) -> bool, symbol: impl Fn(usize) -> String, blank: &str) -> String {
+  let n = graph.node_count();
+  let mut text = String::new();
+  for i in 0..n {
+    text += &if shown(i) { symbol(i) } else { blank.to_string() };
+    if i + 1 < n {
+      match graph.find_edge(NodeIndex::new(i), NodeIndex::new(i + 1)) {
+        Some(e) if shown(i) && shown(i + 1) => text += &match graph[e] {
+          CoxEdge::Int(m) => m.to_string(),
+          CoxEdge::Frac(f) => format!("{}/{}", f.numer().unwrap(), f.denom().unwrap()),
+        },
+        _ => text.push(' '),
+      }
+    }
+  }
+  text
+}
+
+impl CoxGroup<f64> {
+  /// Klitzing's incidence matrix of the Wythoffian polytope of a finite diagram, from the orders of the subgroups of the Coxeter group,
+  /// without building any vertices, so it is instant even for the polytopes of E8; see `element_orbits` for how the orbits are found
+  /// e.g. x4o3o -> rows . . . (8 vertices), x . . (12 edges), x4o . (6 squares)
+  pub fn incidence_matrix(&self) -> Result<IncidenceMatrix, Failed> {
+    let graph = &self.graph;
+    let ringed: Vec<bool> = graph.node_weights().map(|n| n.val != 0f64).collect();
+    incidences(graph, &[ringed], |_, k| label(graph, |i| k & 1 << i != 0, |i| graph[NodeIndex::new(i)].repr.clone(), "."))
+  }
+}
+
+impl LacePrism {
+  /// Klitzing's incidence matrix of the lace prism, with the elements of every layer and the lacing elements,
+  /// where the lacing element of a set of nodes K is the hull of the K-faces of both layers, one rank higher
+  /// The length of the lacing edges does not change the incidences, as long as the layers can be joined by them
+  /// e.g. xo3ox&#x: 3+3 vertices, 3+3 edges in the layers, 6 lacing edges oo3oo&#x, 1+1 triangles in the layers and 6 lacing ones
+  pub fn incidence_matrix(&self) -> Result<IncidenceMatrix, Failed> {
+    let graph = &self.layers[0].graph;
+    let ringed: Vec<Vec<bool>> = self.layers.iter().map(|l| l.graph.node_weights().map(|n| n.val != 0f64).collect()).collect();
+    let layers = self.layers.len();
+    let repr = |t: usize, i: usize| self.layers[t].graph[NodeIndex::new(i)].repr.clone();
+    incidences(graph, &ringed, |part, k| match part {
+      // vertices are written with o in their layer, like o.3o.
+      Part::Layer(t) => label(graph, |i| k == 0 || k & 1 << i != 0, |i| {
+        (0..layers).map(|s| if s != t { ".".to_string() } else if k == 0 { "o".to_string() } else { repr(t, i) }).collect()
+      }, &".".repeat(layers)),
+      Part::Lace => label(graph, |i| k == 0 || k & 1 << i != 0, |i| {
+        (0..layers).map(|s| if k == 0 { "o".to_string() } else { repr(s, i) }).collect()
+      }, &".".repeat(layers)) + "&#" + &self.lacing,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::{group, Scanner};
+
+  fn elements(s: &str) -> Elements {
+    group(s).element_orbits().unwrap()
+  }
+
+  #[test]
+  fn orbits() {
+    assert_eq!(elements("o3x4o").to_string(), "0: 12 vertices\n1: 24 edges\n2: 8 triangles, 6 squares\n3: 1 cuboctahedron");
+    let e = elements("x3o3o5x");
+    assert_eq!(e.counts(), vec![2400, 7200, 7440, 2640, 1]);
+    let facets = e.of_rank(3);
+    let mut facets: Vec<&str> = facets.split(", ").collect();
+    facets.sort();
+    assert_eq!(facets, vec!["120 dodecahedra", "1200 triangular prisms", "600 tetrahedra", "720 pentagonal prisms"]);
+    assert_eq!(elements("x3o3o5o").of_rank(4), "1 hexacosichoron");
+    assert_eq!(elements("x3o x5o").of_rank(4), "1 3-5 duoprism");
+    assert_eq!(elements("x3o3o x").of_rank(3), "2 tetrahedra, 4 triangular prisms");
+    assert_eq!(elements("x3o3o x").of_rank(4), "1 tetrahedral prism");
+    assert_eq!(elements("x x x x").of_rank(4), "1 tesseract");
+    assert_eq!(elements("x5/2o5o").of_rank(2), "12 pentagrams");
+    // the x . x faces are squares too
+    assert_eq!(elements("x4o3x").of_rank(2), "6 squares, 12 squares, 8 triangles");
+    let facets = elements("x4x3x3o").of_rank(3);
+    assert!(facets.contains("8 great rhombicuboctahedra") && facets.contains("16 truncated tetrahedra"), "{}", facets);
+    // edges of two lengths: no name
+    assert_eq!(elements("x3f").of_rank(2), "1 f3x");
+  }
+
+  #[test]
+  fn matrices() {
+    let cube = group("x4o3o").incidence_matrix().unwrap();
+    assert_eq!(cube.to_string(), ". . . | 8 |  3 | 3\n------+---+----+--\nx . . | 2 | 12 | 2\n------+---+----+--\nx4o . | 4 |  4 | 6");
+    // 2_41 of E8, without building any of its vertices: 17280 7-simplices and 240 2_31 as facets
+    let e8 = group("x3o3o3o3o3o3o *c3o").incidence_matrix().unwrap();
+    assert_eq!(e8.counts(), vec![2160, 69120, 483840, 1209600, 1209600, 544320, 144960, 17520]);
+    let last = e8.labels.len() - 1;
+    assert_eq!((e8.labels[last].as_str(), e8.entries[last][last], e8.entries[last][0]), ("x3o3o3o3o3o . o", 240, 126));
+    // two orbits of facets of the truncated cube, counted both ways
+    let tic = group("x4x3o").incidence_matrix().unwrap();
+    assert_eq!(tic.counts(), vec![24, 36, 14]);
+    assert_eq!(tic.labels, vec![". . .", "x . .", ". x .", "x4x .", ". x3o"]);
+    assert_eq!(tic.entries[0], vec![24, 1, 2, 2, 1]);
+  }
+
+  #[test]
+  fn lace_prisms() {
+    // octahedron as a triangular antiprism, tetrahedron as a pyramid, triangular prism, and the pyramid over the cube
+    for (s, counts) in [("xo3ox&#x", vec![6, 12, 8]), ("ox3oo&#x", vec![4, 6, 4]), ("xx3oo&#x", vec![6, 9, 5]), ("xo4oo3oo&#x", vec![9, 20, 18, 7])] {
+      let mut sc = Scanner::new(s);
+      assert_eq!(sc.parse_lace().unwrap().incidence_matrix().unwrap().counts(), counts, "{}", s);
+    }
+    let mut sc = Scanner::new("xo3ox&#x");
+    let oct = sc.parse_lace().unwrap().incidence_matrix().unwrap();
+    assert_eq!(oct.labels, vec!["o.3o.", ".o3.o", "x. ..", ".. .x", "oo3oo&#x", "x.3o.", ".o3.x", "xo ..&#x", ".. ox&#x"]);
+    // every vertex is in 2 edges of its layer, 2 lacing edges, and 4 triangles
+    assert_eq!(oct.entries[0], vec![3, 0, 2, 0, 2, 1, 0, 2, 1]);
+    for s in ["xo3o&#x", "xoo3oox&#xt", "x3o"] {
+      assert!(Scanner::new(s).parse_lace().is_err(), "{}", s);
+    }
+  }
+
+  #[test]
+  fn against_vertices() {
+    for s in ["x4x3o", "x3o3o5x", "o3x3o4o", "x3o x5o", "x3o3o3o3o *c3o", "x4o3o3o3x"] {
+      let group = group(s);
+      let counts: Vec<u64> = group.polytope().unwrap().faces.iter().map(|f| f.len() as u64).collect();
+      assert_eq!(group.element_orbits().unwrap().counts(), counts, "{}", s);
+    }
+  }
+}
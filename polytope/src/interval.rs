@@ -0,0 +1,162 @@
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Most interval Newton steps, each at least halves the width once the root is enclosed
+const MAX_STEPS: usize = 100;
+
+/// Closed interval [lo, hi] of reals, every operation rounded outwards so that the true result stays inside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+  pub lo: f64,
+  pub hi: f64,
+}
+
+impl Interval {
+  pub fn new(lo: f64, hi: f64) -> Interval {
+    Interval { lo, hi }
+  }
+
+  pub fn point(x: f64) -> Interval {
+    Interval { lo: x, hi: x }
+  }
+
+  /// A float that is within a few ulps of the number it stands for, like a square root or cosine computed by the library
+  pub fn approx(x: f64) -> Interval {
+    let e = x.abs()*8f64*f64::EPSILON + f64::MIN_POSITIVE;
+    Interval { lo: (x - e).next_down(), hi: (x + e).next_up() }
+  }
+
+  fn outward(lo: f64, hi: f64) -> Interval {
+    Interval { lo: lo.next_down(), hi: hi.next_up() }
+  }
+
+  pub fn mid(&self) -> f64 {
+    self.lo/2f64 + self.hi/2f64
+  }
+
+  /// Half the width, rounded up, so the interval lies within mid ± radius
+  pub fn radius(&self) -> f64 {
+    let m = self.mid();
+    (m - self.lo).max(self.hi - m).next_up()
+  }
+
+  pub fn contains(&self, x: f64) -> bool {
+    self.lo <= x && x <= self.hi
+  }
+
+  /// Whether the interval lies in the interior of `other`
+  pub fn inside(&self, other: &Interval) -> bool {
+    other.lo < self.lo && self.hi < other.hi
+  }
+
+  pub fn intersect(&self, other: &Interval) -> Interval {
+    Interval { lo: self.lo.max(other.lo), hi: self.hi.min(other.hi) }
+  }
+
+  /// None if the interval contains 0
+  pub fn inverse(&self) -> Option<Interval> {
+    if self.contains(0f64) {
+      return None;
+    }
+    Some(Interval::outward(1f64/self.hi, 1f64/self.lo))
+  }
+
+  /// None if the interval has negative numbers
+  pub fn sqrt(&self) -> Option<Interval> {
+    if self.lo < 0f64 {
+      return None;
+    }
+    Some(Interval::outward(self.lo.sqrt(), self.hi.sqrt()).intersect(&Interval::new(0f64, f64::INFINITY)))
+  }
+
+  /// x^k for k ≥ 0
+  pub fn powi(&self, k: i32) -> Interval {
+    (0..k).fold(Interval::point(1f64), |acc, _| acc**self)
+  }
+}
+
+impl Add for Interval {
+  type Output = Interval;
+  fn add(self, other: Interval) -> Interval {
+    Interval::outward(self.lo + other.lo, self.hi + other.hi)
+  }
+}
+
+impl Neg for Interval {
+  type Output = Interval;
+  fn neg(self) -> Interval {
+    Interval { lo: -self.hi, hi: -self.lo }
+  }
+}
+
+impl Sub for Interval {
+  type Output = Interval;
+  fn sub(self, other: Interval) -> Interval {
+    self + -other
+  }
+}
+
+impl Mul for Interval {
+  type Output = Interval;
+  fn mul(self, other: Interval) -> Interval {
+    let p = [self.lo*other.lo, self.lo*other.hi, self.hi*other.lo, self.hi*other.hi];
+    Interval::outward(p.iter().cloned().fold(f64::INFINITY, f64::min), p.iter().cloned().fold(f64::NEG_INFINITY, f64::max))
+  }
+}
+
+impl fmt::Display for Interval {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "[{}, {}]", self.lo, self.hi)
+  }
+}
+
+/// The polynomial with coefficients `poly`, lowest first, at every point of x, by Horner's rule
+pub fn eval(poly: &[f64], x: Interval) -> Interval {
+  poly.iter().rev().fold(Interval::point(0f64), |acc, a| acc*x + Interval::point(*a))
+}
+
+/// An interval with exactly one root of `poly` (coefficients lowest first), started from guess ± radius, by interval Newton:
+/// for X with midpoint m, N(X) = m - f(m)/f'(X) holds every root in X, and if N(X) lies inside X, X has exactly one root
+/// The interval is then narrowed to X ∩ N(X) until it stops shrinking
+/// None if f' may vanish on X or N(X) does not land inside X, as near a double root
+pub fn newton(poly: &[f64], guess: f64, radius: f64) -> Option<Interval> {
+  let derivative: Vec<f64> = poly.iter().enumerate().skip(1).map(|(k, a)| k as f64*a).collect();
+  let step = |x: Interval| -> Option<Interval> {
+    let m = Interval::point(x.mid());
+    Some(m - eval(poly, m)*eval(&derivative, x).inverse()?)
+  };
+  let mut x = Interval::outward(guess - radius, guess + radius);
+  let n = step(x)?;
+  if !n.inside(&x) {
+    return None;
+  }
+  x = n;
+  for _ in 0..MAX_STEPS {
+    let next = match step(x) {
+      Some(n) => n.intersect(&x),
+      None => break,
+    };
+    if next.hi - next.lo >= x.hi - x.lo {
+      break;
+    }
+    x = next;
+  }
+  Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn enclosures() {
+    let two = Interval::point(2f64).sqrt().unwrap();
+    assert!(two.contains(2f64.sqrt()) && two.hi - two.lo < 1e-15);
+    assert!((two*two).contains(2f64));
+    assert!(Interval::new(-1f64, 1f64).inverse().is_none());
+    // √2 as the root of x² - 2, and no enclosure at the double root of x² - 2x + 1
+    let root = newton(&[-2f64, 0f64, 1f64], 1.4, 0.1).unwrap();
+    assert!(root.contains(2f64.sqrt()) && root.radius() < 1e-14);
+    assert!(newton(&[1f64, -2f64, 1f64], 1f64, 1e-6).is_none());
+  }
+}
@@ -0,0 +1,305 @@
+use std::collections::{HashSet, VecDeque};
+
+use ndarray::{Array1, Array2};
+
+use crate::classify::{classify, gram, CoxType, Kind};
+use crate::{components, subgraph, CoxEdge, CoxGraph, CoxGroup};
+
+/// Largest group that is enumerated element by element to find the underlying group of a diagram with rational marks
+const MAX_ORDER: usize = 100_000;
+
+/// Basic invariants of a finite Coxeter group
+/// For a reducible group the degrees of the factors are combined and the Coxeter number is the largest one of the factors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invariants {
+  /// |W|, the product of the degrees
+  pub order: u64,
+  /// Degrees of the basic invariant polynomials, sorted
+  pub degrees: Vec<u32>,
+  /// Exponents: the degrees minus one
+  pub exponents: Vec<u32>,
+  /// Coxeter number h: the largest degree
+  pub coxeter_number: u32,
+  /// Number of reflections N: the sum of the exponents
+  pub reflections: u32,
+  /// Length of the longest element, which is equal to the number of reflections
+  pub longest_length: u32,
+}
+
+/// Degrees of the basic invariants of an irreducible finite group, None for the affine groups
+pub fn degrees(t: &CoxType) -> Option<Vec<u32>> {
+  match t {
+    CoxType::A(n) => Some((2..=*n as u32 + 1).collect()),
+    CoxType::B(n) => Some((1..=*n as u32).map(|i| 2*i).collect()),
+    CoxType::D(n) => {
+      let mut d: Vec<u32> = (1..*n as u32).map(|i| 2*i).collect();
+      d.push(*n as u32);
+      Some(d)
+    },
+    CoxType::E(6) => Some(vec![2, 5, 6, 8, 9, 12]),
+    CoxType::E(7) => Some(vec![2, 6, 8, 10, 12, 14, 18]),
+    CoxType::E(8) => Some(vec![2, 8, 12, 14, 18, 20, 24, 30]),
+    CoxType::F4 => Some(vec![2, 6, 8, 12]),
+    CoxType::H(3) => Some(vec![2, 6, 10]),
+    CoxType::H(4) => Some(vec![2, 12, 20, 30]),
+    // p/q generates the same rotations as p
+    CoxType::I2(CoxEdge::Int(m)) => Some(vec![2, *m]),
+    CoxType::I2(CoxEdge::Frac(f)) => Some(vec![2, *f.numer().unwrap()]),
+    _ => None,
+  }
+}
+
+impl Invariants {
+  /// Invariants of the product of the given irreducible groups
+  /// None if a type is unknown, or the order does not fit in a u64, as for A_n with n ≥ 20
+  pub fn from_types(types: &[CoxType]) -> Option<Invariants> {
+    let mut all: Vec<u32> = Vec::new();
+    let mut coxeter_number = 0;
+    for t in types {
+      let d = degrees(t)?;
+      coxeter_number = coxeter_number.max(*d.iter().max().unwrap());
+      all.extend(d);
+    }
+    all.sort();
+    let exponents: Vec<u32> = all.iter().map(|d| d - 1).collect();
+    let reflections = exponents.iter().sum();
+    let order = all.iter().try_fold(1u64, |acc, d| acc.checked_mul(*d as u64))?;
+    Some(Invariants {
+      order,
+      degrees: all,
+      exponents,
+      coxeter_number,
+      reflections,
+      longest_length: reflections,
+    })
+  }
+}
+
+/// Matrices of the simple reflections acting on the basis of simple roots:
+/// s_i(v) = v - 2 B(α_i, v) α_i, with B the Gram matrix
+pub fn reflections<T: Clone>(graph: &CoxGraph<T>) -> Vec<Array2<f64>> {
+  let g = gram(graph);
+  let n = g.nrows();
+  (0..n).map(|i| {
+    let mut s = Array2::<f64>::eye(n);
+    for k in 0..n {
+      s[[i, k]] -= 2f64*g[[i, k]];
+    }
+    s
+  }).collect()
+}
+
+/// Key for comparing matrices and vectors up to rounding errors
+fn key<'a>(m: impl Iterator<Item = &'a f64>) -> Vec<i64> {
+  m.map(|x| (x*1e6).round() as i64).collect()
+}
+
+/// All elements of the group generated by the simple reflections, None if there are more than `MAX_ORDER`
+fn closure<T: Clone>(graph: &CoxGraph<T>) -> Option<Vec<Array2<f64>>> {
+  let gens = reflections(graph);
+  let n = graph.node_count();
+  let mut seen: HashSet<Vec<i64>> = HashSet::new();
+  let mut elements = vec![Array2::<f64>::eye(n)];
+  seen.insert(key(elements[0].iter()));
+  let mut queue: VecDeque<usize> = VecDeque::from(vec![0]);
+  while let Some(i) = queue.pop_front() {
+    for s in &gens {
+      let m = s.dot(&elements[i]);
+      if seen.insert(key(m.iter())) {
+        elements.push(m);
+        if elements.len() > MAX_ORDER {
+          return None;
+        }
+        queue.push_back(elements.len() - 1);
+      }
+    }
+  }
+  Some(elements)
+}
+
+/// Rank of a set of vectors by Gaussian elimination
+fn rank(vectors: &[Array1<f64>]) -> usize {
+  let mut rows: Vec<Array1<f64>> = vectors.to_vec();
+  let mut r = 0;
+  let n = rows.first().map(|v| v.len()).unwrap_or(0);
+  for col in 0..n {
+    let pivot = (r..rows.len()).max_by(|a, b| rows[*a][col].abs().partial_cmp(&rows[*b][col].abs()).unwrap());
+    match pivot {
+      Some(p) if rows[p][col].abs() > 1e-9 => {
+        rows.swap(r, p);
+        for i in r+1..rows.len() {
+          let f = rows[i][col]/rows[r][col];
+          let pr = rows[r].clone();
+          rows[i] -= &(pr*f);
+        }
+        r += 1;
+      },
+      _ => {},
+    }
+  }
+  r
+}
+
+/// Identifies an irreducible finite group from its rank and number of reflections
+/// E6 and B6 both have 36 reflections, they are told apart by the order `order` of the group if given
+fn from_reflections(rank: usize, count: usize, order: Option<u64>) -> Option<CoxType> {
+  let n = rank;
+  let candidates = match n {
+    1 => vec![CoxType::A(1)],
+    2 => vec![CoxType::I2(CoxEdge::Int(count as u32))],
+    3 => vec![CoxType::A(3), CoxType::B(3), CoxType::H(3)],
+    4 => vec![CoxType::A(4), CoxType::B(4), CoxType::D(4), CoxType::F4, CoxType::H(4)],
+    6 => vec![CoxType::A(6), CoxType::B(6), CoxType::D(6), CoxType::E(6)],
+    7 => vec![CoxType::A(7), CoxType::B(7), CoxType::D(7), CoxType::E(7)],
+    8 => vec![CoxType::A(8), CoxType::B(8), CoxType::D(8), CoxType::E(8)],
+    _ => vec![CoxType::A(n), CoxType::B(n), CoxType::D(n)],
+  };
+  let matching: Vec<CoxType> = candidates.into_iter().filter(|t| {
+    Invariants::from_types(&[*t]).map(|i| i.reflections as usize) == Some(count)
+  }).collect();
+  match (matching.len(), order) {
+    (1, _) => Some(matching[0]),
+    (_, Some(o)) => matching.into_iter().find(|t| Invariants::from_types(&[*t]).map(|i| i.order) == Some(o)),
+    _ => None,
+  }
+}
+
+/// The underlying finite group of a connected spherical diagram with rational marks, e.g. o5/2o3o -> H3
+/// The group is enumerated, its roots are split into mutually orthogonal sets, and each set is identified
+/// by its rank and number of reflections
+pub fn underlying<T: Clone>(graph: &CoxGraph<T>) -> Option<Vec<CoxType>> {
+  let elements = closure(graph)?;
+  let n = graph.node_count();
+  let g = gram(graph);
+  // all roots up to sign: images of the simple roots
+  let mut roots: Vec<Array1<f64>> = Vec::new();
+  let mut seen: HashSet<Vec<i64>> = HashSet::new();
+  for w in &elements {
+    for i in 0..n {
+      let r = w.column(i).to_owned();
+      let neg = -&r;
+      if !seen.contains(&key(neg.iter())) && seen.insert(key(r.iter())) {
+        roots.push(r);
+      }
+    }
+  }
+  identify(&roots, &g, Some(elements.len() as u64))
+}
+
+/// Identifies the irreducible factors of a finite group from all its roots up to sign, in a basis with bilinear form `g`
+/// Roots that are not orthogonal belong to the same factor, each factor is identified by its rank and number of reflections
+/// `order` is the order of the whole group, it is only used to tell E6 and B6 apart
+pub fn identify(roots: &[Array1<f64>], g: &Array2<f64>, order: Option<u64>) -> Option<Vec<CoxType>> {
+  // roots that are not orthogonal belong to the same irreducible factor
+  let gr: Vec<Array1<f64>> = roots.iter().map(|r| g.dot(r)).collect();
+  let mut factor: Vec<usize> = (0..roots.len()).collect();
+  fn find(f: &mut Vec<usize>, i: usize) -> usize {
+    if f[i] != i {
+      let r = find(f, f[i]);
+      f[i] = r;
+    }
+    f[i]
+  }
+  for (i, r) in roots.iter().enumerate() {
+    for (j, gc) in gr.iter().enumerate().skip(i+1) {
+      if r.dot(gc).abs() > 1e-9 {
+        let (a, c) = (find(&mut factor, i), find(&mut factor, j));
+        factor[a] = c;
+      }
+    }
+  }
+  let mut groups: Vec<(usize, Vec<Array1<f64>>)> = Vec::new();
+  for (i, r) in roots.iter().enumerate() {
+    let f = find(&mut factor, i);
+    match groups.iter_mut().find(|(k, _)| *k == f) {
+      Some((_, rs)) => rs.push(r.clone()),
+      None => groups.push((f, vec![r.clone()])),
+    }
+  }
+  let single = groups.len() == 1;
+  groups.iter().map(|(_, rs)| {
+    from_reflections(rank(rs), rs.len(), if single { order } else { None })
+  }).collect()
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Irreducible factors of the group as finite Coxeter groups, None if the group is infinite
+  /// Components with rational marks are replaced by the group they generate
+  pub fn finite_types(&self) -> Option<Vec<CoxType>> {
+    let class = classify(&self.graph);
+    if class.kind != Kind::Spherical {
+      return None;
+    }
+    let mut types = Vec::new();
+    for (comp, (nodes, _, t)) in components(&self.graph).iter().zip(class.components) {
+      debug_assert_eq!(comp, &nodes);
+      match t {
+        Some(CoxType::I2(e)) => types.push(CoxType::I2(e)),
+        Some(t) => types.push(t),
+        None => types.extend(underlying(&subgraph(&self.graph, comp))?),
+      }
+    }
+    Some(types)
+  }
+
+  /// Order, degrees, exponents, Coxeter number, number of reflections and length of the longest element,
+  /// None if the group is not finite
+  /// e.g. x3o3o5o -> |W| = 14400, degrees 2,12,20,30
+  pub fn invariants(&self) -> Option<Invariants> {
+    Invariants::from_types(&self.finite_types()?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::Scanner;
+
+  fn inv(s: &str) -> Invariants {
+    let mut sc = Scanner::new(s);
+    sc.parse_tope::<f64>().unwrap().invariants().unwrap()
+  }
+
+  #[test]
+  fn classical_orders() {
+    let table = [
+      ("x3o3o", 24, 4, 6),
+      ("x3o3o3o3o", 720, 6, 15),
+      ("x4o3o", 48, 6, 9),
+      ("x4o3o3o3o", 3840, 10, 25),
+      ("x3o3o *b3o", 192, 6, 12),
+      ("x3o3o3o *b3o", 1920, 8, 20),
+      ("x3o3o3o3o *c3o", 51840, 12, 36),
+      ("x3o3o3o3o3o *c3o", 2903040, 18, 63),
+      ("x3o3o3o3o3o3o *c3o", 696729600, 30, 120),
+      ("x3o4o3o", 1152, 12, 24),
+      ("x5o3o", 120, 10, 15),
+      ("x5o3o3o", 14400, 30, 60),
+      ("x8o", 16, 8, 8),
+    ];
+    for (s, order, h, n) in table {
+      let i = inv(s);
+      assert_eq!((i.order, i.coxeter_number, i.reflections), (order, h, n), "{}", s);
+      assert_eq!(i.longest_length, n);
+      // N = rank*h/2 for irreducible groups
+      assert_eq!(2*n as usize, i.degrees.len()*h as usize);
+    }
+    assert_eq!(inv("x3o3o3o3o3o *c3o").degrees, vec![2, 6, 8, 10, 12, 14, 18]);
+    assert_eq!(inv("x5o3o3o").exponents, vec![1, 11, 19, 29]);
+  }
+
+  #[test]
+  fn reducible_and_star() {
+    let i = inv("x5o x3o");
+    assert_eq!((i.order, i.reflections, i.coxeter_number), (60, 8, 5));
+    assert_eq!(inv("x5/2o3o").order, 120);
+    assert_eq!(inv("x5/2o5o").degrees, vec![2, 6, 10]);
+    assert_eq!(inv("x3/2o3o").order, 24);
+    assert_eq!(inv("x5/2o").order, 10);
+    let mut sc = Scanner::new("x4o3o4o");
+    assert_eq!(sc.parse_tope::<f64>().unwrap().invariants(), None);
+    // 20! still fits in a u64, 21! does not
+    assert_eq!(Invariants::from_types(&[CoxType::A(19)]).unwrap().order, 2432902008176640000);
+    assert_eq!(Invariants::from_types(&[CoxType::A(20)]), None);
+  }
+}
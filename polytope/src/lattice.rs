@@ -0,0 +1,212 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::classify::{gram, signature};
+use crate::orbit::{normals, orbit, reflect, seed};
+use crate::product::Polytope;
+use crate::{determine_cartesian, is_face, CoxGraph, CoxGroup, Failed};
+
+/// Hasse diagram of the face lattice of a polytope
+/// The empty face is implicitly below every vertex, the polytope itself is the only face of the top rank
+#[derive(Debug, Clone, Default)]
+pub struct Hasse {
+  /// faces[k]: the k-faces as sorted vertex lists, as in `Polytope::faces`
+  pub faces: Vec<Vec<Vec<usize>>>,
+  /// sub[k][i]: indices into faces[k-1] of the facets of the k-face i, empty for vertices
+  pub sub: Vec<Vec<Vec<usize>>>,
+  /// sup[k][i]: indices into faces[k+1] of the faces that the k-face i is a facet of, empty for the top face
+  pub sup: Vec<Vec<Vec<usize>>>,
+}
+
+impl Hasse {
+  pub fn rank(&self) -> usize {
+    self.faces.len() - 1
+  }
+
+  /// Number of faces of every rank, e.g. [8, 12, 6, 1] for the cube
+  pub fn counts(&self) -> Vec<usize> {
+    self.faces.iter().map(|f| f.len()).collect()
+  }
+
+  /// Indices into faces[l] of the l-faces incident to the k-face i, below it if l < k and above it if l > k
+  pub fn incident(&self, k: usize, i: usize, l: usize) -> Vec<usize> {
+    let mut set = vec![i];
+    let mut rank = k;
+    while rank != l {
+      let mut next: Vec<usize> = if l < rank {
+        set.iter().flat_map(|f| self.sub[rank][*f].iter().cloned()).collect()
+      } else {
+        set.iter().flat_map(|f| self.sup[rank][*f].iter().cloned()).collect()
+      };
+      next.sort();
+      next.dedup();
+      set = next;
+      rank = if l < rank { rank - 1 } else { rank + 1 };
+    }
+    set
+  }
+}
+
+/// Whether sorted list a is contained in sorted list b
+fn subset(a: &[usize], b: &[usize]) -> bool {
+  let mut j = 0;
+  for x in a {
+    while j < b.len() && b[j] < *x {
+      j += 1;
+    }
+    if j == b.len() || b[j] != *x {
+      return false;
+    }
+  }
+  true
+}
+
+impl Polytope {
+  /// Hasse diagram of the faces, where a k-face is a facet of a (k+1)-face if its vertices are a subset
+  pub fn hasse(&self) -> Hasse {
+    let rank = self.rank();
+    let mut sub: Vec<Vec<Vec<usize>>> = self.faces.iter().map(|f| vec![Vec::new(); f.len()]).collect();
+    let mut sup = sub.clone();
+    for k in 1..=rank {
+      // lower faces by their first vertex, so only faces sharing a vertex get compared
+      let mut by_vertex: Vec<Vec<usize>> = vec![Vec::new(); self.verts.len()];
+      for (i, f) in self.faces[k-1].iter().enumerate() {
+        by_vertex[f[0]].push(i);
+      }
+      for (j, upper) in self.faces[k].iter().enumerate() {
+        for v in upper {
+          for i in &by_vertex[*v] {
+            if subset(&self.faces[k-1][*i], upper) {
+              sub[k][j].push(*i);
+              sup[k-1][*i].push(j);
+            }
+          }
+        }
+      }
+    }
+    Hasse { faces: self.faces.clone(), sub, sup }
+  }
+}
+
+/// Builds the Wythoffian polytope of a finite diagram with all its faces
+/// The seed point is the vertex, and a set of nodes K generates a |K|-face, namely the orbit of the seed under W_K,
+/// when every component of the subdiagram K has a ringed node; the other k-faces are the images of those under the group
+/// Faces with the same vertices are counted once
+/// e.g. x4o3o -> [8, 12, 6, 1], x5/2o3o -> [20, 30, 12, 1]
+pub fn wythoffian(graph: &CoxGraph<f64>) -> Result<Polytope, Failed> {
+  let mut symm = graph.clone();
+  let n = symm.node_count();
+  let (_, zero, neg) = signature(&gram(&symm));
+  if zero + neg > 0 {
+    println!("Only finite groups give polytopes");
+    return Err(Failed);
+  }
+  determine_cartesian(&mut symm)?;
+  let normals = normals(&symm);
+  let verts = match orbit(seed(&symm), &normals) {
+    Some(set) => set,
+    None => return Err(Failed),
+  };
+  // how every mirror permutes the vertices
+  let perms: Vec<Vec<usize>> = normals.iter().map(|m| {
+    verts.points.iter().map(|p| verts.find(&reflect(p, m)).unwrap()).collect()
+  }).collect();
+  let ringed: Vec<bool> = symm.node_weights().map(|node| node.val != 0f64).collect();
+
+  let mut faces: Vec<Vec<Vec<usize>>> = Vec::new();
+  for k in 0..=n {
+    let mut found: HashSet<Vec<usize>> = HashSet::new();
+    let mut rank_faces: Vec<Vec<usize>> = Vec::new();
+    for mask in 0u32..(1 << n) {
+      if mask.count_ones() as usize != k {
+        continue;
+      }
+      let nodes: Vec<usize> = (0..n).filter(|i| mask & (1 << i) != 0).collect();
+      if !is_face(&symm, &nodes, |i| ringed[i]) {
+        continue;
+      }
+      // the face through the seed: orbit of vertex 0 under W_K
+      let mut face = vec![0];
+      let mut queue = VecDeque::from(vec![0]);
+      while let Some(v) = queue.pop_front() {
+        for i in &nodes {
+          let w = perms[*i][v];
+          if !face.contains(&w) {
+            face.push(w);
+            queue.push_back(w);
+          }
+        }
+      }
+      face.sort();
+      // and its images under the whole group
+      if !found.insert(face.clone()) {
+        continue;
+      }
+      rank_faces.push(face.clone());
+      let mut queue = VecDeque::from(vec![face]);
+      while let Some(f) = queue.pop_front() {
+        for p in &perms {
+          let mut g: Vec<usize> = f.iter().map(|v| p[*v]).collect();
+          g.sort();
+          if found.insert(g.clone()) {
+            rank_faces.push(g.clone());
+            queue.push_back(g);
+          }
+        }
+      }
+    }
+    if rank_faces.is_empty() {
+      break;
+    }
+    faces.push(rank_faces);
+  }
+  Ok(Polytope { verts: verts.points, faces })
+}
+
+impl CoxGroup<f64> {
+  /// Face lattice of the Wythoffian polytope of the diagram
+  pub fn lattice(&self) -> Result<Hasse, Failed> {
+    Ok(wythoffian(&self.graph)?.hasse())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  fn lattice(s: &str) -> Hasse {
+    group(s).lattice().unwrap()
+  }
+
+  #[test]
+  fn face_counts() {
+    for (s, counts) in [
+      ("x4o3o", vec![8, 12, 6, 1]),
+      ("x3x3o", vec![12, 18, 8, 1]),
+      ("x3o4o3o", vec![24, 96, 96, 24, 1]),
+      ("x3o3o *b3o", vec![8, 24, 32, 16, 1]),
+      ("x5/2o3o", vec![20, 30, 12, 1]),
+      ("x3o3o5o", vec![120, 720, 1200, 600, 1]),
+      ("x3o o", vec![3, 3, 1]),
+    ] {
+      assert_eq!(lattice(s).counts(), counts, "{}", s);
+    }
+  }
+
+  #[test]
+  fn incidences() {
+    let cube = lattice("x4o3o");
+    assert!(cube.sub[1].iter().all(|e| e.len() == 2));
+    assert!(cube.sub[2].iter().all(|f| f.len() == 4));
+    assert!(cube.sup[0].iter().all(|v| v.len() == 3));
+    // every vertex is in 3 squares
+    assert_eq!(cube.incident(0, 0, 2).len(), 3);
+    assert_eq!(cube.incident(3, 0, 0).len(), 8);
+  }
+
+  #[test]
+  fn prism_of_rank_3() {
+    let p = group("x4o3o x").polytope().unwrap();
+    assert_eq!(p.faces.iter().map(|f| f.len()).collect::<Vec<_>>(), vec![16, 32, 24, 8, 1]);
+  }
+}
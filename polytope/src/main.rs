@@ -1,12 +1,10 @@
 #[macro_use]
 extern crate lazy_static;
 
-use petgraph::stable_graph::EdgeIndex;
 // use petgraph::adj::EdgeIndex;
 // use rusqlite::{Connection, Result};
-use regex::RegexSet;
 use fraction::{GenericFraction, ToPrimitive};
-use ndarray_linalg::Determinant;
+use ndarray_linalg::{Cholesky, Inverse, UPLO};
 
 use std::fmt::{Debug, Formatter, Display};
 use std::process::{Command, Stdio, ExitStatus};
@@ -15,15 +13,54 @@ use std::convert::From;
 use std::f64::consts::PI;
 
 use petgraph::graph::{UnGraph, NodeIndex, Node};
-use petgraph::algo::{is_isomorphic_subgraph_matching, subgraph_isomorphisms_iter};
+use petgraph::unionfind::UnionFind;
 
 // use crate::parse::parse;
 use crate::parse::Scanner;
 
 pub mod parse;
+pub mod wythoff;
+pub mod bracket;
+pub mod product;
+pub mod serialize;
+pub mod classify;
+pub mod invariants;
+pub mod coset;
+pub mod element;
+pub mod roots;
+pub mod interval;
+pub mod orbit;
+pub mod lattice;
+pub mod edges;
+pub mod snub;
+pub mod star;
+pub mod schwarz;
+pub mod uniform;
+pub mod polydb;
+pub mod dual;
+pub mod radius;
+pub mod volume;
+pub mod dihedral;
+pub mod figure;
+pub mod incidence;
 
 pub type CoxGraph<T> = UnGraph<CoxNode<T>, CoxEdge, u32>;
 
+/// Error of the computations on polytopes, the reason has already been printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Failed;
+
+/// So that `?` passes errors between functions returning `Failed` and those returning `()`
+impl From<()> for Failed {
+  fn from(_: ()) -> Failed {
+    Failed
+  }
+}
+
+impl From<Failed> for () {
+  fn from(_: Failed) {}
+}
+
 /// The set of irreducible discrete reflection-generated pointgroups of arbitrary dimension are known to be the following Coxeter groups. The left group names are according to Coxeter himself; in parantheses the slightly differing Lie group names are added.
 ///
 /// A(n)  (= An)    or  o3o...o3o                                                        (n>0 nodes)
@@ -46,20 +83,47 @@ pub type CoxGraph<T> = UnGraph<CoxNode<T>, CoxEdge, u32>;
 /// 
 /// G(4)  (= H4)    or  o3o3o5o
 #[derive(Debug, Clone, Default)]
-pub struct CoxGroup<'a, T: Clone>{
+pub struct CoxGroup<T: Clone>{
   graph: CoxGraph<T>,
-  polys: Vec<Polygon<'a, T>>,
+  polys: Vec<Polygon<T>>,
 }
 
+/// The 2-face generated by the mirrors of two nodes m1 and m2
+/// verts are the vertices in cyclic order, in cartesian coordinates
+/// m1_edges are the edges that are bisected by a copy of mirror m1, m2_edges those bisected by a copy of m2, as pairs of indices into verts
+/// A polygon from two unringed nodes is a single vertex, and from one ringed node and a node it is not connected to a single edge (a digon)
 #[derive(Debug, Clone)]
-pub struct Polygon<'a, T>{
-  m1: &'a CoxNode,
-  m2: &'a CoxNode,
-  verts: Vec<&'a ndarray::Array2<T>>,
+pub struct Polygon<T>{
+  m1: NodeIndex,
+  m2: NodeIndex,
+  verts: Vec<ndarray::Array1<T>>,
   m1_edges: Vec<(usize,usize)>,
   m2_edges: Vec<(usize,usize)>,
 }
 
+impl<T> Polygon<T> {
+  /// The two nodes whose mirrors generate the polygon
+  pub fn nodes(&self) -> (NodeIndex, NodeIndex) {
+    (self.m1, self.m2)
+  }
+
+  pub fn verts(&self) -> &[ndarray::Array1<T>] {
+    &self.verts
+  }
+
+  /// Edges bisected by copies of mirror m1 and of mirror m2
+  pub fn edges(&self) -> [&[(usize,usize)]; 2] {
+    [&self.m1_edges, &self.m2_edges]
+  }
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// The polygons made by `reflect_matrices`, empty before that
+  pub fn polygons(&self) -> &[Polygon<T>] {
+    &self.polys
+  }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum CoxEdge{
   Int(u32),
@@ -101,6 +165,13 @@ impl<T: Clone> Display for CoxNode<T> {
   }
 }
 
+impl<T: Clone> Display for CoxGroup<T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!("{} {}", self.dynkin(), self.bracket().unwrap_or("[?]".to_string())))?;
+    Ok(())
+  }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct CoxNode<T: Clone =f64>{
   name: char,
@@ -110,6 +181,60 @@ pub struct CoxNode<T: Clone =f64>{
   cart_vec: Option<ndarray::Array1<T>>,
 }
 
+/// Returns the mark between nodes `i` and `j`
+/// 2-edges are removed from the graph when parsing, so a missing edge is an implicit 2
+pub fn mark<T: Clone>(graph: &CoxGraph<T>, i: usize, j: usize) -> CoxEdge {
+  match graph.find_edge(NodeIndex::new(i), NodeIndex::new(j)) {
+    Some(e) => graph[e],
+    None => CoxEdge::Int(2),
+  }
+}
+
+/// Splits the graph into its connected components, as sorted lists of node indices
+/// Components are ordered by their smallest node
+pub fn components<T: Clone>(graph: &CoxGraph<T>) -> Vec<Vec<usize>> {
+  let mut uf = UnionFind::<usize>::new(graph.node_count());
+  for e in graph.edge_indices() {
+    let (a, b) = graph.edge_endpoints(e).unwrap();
+    uf.union(a.index(), b.index());
+  }
+  let mut comps: Vec<Vec<usize>> = Vec::new();
+  let mut roots: Vec<usize> = Vec::new();
+  for i in 0..graph.node_count() {
+    let r = uf.find(i);
+    match roots.iter().position(|x| *x == r) {
+      Some(c) => comps[c].push(i),
+      None => {
+        roots.push(r);
+        comps.push(vec![i]);
+      }
+    }
+  }
+  comps
+}
+
+/// Whether the nodes give a face of the polytope of the diagram: every component of their diagram has a ringed node
+/// `ringed` is asked about nodes of the whole graph, e.g. |i| graph[NodeIndex::new(i)].val != 0f64
+pub fn is_face<T: Clone>(graph: &CoxGraph<T>, nodes: &[usize], ringed: impl Fn(usize) -> bool) -> bool {
+  components(&subgraph(graph, nodes)).iter().all(|c| c.iter().any(|i| ringed(nodes[*i])))
+}
+
+/// Returns the subgraph on the given nodes, renumbered in the order of `nodes`
+pub fn subgraph<T: Clone>(graph: &CoxGraph<T>, nodes: &[usize]) -> CoxGraph<T> {
+  let mut sub = CoxGraph::<T>::default();
+  for i in nodes {
+    sub.add_node(graph[NodeIndex::new(*i)].clone());
+  }
+  for (a, i) in nodes.iter().enumerate() {
+    for (b, j) in nodes.iter().enumerate().skip(a+1) {
+      if let Some(e) = graph.find_edge(NodeIndex::new(*i), NodeIndex::new(*j)) {
+        sub.add_edge(NodeIndex::new(a), NodeIndex::new(b), graph[e]);
+      }
+    }
+  }
+  sub
+}
+
 // struct Frac<T>{
 //     sign: i8,
 //     p: T,
@@ -177,60 +302,6 @@ pub struct CoxNode<T: Clone =f64>{
 //     }
 // }
 
-// The set of irreducible discrete reflection-generated pointgroups of arbitrary dimension are known to be the following Coxeter groups. The left group names are according to Coxeter himself; in parantheses the slightly differing Lie group names are added.
-
-// A(n)  (= An)    or  o3o...o3o                                                        (n>0 nodes)
-// B(n)  (= Dn)    or  o3o...o3o *b3o      =  o3o...o3o *-c3o      =  o3o3o *b3o...o3o  (n>3 nodes)
-// C(n)  (= Bn)    or  o3o...o3o4o         =  o4o3o...o3o                               (n>1 nodes)
-// D(2)p (= I2(p)) or  oPo
-// E(6)  (= E6)    or  o3o3o3o3o *c3o      =  o3o3o3o3o *-d3o      =  o3o3o3o *b3o3o
-// E(7)  (= E7)    or  o3o3o3o3o3o *c3o    =  o3o3o3o3o3o *-d3o    =  o3o3o3o *b3o3o3o
-// E(8)  (= E8)    or  o3o3o3o3o3o3o *c3o  =  o3o3o3o3o3o3o *-d3o  =  o3o3o3o *b3o3o3o3o
-// F(4)  (= F4)    or  o3o4o3o
-// G(3)  (= H3)    or  o3o5o
-// G(4)  (= H4)    or  o3o3o5o
-fn check_input(haystack: &String) {
-  let patterns = [
-    // An
-    r"^(?:[\p{Letter}]+3)+[\p{Letter}]$",
-    // Dn
-    r"^(?:[\p{Letter}]+3)+[\p{Letter}]+[ ]?\*(?:b||-c)3[\p{Letter}]+$",
-    r"^[\p{Letter}]+3[\p{Letter}]+3[\p{Letter}]+[ ]?\*b(?:3[\p{Letter}]+)+$",
-    // Bn
-    r"^(?:[\p{Letter}]+3)+[\p{Letter}]+4[\p{Letter}]+$",
-    r"^[\p{Letter}]+4[\p{Letter}]+(?:3[\p{Letter}]+)+$",
-    // I2(p)
-    r"^[\p{Letter}]+[0-9]+[\p{Letter}]+$",
-    // E6
-    r"^[\p{Letter}]+(?:3[\p{Letter}]+){4}[ ]?\*(?:b||-c)3[\p{Letter}]+$",
-    // E7
-    r"^[\p{Letter}]+(?:3[\p{Letter}]+){5}[ ]?\*(?:b||-c)3[\p{Letter}]+$",
-    // E8
-    r"^[\p{Letter}]+(?:3[\p{Letter}]+){6}[ ]?\*(?:b||-c)3[\p{Letter}]+$",
-    // F4
-    r"^[\p{Letter}]+3[\p{Letter}]+4[\p{Letter}]+3[\p{Letter}]+$",
-    // H3
-    r"^[\p{Letter}]+3[\p{Letter}]+5[\p{Letter}]+$",
-    r"^[\p{Letter}]+5[\p{Letter}]+3[\p{Letter}]+$",
-    // H4
-    r"^[\p{Letter}]+3[\p{Letter}]+3[\p{Letter}]+5[\p{Letter}]+$",
-    r"^[\p{Letter}]+5[\p{Letter}]+3[\p{Letter}]+3[\p{Letter}]+$",
-  ];
-  let re_set = RegexSet::new(patterns).unwrap();
-  let matches: Vec<_> = re_set.matches(haystack).into_iter().collect();
-  println!("{:?}",matches);
-  // let re_an = Regex::new();
-  // let re_dn = Regex::new();
-  // let re_bn = Regex::new();
-  // // oPo
-  // let re_e6 = Regex::new();
-  // let re_e7 = Regex::new();
-  // let re_e8 = Regex::new();
-  // let re_f4 = Regex::new();
-  // let re_h3 = Regex::new();
-  // let re_h4 = Regex::new();
-}
-
 /// Initialize the first matrices for each node
 fn init_matrices<T: Clone + fraction::Zero + fraction::One>(symm: &mut CoxGraph<f64>) -> Result<(), ()>{
   // let mut nodes = symm.raw_nodes();
@@ -303,63 +374,92 @@ fn init_matrices<T: Clone + fraction::Zero + fraction::One>(symm: &mut CoxGraph<
 ///    ...repeat until P(ababab)+=P
 /// 2. this gives (non-lacing) edges for polygon ab
 /// 3. return coordinates in cartesian space
-#[allow(non_snake_case)]
-fn reflect_matrices<T>(symm: &mut CoxGraph<f64>) -> Result<(),()> {
-  let A = &symm[NodeIndex::new(0)].mats[0];
-  let B = &symm[NodeIndex::new(1)].mats[0];
-  let C = &symm[NodeIndex::new(2)].mats[0];
-
-  let n: &u32;
-  match symm.edge_weight(symm.find_edge(NodeIndex::new(0), NodeIndex::new(1)).unwrap_or(EdgeIndex::new(usize::MAX))) {
-    Some(a) => {
-      match a {
-        CoxEdge::Int(a) => {
-          n = a;
-        },
-        CoxEdge::Frac(a) => {
-          n = a.numer().unwrap();
+///
+/// This is done for every pair of nodes, in mirror space with the matrices of `init_matrices`, so these have to be there,
+/// and the cartesian coordinates come from the `cart_vec`s of `determine_cartesian`
+/// The polygons are stored in `polys`, one for every pair of nodes in the order (0,1), (0,2), ..., (1,2), ...
+fn reflect_matrices(group: &mut CoxGroup<f64>) -> Result<(),()> {
+  let symm = &group.graph;
+  let dims = symm.node_count();
+  let p = ndarray::Array1::from(symm.node_weights().map(|n| n.val).collect::<Vec<_>>());
+  // affine components have one dimension less in cartesian space than nodes
+  let cart_dims = symm.node_weights().next().and_then(|n| n.cart_vec.as_ref()).map_or(dims, |c| c.len());
+  let to_cartesian = |q: &ndarray::Array1<f64>| {
+    symm.node_weights().zip(q.iter()).fold(ndarray::Array1::<f64>::zeros(cart_dims), |acc, (n, v)| {
+      acc + n.cart_vec.as_ref().unwrap()*(*v)
+    })
+  };
+  let mut polys = Vec::new();
+  for a in 0..dims {
+    for b in a+1..dims {
+      let mats = [&symm[NodeIndex::new(a)].mats[0], &symm[NodeIndex::new(b)].mats[0]];
+      // vertices: p reflected in A and B until nothing new comes up
+      let mut verts = orbit::PointSet::default();
+      verts.insert(p.clone());
+      let mut i = 0;
+      while i < verts.len() {
+        for m in mats {
+          let q = m.dot(&verts.points[i]);
+          verts.insert(q);
+        }
+        i += 1;
+        if verts.len() > orbit::MAX_ORBIT {
+          println!("Polygon {}{} does not close", symm[NodeIndex::new(a)].name, symm[NodeIndex::new(b)].name);
+          return Err(());
         }
       }
-    },
-    None => {
-      n = &2;
-    }
-  }
-
-  let mut mats: Vec<Box<ndarray::Array2<f64>>> = Vec::new();
-  // Create polygon AB:
-  // reflect p in B
-  mats.push(Box::new(A.clone()));
-  mats.push(Box::new(B.clone()));
-  for i in 1..*n {
-    if i % 2 == 1 {
-      // reflect A in B
-      let Bi = &*mats[mats.len()-1];
-      let Ai = &*mats[mats.len()-2];
-      let Ai1= Box::new(Bi.dot(&Ai.dot(&*Bi)));
-      mats.push(Ai1);
-    } else {
-      // reflect B in A
-      let Ai = &*mats[mats.len()-1];
-      let Bi = &*mats[mats.len()-2];
-      let Bi1= Box::new(Ai.dot(&Bi.dot(Ai)));
-      mats.push(Bi1);
+      let perms: Vec<Vec<usize>> = mats.iter().map(|m| {
+        verts.points.iter().map(|q| verts.find(&m.dot(q)).unwrap()).collect()
+      }).collect();
+      // edges: from p to its image in a ringed mirror, and all images of that edge
+      let mut edges: Vec<Vec<(usize,usize)>> = vec![Vec::new(), Vec::new()];
+      for (k, node) in [a, b].iter().enumerate() {
+        if p[*node] == 0.0 {
+          continue;
+        }
+        let first = (0, perms[k][0]);
+        let mut queue = vec![first];
+        edges[k].push(first);
+        while let Some((u, v)) = queue.pop() {
+          for perm in &perms {
+            let e = (perm[u].min(perm[v]), perm[u].max(perm[v]));
+            if !edges[k].contains(&e) {
+              edges[k].push(e);
+              queue.push(e);
+            }
+          }
+        }
+      }
+      // walk along the edges to put the vertices in cyclic order
+      let all: Vec<(usize,usize)> = edges.concat();
+      let mut cycle = vec![0];
+      loop {
+        let last = *cycle.last().unwrap();
+        let next = all.iter()
+          .filter_map(|(u, v)| if *u == last { Some(*v) } else if *v == last { Some(*u) } else { None })
+          .find(|w| !cycle.contains(w));
+        match next {
+          Some(w) => cycle.push(w),
+          None => break,
+        }
+      }
+      let index = |v: usize| cycle.iter().position(|w| *w == v).unwrap();
+      let renumber = |es: &Vec<(usize,usize)>| es.iter().map(|(u, v)| (index(*u), index(*v))).collect();
+      polys.push(Polygon {
+        m1: NodeIndex::new(a),
+        m2: NodeIndex::new(b),
+        verts: cycle.iter().map(|v| to_cartesian(&verts.points[*v])).collect(),
+        m1_edges: renumber(&edges[0]),
+        m2_edges: renumber(&edges[1]),
+      });
     }
-
-    // println!("p: {:?}, i: {:?}, mats: {:?}", n,i, mats);
-  }
-  if mats[0].abs_diff_eq( &mats[mats.len()-1], 1e-14) {
-    println!("Yay! we're in some poly thingy");
-
-  } else {
-    println!("Nay! we're not in some poly thingy {:?}", (*mats[0]).clone()-(*mats[mats.len()-1]).clone());
   }
+  group.polys = polys;
   Ok(())
 }
 
-/// Determines the basis vectors for a symmetry. Derived from exact_cartesian.pdf
-/// First checks isomorphism with part of the graph that corresponds to either cubic, icosahedral or tetrahedal symmetry
-/// Then determines basis vectors for each of the nodes. E.g.
+/// Basis vectors of the rank 3 groups with marks p and 3 for p = 3, 4, 5, in the orientation derived in exact_cartesian.pdf,
+/// for the chain a-p-b-3-c, with a the end of the lower node index if p = 3:
 /// x4o3o -> (1/2,1/2,1/2) (cube)
 /// o4x3o -> (1/√2,0,1/√2) (cuboctahedron)
 /// o4o3x -> (0,0,1/√2)    (octahedron)
@@ -367,153 +467,112 @@ fn reflect_matrices<T>(symm: &mut CoxGraph<f64>) -> Result<(),()> {
 /// x5o3o -> (1/2,0,φ²/2)       (dodecahedron)
 /// o5x3o -> (0,0,φ)  (icosidodecahedron)
 /// o5o3x -> (0, 1/2, φ/2) (icosahedron)
-/// 
+///
 /// x3o3o -> (0,1/2,√2/4) (tet)
 /// o3x3o -> (0,0,√2/2)   (oct)
 /// o3o3x -> (1/2,0,√2/4) (tet)
-/// One could ask, why not directly closed-form exact expressions?
-/// Because there is the secret hope of being able to derive cartesian coordinates for higher-dimensional polytopes as well
-/// But well... it's taken some time up to now...
-fn determine_cartesian_3d(symm:& mut CoxGraph<f64>) -> Result<(), ()> {
-  // let dims=symm.node_count();
-  // assert_eq!(symm.node_count(),3);
-  // derivations are in exact_cartesian.pdf
-  /* reference is x4o3o -> a4b3c -> (.5,.5,.5)
-    A B C
-    o4x3o -> (1/sqrt(2),0,1/sqrt(2))
-    o4o3x -> (0,0,1/sqrt(2))
-     | |
-     r q p=2
-    x2o5o*a3*c
-    Q P R
-    x2o3o*a3*c
-
-     p q r=2
-    o3o5o
-    o3o3o
-
-    o3o4o
-     q r p=2
-   */
-  //  p q r=2
-  // a1b2c
-  // Q R P
-  //  r q p=2
-  // o3o4o
-  // Q P R
-  // [1,2,0]
-  //  p q   r
-  // o2o3o*a4*c
-  // Q R P
-  let cubic:&CoxGraph<f64> = &UnGraph::from_edges(&[(1,2,CoxEdge::Int(3)),(0,2,CoxEdge::Int(4))]);
-  //  p q r=2
-  // o3o3o
-  // Q R P
-  // [2,0,1]
-  let tetrahedal:& CoxGraph<f64> = &UnGraph::from_edges(&[(0,1,CoxEdge::Int(3)),(1,2,CoxEdge::Int(3))]);
-  //  p q r=2
-  // o3o5o
-  // Q R P
-  // [2,0,1]
-  let icosahedral:& CoxGraph<f64> = &UnGraph::from_edges(&[(0,1,CoxEdge::Int(3)),(1,2,CoxEdge::Int(5))]);
-
-  let mut n_eq = |_: &_,_: &_| true;
-  let mut e_eq = |a: &_,b: &_| a==b;
-
-  // non-mutable reference
-  let g1 = &*symm;
-  
-  // (sub)symmetry
-  let the_symm: &CoxGraph::<f64>;
-  // isomorphism mapping
-  let is: Vec<usize>;
-  // edge values
-  // let p: f64; let q: f64; let r: f64;
-  if is_isomorphic_subgraph_matching(&cubic, &*symm, n_eq, e_eq) {
-    println!("Cubic subsymmetry!");
-    // p=2, q=3, r=4
-    // Find the isomorphism
-    let mut it = subgraph_isomorphisms_iter(&cubic, &g1, &mut n_eq, &mut e_eq).unwrap();
-    is = it.next().unwrap();
-    the_symm = cubic;
-  } else if is_isomorphic_subgraph_matching(&tetrahedal, &*symm, |_,_| true, |a,b| a==b) {
-    
-    // p=q=3, r=2
-    let mut it = subgraph_isomorphisms_iter(&tetrahedal, &g1, &mut n_eq, &mut e_eq).unwrap();
-    is = it.next().unwrap();
-    the_symm = tetrahedal;
-    println!("Tet subsymmetry {:?}", is);
-  } else if is_isomorphic_subgraph_matching(&icosahedral, &*symm, |_,_| true, |a,b| a==b) {
-    // p=3, q=5, r=2
-    println!("ico subsymmetry");
-    let mut it = subgraph_isomorphisms_iter(&icosahedral, &g1, &mut n_eq, &mut e_eq).unwrap();
-    is = it.next().unwrap();
-    the_symm = icosahedral;
-  } else {
-    println!("Unknown subsymmetry");
-    is = vec![0,1,2];
-    the_symm = symm;
+/// So the cube has its vertices at (±1/2,±1/2,±1/2), and the icosahedron at (0,±1/2,±φ/2) and its cyclic permutations
+/// Returns the vectors as rows in the order of `nodes`, None if the nodes are not such a chain
+fn canonical_3d(symm: &CoxGraph<f64>, nodes: &[usize]) -> Option<ndarray::Array2<f64>> {
+  if nodes.len() != 3 {
+    return None;
+  }
+  let m = |i: usize, j: usize| mark(symm, nodes[i], nodes[j]);
+  // the middle node is linked to both others
+  let b = (0..3).find(|k| (0..3).all(|l| l == *k || m(*k, l) != CoxEdge::Int(2)))?;
+  let (e1, e2) = ((b + 1) % 3, (b + 2) % 3);
+  let (e1, e2) = if nodes[e1] < nodes[e2] { (e1, e2) } else { (e2, e1) };
+  let (a, c) = if m(b, e1) == CoxEdge::Int(3) && m(b, e2) != CoxEdge::Int(3) { (e2, e1) } else { (e1, e2) };
+  if m(a, c) != CoxEdge::Int(2) || m(b, c) != CoxEdge::Int(3) {
+    return None;
+  }
+  let (phi, q) = ((1f64 + 5f64.sqrt())/2f64, 2f64.sqrt());
+  let vecs = match m(a, b) {
+    CoxEdge::Int(3) => [[0f64, 0.5, q/4f64], [0f64, 0f64, q/2f64], [0.5, 0f64, q/4f64]],
+    CoxEdge::Int(4) => [[0.5, 0.5, 0.5], [1f64/q, 0f64, 1f64/q], [0f64, 0f64, 1f64/q]],
+    CoxEdge::Int(5) => [[0.5, 0f64, phi*phi/2f64], [0f64, 0f64, phi], [0f64, 0.5, phi/2f64]],
+    _ => return None,
+  };
+  let mut basis = ndarray::Array2::<f64>::zeros((3, 3));
+  for (k, v) in [a, b, c].iter().zip(vecs) {
+    basis.row_mut(*k).assign(&ndarray::Array1::from(v.to_vec()));
   }
+  Some(basis)
+}
 
-  // first, construct the H² matrix
-  let p = f64::from(the_symm.edge_weight(the_symm.find_edge(0.into(), 1.into()).unwrap_or(u32::MAX.into())).unwrap_or(&CoxEdge::Int(2)));
-  let q = f64::from(the_symm.edge_weight(the_symm.find_edge(1.into(), 2.into()).unwrap_or(u32::MAX.into())).unwrap_or(&CoxEdge::Int(2)));
-  let r = f64::from(the_symm.edge_weight(the_symm.find_edge(0.into(), 2.into()).unwrap_or(u32::MAX.into())).unwrap_or(&CoxEdge::Int(2)));
-  // pre-determine cp := cos(𝛑/p), sp := sin(𝛑/p) etc.
-  let cp = (PI/p).cos(); let cq = (PI/q).cos(); let cr = (PI/r).cos();
-  let sp = (PI/p).sin(); let sq = (PI/q).sin(); let sr = (PI/r).sin();
-
-  #[allow(non_snake_case)]
-  let H = ndarray::Array2::<f64>::from(vec!
-  [
-    [1.0,-cq,-cr],
-    [-cq, 1.0, -cp],
-    [-cr, -cp, 1.0]
-  ]).det().unwrap_or(0.0).sqrt();
-  if true {//H.is_nan() {
-    println!("{:?},{:?},{:?},{:?},{:?}",cq,cr,cp,(1f64-cp*cp)+cq*(-cq-cp*cr)-cr*(cp*cq+cr), H);
-    // return Err(());
+/// Determines the basis vectors for a symmetry of any rank from the Gram matrix of the mirror normals
+/// The basis vector of a node lies on all other mirrors, at distance 1/2 from its own mirror, so that x gives edges of length 1
+/// With the unit normals n_j as rows of N, where G = N Nᵀ (Cholesky), that is the dual basis V = G⁻¹N/2, since V Nᵀ = I/2
+/// The components are orthogonal, so each one gets its own block of coordinates in the order of `components`
+/// Within a block the first mirror is x=0, the second one is in the xy-plane etc., except for the rank 3 groups of
+/// `canonical_3d`, which keep the orientation of exact_cartesian.pdf, e.g. the cube at (±1/2,±1/2,±1/2)
+/// For an affine component the last node whose removal leaves a finite group is the affine node a,
+/// the others get the dual basis of the finite group, one dimension less than the number of nodes
+/// With c the null vector of the Gram matrix, c_a = 1, the affine normal is n_a = -Σc_jn_j, and the affine mirror of a seed
+/// with node values v lies at n_a·x = -(Σc_kv_k)/2, so that the seed is at distance v_a/2 from it
+/// The block is then shifted by (Σc_kv_k)/2·n_a, which puts the affine mirror through the origin and keeps the seed linear in v:
+/// the affine node gets n_a/2, a finite node j the vector of its finite group plus c_j/2·n_a
+/// e.g. x4o3o -> |a| = √3/2 (cube), x3o3o5o -> |a| = φ (600-cell), o4o3o4x -> |d| = 1/2
+fn determine_cartesian(symm: &mut CoxGraph<f64>) -> Result<(), ()> {
+  let comps = components(symm);
+  let mut blocks: Vec<(Vec<usize>, Option<usize>)> = Vec::new();
+  for comp in comps {
+    let sub = subgraph(symm, &comp);
+    let (_, zero, neg) = classify::signature(&classify::gram(&sub));
+    match (zero, neg) {
+      (0, 0) => blocks.push((comp, None)),
+      (1, 0) => {
+        let affine = (0..comp.len()).rev().find(|k| {
+          let rest: Vec<usize> = (0..comp.len()).filter(|i| i != k).collect();
+          classify::signature(&classify::gram(&subgraph(&sub, &rest))).1 == 0
+        });
+        match affine {
+          Some(k) => blocks.push((comp.clone(), Some(comp[k]))),
+          None => {
+            println!("No affine node in {:?}", comp);
+            return Err(());
+          }
+        }
+      },
+      _ => {
+        println!("No basis vectors for the hyperbolic component {:?}", comp);
+        return Err(());
+      }
+    }
+  }
+  let dims: usize = blocks.iter().map(|(c, a)| c.len() - a.iter().count()).sum();
+  let full = classify::gram(symm);
+  let mut offset = 0;
+  for (comp, affine) in blocks {
+    let finite: Vec<usize> = comp.iter().cloned().filter(|i| Some(*i) != affine).collect();
+    let g = classify::gram(&subgraph(symm, &finite));
+    let mut basis = match canonical_3d(symm, &finite) {
+      Some(b) => b,
+      None => g.inv().map_err(|_| ())?.dot(&g.cholesky(UPLO::Lower).map_err(|_| ())?)/2f64,
+    };
+    if let Some(a) = affine {
+      // c_j = -(G⁻¹g)_j with g_j = n_j·n_a, and N = 2GV
+      let ga = ndarray::Array1::from(finite.iter().map(|j| full[[*j, a]]).collect::<Vec<_>>());
+      let c = -g.inv().map_err(|_| ())?.dot(&ga);
+      let n_a = -c.dot(&(g.dot(&basis)*2f64));
+      for (k, cj) in c.iter().enumerate() {
+        let row = &basis.row(k) + &(&n_a*(cj/2f64));
+        basis.row_mut(k).assign(&row);
+      }
+      let mut v = ndarray::Array1::<f64>::zeros(dims);
+      v.slice_mut(ndarray::s![offset..offset+finite.len()]).assign(&(&n_a/2f64));
+      symm[NodeIndex::new(a)].cart_vec = Some(v);
+    }
+    for (k, i) in finite.iter().enumerate() {
+      let mut v = ndarray::Array1::<f64>::zeros(dims);
+      v.slice_mut(ndarray::s![offset..offset+finite.len()]).assign(&basis.row(k));
+      symm[NodeIndex::new(*i)].cart_vec = Some(v);
+    }
+    offset += finite.len();
   }
-  println!("p: {:?}, q: {:?}, r: {:?}, H: {:?}", p,q,r,H);
-  
-  #[allow(non_snake_case)]
-  let P = ndarray::Array1::from(vec![
-    H/(sr*sp),
-    0.0,
-    (cq+cr*cp)/(sr*sp),
-  ]);
-  #[allow(non_snake_case)]
-  let Q = ndarray::Array1::from(vec![
-    H/(PI/r).tan(),
-    H,
-    (cp+cq*cr)/sr,
-  ]) / sq;
-  #[allow(non_snake_case)]
-  let R = ndarray::Array1::from(vec![
-    0f64,
-    0f64,
-    1f64,
-  ]);
-
-  // Normal vectors to planes
-  let n_qr = ndarray::Array1::from(vec![sr,-cr,0f64]);
-  let n_pr = ndarray::Array1::from(vec![0f64,1f64,0f64]);
-  let n_pq = ndarray::Array1::from(vec![(-cq-cp*cr)/sr,-cp,H/sr]);
-  
-  // So we are working on the_symm, now it is time to map the obtained cartesian coords into the provided symmetry
-  // 
-  symm[NodeIndex::new(is[0])].cart_vec = Some(Q.clone()/Q.dot(&n_pr)/2f64);
-  symm[NodeIndex::new(is[1])].cart_vec = Some(R.clone()/R.dot(&n_pq)/2f64);
-  symm[NodeIndex::new(is[2])].cart_vec = Some(P.clone()/P.dot(&n_qr)/2f64);
-  
-  println!("{:?}?{:?}?{:?}",
-    symm[NodeIndex::new(0)].cart_vec,
-    symm[NodeIndex::new(1)].cart_vec,
-    symm[NodeIndex::new(2)].cart_vec);
   Ok(())
 }
 
-
 fn print_svg<N: std::fmt::Display,E: std::fmt::Display>(graph: &UnGraph<N, E>) -> ExitStatus{
   let status = Command::new("dot")
     .arg("-Tsvg")  // Specify the output format (SVG)
@@ -535,6 +594,9 @@ fn main() -> Result<(),()>{
   // let mut sc = Scanner::new("x4o3o");
   let mut sc = Scanner::new("x6f2o");
   let mut group: CoxGroup<f64> = sc.parse_tope::<f64>().unwrap();
+  println!("{}", group);
+  println!("{}", group.classify());
+  println!("{:?}", group.invariants());
   println!("{:?}", petgraph::dot::Dot::new(&group.graph));
   println!("{:?}", ndarray::Array2::from(Vec::from(&[[1,2,3],[4,5,6]])));
   // group.build_symmetry();
@@ -543,9 +605,11 @@ fn main() -> Result<(),()>{
 
   println!("{:?}", petgraph::dot::Dot::new(&group.graph));
   print_svg(&group.graph);
-  determine_cartesian_3d(&mut group.graph)?;
+  determine_cartesian(&mut group.graph)?;
+  let tope = group.polytope()?;
+  println!("{} vertices, {:?} faces", tope.verts.len(), tope.faces.iter().map(|f| f.len()).collect::<Vec<_>>());
 
-  reflect_matrices::<f64>(&mut group.graph)?;
+  reflect_matrices(&mut group)?;
   println!("{:?}", f64::try_from(GenericFraction::<u32>::new(1u8,2u8)));
   // iso_map(&mut group.graph)?;
   // conn.execute(
@@ -605,3 +669,94 @@ fn main() -> Result<(),()>{
 //     println!("Foo + Bar = {:?}", Foo + Bar);
 //     println!("Bar + Foo = {:?}", Bar + Foo);
 // }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn basis(s: &str) -> Vec<ndarray::Array1<f64>> {
+    let mut sc = Scanner::new(s);
+    let mut group = sc.parse_tope::<f64>().unwrap();
+    determine_cartesian(&mut group.graph).unwrap();
+    group.graph.node_weights().map(|n| n.cart_vec.clone().unwrap()).collect()
+  }
+
+  fn norm(v: &ndarray::Array1<f64>) -> f64 {
+    v.dot(v).sqrt()
+  }
+
+  #[test]
+  fn cartesian_any_rank() {
+    // cube, cuboctahedron, octahedron
+    let b = basis("x4o3o");
+    assert!((norm(&b[0]) - 3f64.sqrt()/2f64).abs() < 1e-9);
+    assert!((norm(&b[1]) - 1f64).abs() < 1e-9);
+    assert!((norm(&b[2]) - 0.5f64.sqrt()).abs() < 1e-9);
+    // 600-cell has circumradius φ
+    let phi = (1f64 + 5f64.sqrt())/2f64;
+    assert!((norm(&basis("x3o3o5o")[0]) - phi).abs() < 1e-9);
+    // 2_21 has circumradius √(2/3)
+    assert!((norm(&basis("x3o3o3o3o *c3o")[0]) - (2f64/3f64).sqrt()).abs() < 1e-9);
+    // duoprism: two orthogonal blocks
+    let b = basis("x4o x3o");
+    assert_eq!(b[0].len(), 4);
+    assert!(b[0].dot(&b[2]).abs() < 1e-9);
+    // the orientation of exact_cartesian.pdf
+    assert!(basis("x4o3o")[0].abs_diff_eq(&ndarray::Array1::from(vec![0.5, 0.5, 0.5]), 1e-12));
+    let phi = (1f64 + 5f64.sqrt())/2f64;
+    assert!(basis("o5o3x")[2].abs_diff_eq(&ndarray::Array1::from(vec![0f64, 0.5, phi/2f64]), 1e-12));
+  }
+
+  #[test]
+  fn cartesian_affine() {
+    let seed = |s: &str| {
+      let mut sc = Scanner::new(s);
+      let mut group = sc.parse_tope::<f64>().unwrap();
+      determine_cartesian(&mut group.graph).unwrap();
+      let normals = orbit::normals(&group.graph);
+      (orbit::seed(&group.graph), normals)
+    };
+    // only the affine node ringed: half its normal, at distance 1/2 from the affine mirror through the origin
+    let (s, n) = seed("o4o3o4x");
+    assert_eq!(s.len(), 3);
+    assert!((n[3].dot(&s) - 0.5).abs() < 1e-9 && (s.dot(&s) - 0.25).abs() < 1e-9);
+    // a ringed affine node moves the seed
+    let (s, n) = seed("x4o3o4x");
+    assert!(!s.abs_diff_eq(&seed("x4o3o4o").0, 1e-9));
+    assert!((n[3].dot(&s) - 0.5).abs() < 1e-9);
+    // the finite mirrors keep their angles with the affine one
+    assert!((n[2].dot(&n[3]) + 0.5f64.sqrt()).abs() < 1e-9 && n[0].dot(&n[3]).abs() < 1e-9);
+  }
+
+  fn polygons(s: &str) -> Vec<Polygon<f64>> {
+    let mut sc = Scanner::new(s);
+    let mut group = sc.parse_tope::<f64>().unwrap();
+    init_matrices::<f64>(&mut group.graph).unwrap();
+    determine_cartesian(&mut group.graph).unwrap();
+    reflect_matrices(&mut group).unwrap();
+    group.polys
+  }
+
+  #[test]
+  fn polygons_of_all_pairs() {
+    let ps = polygons("x4x3o");
+    assert_eq!(ps.len(), 3);
+    // octagon, digon and triangle
+    assert_eq!((ps[0].verts.len(), ps[0].m1_edges.len(), ps[0].m2_edges.len()), (8, 4, 4));
+    assert_eq!((ps[1].verts.len(), ps[1].m1_edges.len()), (2, 1));
+    assert_eq!((ps[2].verts.len(), ps[2].m2_edges.len()), (3, 0));
+    let ps = polygons("x5/2o3o");
+    assert_eq!(ps[2].verts.len(), 1);
+    // pentagram, consecutive vertices at distance 1
+    let star = &ps[0];
+    assert_eq!(star.verts.len(), 5);
+    for i in 0..5 {
+      assert!((norm(&(&star.verts[i] - &star.verts[(i + 1) % 5])) - 1f64).abs() < 1e-9);
+    }
+    // the square tiling: squares in the plane, as the affine component has one dimension less
+    let ps = polygons("x4o4o");
+    assert_eq!(ps.iter().map(|p| p.verts.len()).collect::<Vec<_>>(), vec![4, 2, 1]);
+    assert_eq!(ps[0].verts[0].len(), 2);
+    assert!((norm(&(&ps[0].verts[0] - &ps[0].verts[1])) - 1f64).abs() < 1e-9);
+  }
+}
@@ -0,0 +1,191 @@
+use std::collections::{HashMap, VecDeque};
+
+use ndarray::Array1;
+use petgraph::graph::NodeIndex;
+
+use crate::classify::{gram, signature};
+use crate::{determine_cartesian, CoxGraph, CoxGroup, Failed};
+
+/// Points closer than about 1/SCALE are considered equal
+const SCALE: f64 = 1e6;
+
+/// Gives up on closing an orbit after this many points, the group is then infinite or too large
+pub const MAX_ORBIT: usize = 1_000_000;
+
+/// A set of points with deduplication up to rounding
+/// Points are hashed on their coordinates rounded to 1/SCALE; a coordinate that is close to halfway
+/// between two grid values is looked up on both sides, so nearly equal points never end up in different cells
+#[derive(Debug, Clone, Default)]
+pub struct PointSet {
+  pub points: Vec<Array1<f64>>,
+  map: HashMap<Vec<i64>, usize>,
+}
+
+impl PointSet {
+  /// Index of `p` in the set, if it is there
+  pub fn find(&self, p: &Array1<f64>) -> Option<usize> {
+    let mut keys: Vec<Vec<i64>> = vec![Vec::new()];
+    for x in p.iter() {
+      let s = x*SCALE;
+      let r = s.round();
+      let mut options = vec![r as i64];
+      if (s - r).abs() > 0.4 {
+        options.push(if s > r { r as i64 + 1 } else { r as i64 - 1 });
+      }
+      keys = keys.into_iter().flat_map(|k| options.iter().map(move |o| {
+        let mut k = k.clone();
+        k.push(*o);
+        k
+      })).collect();
+    }
+    keys.iter().find_map(|k| self.map.get(k).cloned())
+  }
+
+  /// Index of `p`, adding it if it is new, and whether it was new
+  pub fn insert(&mut self, p: Array1<f64>) -> (usize, bool) {
+    match self.find(&p) {
+      Some(i) => (i, false),
+      None => {
+        let key = p.iter().map(|x| (x*SCALE).round() as i64).collect();
+        self.map.insert(key, self.points.len());
+        self.points.push(p);
+        (self.points.len() - 1, true)
+      }
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.points.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.points.is_empty()
+  }
+}
+
+/// Reflection of p in the mirror through the origin with unit normal n
+pub fn reflect(p: &Array1<f64>, n: &Array1<f64>) -> Array1<f64> {
+  p - &(n*(2f64*n.dot(p)))
+}
+
+/// Closes the point under the reflections in the mirrors with the given unit normals, starting with the point itself
+/// None if there are more than `MAX_ORBIT` points
+pub fn orbit(point: Array1<f64>, normals: &[Array1<f64>]) -> Option<PointSet> {
+  let mut set = PointSet::default();
+  set.insert(point);
+  let mut queue = VecDeque::from(vec![0]);
+  while let Some(i) = queue.pop_front() {
+    for n in normals {
+      let (j, new) = set.insert(reflect(&set.points[i], n));
+      if new {
+        if set.len() > MAX_ORBIT {
+          return None;
+        }
+        queue.push_back(j);
+      }
+    }
+  }
+  Some(set)
+}
+
+/// Unit mirror normals from the basis vectors of `determine_cartesian`, which are the dual basis: N = 2GV
+pub fn normals(symm: &CoxGraph<f64>) -> Vec<Array1<f64>> {
+  let g = gram(symm);
+  let n = symm.node_count();
+  (0..n).map(|i| {
+    (0..n).fold(Array1::zeros(symm[NodeIndex::new(0)].cart_vec.as_ref().unwrap().len()), |acc, j| {
+      acc + symm[NodeIndex::new(j)].cart_vec.as_ref().unwrap()*(2f64*g[[i, j]])
+    })
+  }).collect()
+}
+
+/// The seed point: the sum of the basis vectors weighted by the node values
+pub fn seed(symm: &CoxGraph<f64>) -> Array1<f64> {
+  symm.node_weights().fold(Array1::zeros(symm[NodeIndex::new(0)].cart_vec.as_ref().unwrap().len()), |acc, node| {
+    acc + node.cart_vec.as_ref().unwrap()*node.val
+  })
+}
+
+impl CoxGroup<f64> {
+  /// Vertices of the Wythoffian polytope: the orbit of the seed point under the reflection group,
+  /// in the orientation of `determine_cartesian`, with the seed first
+  /// Rank 3 groups with marks 4 3, 5 3 and 3 3 come out in the frame of exact_cartesian.pdf, e.g. the cube at (±1/2,±1/2,±1/2)
+  /// Only for finite groups; rational marks are fine as long as the mirrors generate a finite group
+  /// e.g. x3o3o5o -> 120 vertices, x5/2o3o -> 20 vertices
+  pub fn vertices(&self) -> Result<Vec<Array1<f64>>, Failed> {
+    let mut symm = self.graph.clone();
+    if symm.node_count() == 0 {
+      return Ok(vec![Array1::zeros(0)]);
+    }
+    let (_, zero, neg) = signature(&gram(&symm));
+    if zero + neg > 0 {
+      println!("The orbit of a non-spherical group is infinite");
+      return Err(Failed);
+    }
+    determine_cartesian(&mut symm)?;
+    match orbit(seed(&symm), &normals(&symm)) {
+      Some(set) => Ok(set.points),
+      None => {
+        println!("More than {} vertices", MAX_ORBIT);
+        Err(Failed)
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  fn verts(s: &str) -> Vec<Array1<f64>> {
+    group(s).vertices().unwrap()
+  }
+
+  #[test]
+  fn vertex_counts() {
+    for (s, n) in [("x3o3o5o", 120), ("x5o3o3o", 600), ("x3x3o", 12), ("o4x3o", 12), ("x4o x3o", 12),
+                   ("x5/2o3o", 20), ("x3o5/2o", 12), ("x5o5/2o", 12), ("x3o3o3o3o *c3o", 27), ("o x", 2)] {
+      assert_eq!(verts(s).len(), n, "{}", s);
+    }
+  }
+
+  #[test]
+  fn unit_edges() {
+    // the shortest distance between vertices of a uniform polytope is the edge length 1
+    for s in ["x4o3o", "x3x3o", "x5o3o3o", "x3o3o *b3o", "x4x3x"] {
+      let v = verts(s);
+      let min = v.iter().flat_map(|a| v.iter().map(move |b| (a - b).dot(&(a - b)).sqrt()))
+        .filter(|d| *d > 1e-9)
+        .fold(f64::MAX, f64::min);
+      assert!((min - 1f64).abs() < 1e-9, "{}: {}", s, min);
+    }
+    let v = verts("x4o3o");
+    assert!(v.iter().all(|p| (p.dot(p) - 0.75).abs() < 1e-9));
+  }
+
+  #[test]
+  fn canonical_frame() {
+    let cube = verts("x4o3o");
+    assert!(cube[0].abs_diff_eq(&Array1::from(vec![0.5, 0.5, 0.5]), 1e-12));
+    let signs: std::collections::HashSet<Vec<bool>> = cube.iter().map(|v| {
+      assert!(v.iter().all(|x| (x.abs() - 0.5).abs() < 1e-12), "{:?}", v);
+      v.iter().map(|x| *x > 0f64).collect()
+    }).collect();
+    assert_eq!(signs.len(), 8);
+    // icosahedron: cyclic permutations of (0,±1/2,±φ/2)
+    let phi = (1f64 + 5f64.sqrt())/2f64;
+    for v in verts("o5o3x") {
+      let mut a: Vec<f64> = v.iter().map(|x| x.abs()).collect();
+      a.sort_by(|a, b| a.partial_cmp(b).unwrap());
+      assert!(a[0] < 1e-12 && (a[1] - 0.5).abs() < 1e-12 && (a[2] - phi/2f64).abs() < 1e-12, "{:?}", v);
+    }
+  }
+
+  #[test]
+  fn near_boundary() {
+    let mut set = PointSet::default();
+    set.insert(Array1::from(vec![0.5e-6 - 1e-12]));
+    assert_eq!(set.insert(Array1::from(vec![0.5e-6 + 1e-12])), (0, false));
+  }
+}
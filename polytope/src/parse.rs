@@ -2,7 +2,6 @@ use core::fmt;
 use std::fmt::Debug;
 use crate::{CoxNode, CoxEdge, CoxGraph, CoxGroup};
 
-use fraction::GenericFraction;
 use petgraph::prelude::NodeIndex;
 // use petgraph::data::Build;
 
@@ -11,7 +10,8 @@ use std::clone::Clone;
 
 
 /// Minimal parser based on the following BNF:
-/// <tope> ::= <subtope> [<branch>]
+/// <tope> ::= <factor> {" " <factor>}
+/// <factor> ::= <subtope> {<branch>}
 /// <subtope> ::= <node> {<edge> <node>}
 /// <node> ::= [a-z] | "(" <bla ")"
 /// <complexNode> ::= "(" <bla> ")"
@@ -19,7 +19,7 @@ use std::clone::Clone;
 /// <nNode> ::= "-" <pNode>
 /// <pNode> ::= [a-z]+
 /// <edge> ::= [0-9]+ | <rationalEdge>
-/// <rationalEdge> ::= "(" [0-9]+ "/" [0-9]+ ")"
+/// <rationalEdge> ::= [0-9]+ "/" [0-9]+
 ///
 /// <branch> ::= <brSep> <brIndex> | <brSep> <brIndex> <edge> <brSep> <brIndex> | <brSep> <brIndex> <edgeNode>
 /// <brSep> ::= " *" | "*"
@@ -56,7 +56,7 @@ impl SyntaxError {
   //     }
   // }
 
-  fn new_parse_error(message: String) -> Self {
+  pub(crate) fn new_parse_error(message: String) -> Self {
     SyntaxError {
       message,
       level: "Parse".to_string(),
@@ -73,6 +73,22 @@ impl fmt::Display for SyntaxError {
 impl Error for SyntaxError {}
 //---------------------end
 
+/// Appends the nodes and edges of `other` to `graph`, without connecting them
+fn append<T: Clone>(graph: &mut CoxGraph<T>, other: CoxGraph<T>) {
+  let offset = graph.node_count();
+  let (nodes, edges) = other.into_nodes_edges();
+  for n in nodes {
+    graph.add_node(n.weight);
+  }
+  for e in edges {
+    graph.add_edge(
+      NodeIndex::new(e.source().index() + offset),
+      NodeIndex::new(e.target().index() + offset),
+      e.weight,
+    );
+  }
+}
+
 #[derive(Debug)]
 pub struct Scanner{
   cur: usize,
@@ -80,6 +96,12 @@ pub struct Scanner{
   node_index: char,
 }
 
+/// The group of a Dynkin string with float node values, for the tests of the other modules
+#[cfg(test)]
+pub(crate) fn group(s: &str) -> CoxGroup<f64> {
+  Scanner::new(s).parse_tope::<f64>().unwrap()
+}
+
 impl Scanner {
   pub fn new(string: &str) -> Self {
     Self {
@@ -102,14 +124,33 @@ impl Scanner {
 
   /// Checks for a complete polytope
   /// e.g. x3o3o *b3o
+  /// Space-separated factors are a direct product, e.g. x4o x3o is the same graph as x4o2x3o
   /// returns a graph
   fn tope<T: Debug + Clone+ From<f64>>(&mut self) -> Result<CoxGraph<T>, SyntaxError> {
     println!("Parsing tope! {:?}", self);
-    let mut tope = self.subtope::<T>()?;
-    println!("Parsing tope branch! {:?}", self);
-    self.branch(&mut tope)?;
+    let mut tope = self.factor::<T>()?;
+    loop {
+      // branch() may already have taken the space between factors
+      self.take(&' ');
+      match self.peek() {
+        Some(c) if c.is_alphabetic() || c == '(' => {
+          let factor = self.factor::<T>()?;
+          append(&mut tope, factor);
+        },
+        _ => break,
+      }
+    }
     Ok(tope)
   }
+
+  /// Checks for a connected part of a polytope, branch indices are relative to the factor
+  /// e.g. x3o3o *b3o
+  fn factor<T: Debug + Clone+ From<f64>>(&mut self) -> Result<CoxGraph<T>, SyntaxError> {
+    let mut factor = self.subtope::<T>()?;
+    println!("Parsing tope branch! {:?}", self);
+    while self.branch(&mut factor)? {}
+    Ok(factor)
+  }
   
   /// Checks for a sub-polytope
   /// e.g. x4o3o3o
@@ -155,7 +196,9 @@ impl Scanner {
         match c {
           '(' => {
             let node = self.expression()?;
-            self.take(&')');
+            if !self.take(&')') {
+              return Err(SyntaxError::new_parse_error(format!("Expected ')' at {}", self.cursor())));
+            }
             Ok(node)
           },
           c if c.is_alphabetic() => {
@@ -174,26 +217,10 @@ impl Scanner {
     }
   }
 
-  /// checks for an edge e.g. 3, 5/2
+  /// checks for an edge e.g. 3, 10, 5/2
   /// returns a CoxEdge
   fn edge(&mut self) -> Result<CoxEdge, SyntaxError>{
-    // Calling edge at the end of a string is a bug
-    let p = self.peek().unwrap();
-
-    self.pop();
-    if !p.is_numeric() {
-      return Err(SyntaxError::new_parse_error(format!("Not a numerical at {}: found {}", "self.cur", p).to_string()));
-    }
-    let next = self.peek().unwrap();
-    if next == '/' {
-      self.pop();
-      let q = self.pop().unwrap();
-      if !q.is_numeric() {
-        return Err(SyntaxError::new_parse_error(format!("Not a numerical at {}: found {}", "self.cur", q).to_string()));
-      }
-      return Ok(CoxEdge::Frac(GenericFraction::<u32>::new(p.to_digit(10).unwrap(),q.to_digit(10).unwrap())));
-    }
-    Ok(CoxEdge::Int(p.to_digit(10).unwrap()))
+    self.read_mark()
   }
 
   /// Adds a branch to the graph.
@@ -202,22 +229,23 @@ impl Scanner {
   /// (a) " *<index>" = (b) " *<index><edge>*<index>"
   /// additional branch:
   /// (c) " *<index>[<edge><node>]+"
-  /// 
-  fn branch<T: Debug + Clone + From<f64>>(&mut self, tope: &mut CoxGraph<T>) -> Result<(), SyntaxError>{
+  /// Returns whether a branch was found, a factor can have any number of them
+  /// e.g. x3o3o *b3o *c3o
+  fn branch<T: Debug + Clone + From<f64>>(&mut self, tope: &mut CoxGraph<T>) -> Result<bool, SyntaxError>{
     // take optional space
     self.take(&' ');
     // if there is no '*', there is no branch. exit
     if !self.take(&'*'){
-      return Ok(());
+      return Ok(false);
     }
 
     let i = self.index()?;//u32::from(index) - u32::from('a');
     match self.peek() {
-      None => {
+      None | Some(' ') => {
         // (a): Connect to last edge of tope
         let b = tope.node_count() as u32 - 1;
         tope.add_edge(i.into(), b.into(), CoxEdge::Int(3));
-        Ok(())
+        Ok(true)
       },
       Some(_c) => {
         // read the edge value. this increments the cursor
@@ -227,14 +255,14 @@ impl Scanner {
           // (b): " *<i1><edge>*<i2>" Connect i1 to i2
           let b = self.index()?;
           tope.add_edge(i.into(), b.into(), e);
-          Ok(())
+          Ok(true)
         } else {
           // (c) "*<i><edge><node>[<edgenode>]+"
           let n = self.node()?;
           let i_n = tope.add_node(n);
           tope.add_edge(i.into(), i_n, e);
           self.edge_nodes(tope, i_n)?;
-          Ok(())
+          Ok(true)
         }
       }
     }
@@ -251,8 +279,42 @@ impl Scanner {
     }
   }
 
-  fn expression<T: Clone>(&mut self) -> Result<CoxNode<T>, SyntaxError>{
-    todo!("implement expression");
+  /// Checks for the value of a node in brackets, a sum of node symbols with integer factors
+  /// e.g. -x, f+f, 2x-q, 3
+  /// The text in brackets becomes the repr, so the node is written back as it was read
+  fn expression<T: Clone + From<f64>>(&mut self) -> Result<CoxNode<T>, SyntaxError>{
+    let start = self.cur;
+    let mut val = 0f64;
+    loop {
+      let sign = if self.take(&'-') { -1f64 } else { self.take(&'+'); 1f64 };
+      let factor = match self.peek() {
+        Some(c) if c.is_ascii_digit() => Some(self.read_number()? as f64),
+        _ => None,
+      };
+      let symbol = match self.peek() {
+        Some(c) if c.is_alphabetic() => {
+          self.pop();
+          Some(self.eval::<f64>(&c)?)
+        },
+        _ => None,
+      };
+      val += sign*match (factor, symbol) {
+        (Some(k), Some(v)) => k*v,
+        (Some(k), None) => k,
+        (None, Some(v)) => v,
+        (None, None) => return Err(SyntaxError::new_parse_error(format!("Not a node value at {}", self.cursor()))),
+      };
+      if !matches!(self.peek(), Some('+') | Some('-')) {
+        break;
+      }
+    }
+    Ok(CoxNode::<T> {
+      name: self.node_index,
+      repr: self.chars[start..self.cur].iter().collect(),
+      val: T::from(val),
+      mats: Vec::new(),
+      cart_vec: None
+    })
   }
 
   ///     x(m,0) = o
@@ -273,7 +335,7 @@ impl Scanner {
   ///          x(∞)   = u                u : x  = 2
   /// general:                        x(m) : x  = sin(2π/m) / sin(π/m) = 2 cos(π/m) for m>1
   /// x(m,n)
-  fn eval<T: Clone + From<f64>>(&mut self, c: &char) -> Result<T, SyntaxError> {
+  pub(crate) fn eval<T: Clone + From<f64>>(&mut self, c: &char) -> Result<T, SyntaxError> {
     // TODO: make this a HashMap
     match c {
       'o' => Ok(T::from(0f64)),
@@ -284,6 +346,8 @@ impl Scanner {
       'h' => Ok(T::from(3f64.sqrt())),
       'k' => Ok(T::from((2f64+2f64.sqrt()).sqrt())),
       'u' => Ok(T::from(2f64)),
+      // snub node: alternated x, the alternation itself is carried by the repr
+      's' => Ok(T::from(1f64)),
       'F' => Ok(T::from((3f64+5f64.sqrt())/2f64)),
       _ => Err(SyntaxError::new_parse_error(format!("Unrecognized symbol: {}", c).to_string()))
     }
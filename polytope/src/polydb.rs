@@ -0,0 +1,83 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::Failed;
+
+/// The database next to the repository root, read by mesh_from_db.py
+pub const PATH: &str = "polydb.sqlite3";
+
+/// The tables of polydb.sqlite3, for a fresh database
+pub const SCHEMA: &str = "
+CREATE TABLE Info(timestamp VARCHAR,git_hash VARCHAR);
+CREATE TABLE Polyhedron(id INTEGER PRIMARY KEY,name VARCHAR,longname VARCHAR);
+CREATE TABLE Face(poly INTEGER,id INTEGER,degree INTEGER,norm_x REAL,norm_y REAL,norm_z REAL,norm_w REAL);
+CREATE TABLE Edge(poly INTEGER,id INTEGER,dihedral REAL,face1 INTEGER,face2 INTEGER);
+CREATE TABLE Vertex(poly INTEGER,id INTEGER,degree INTEGER,x REAL,y REAL,z REAL);
+CREATE TABLE Lattice(poly INTEGER,dim1 INTEGER,id1 INTEGER,dim2 INTEGER,id2 INTEGER);
+CREATE TABLE Polygon(poly INTEGER,face INTEGER,vertex INTEGER,idx INTEGER);
+CREATE TABLE Deg2Edge(deg1 INTEGER,deg2 INTEGER,poly INTEGER,edge INTEGER);
+";
+
+/// Prints a database error and turns it into `Failed`
+pub fn fail(e: rusqlite::Error) -> Failed {
+  println!("Database error: {}", e);
+  Failed
+}
+
+pub fn open(path: &str) -> Result<Connection, Failed> {
+  Connection::open(path).map_err(fail)
+}
+
+/// An empty database in memory with the tables of polydb.sqlite3
+pub fn memory() -> Result<Connection, Failed> {
+  let conn = Connection::open_in_memory().map_err(fail)?;
+  conn.execute_batch(SCHEMA).map_err(fail)?;
+  Ok(conn)
+}
+
+/// The id of the polyhedron with this long name, added with the short name `name` if it is not there yet
+pub fn polyhedron(conn: &Connection, name: &str, longname: &str) -> Result<i64, Failed> {
+  insert(conn, name, longname, false)
+}
+
+/// As `polyhedron`, for the dual of a polyhedron, which is flagged in the column `dual`
+pub fn dual(conn: &Connection, name: &str, longname: &str) -> Result<i64, Failed> {
+  insert(conn, name, longname, true)
+}
+
+/// Adds a column to a table unless it is there, for databases made before it was added, e.g. ("Polyhedron", "dual INTEGER")
+pub fn column(conn: &Connection, table: &str, column: &str) -> Result<(), Failed> {
+  let name = column.split_whitespace().next().unwrap_or(column);
+  if conn.prepare(&format!("SELECT {} FROM {}", name, table)).is_err() {
+    conn.execute(&format!("ALTER TABLE {} ADD COLUMN {}", table, column), []).map_err(fail)?;
+  }
+  Ok(())
+}
+
+fn insert(conn: &Connection, name: &str, longname: &str, dual: bool) -> Result<i64, Failed> {
+  column(conn, "Polyhedron", "dual INTEGER NOT NULL DEFAULT 0")?;
+  let id: Option<i64> = conn.query_row("SELECT id FROM Polyhedron WHERE longname=?1", params![longname], |row| row.get(0))
+    .optional().map_err(fail)?;
+  if let Some(id) = id {
+    return Ok(id);
+  }
+  conn.execute("INSERT INTO Polyhedron(id, name, longname, dual) VALUES ((SELECT IFNULL(MAX(id), -1) + 1 FROM Polyhedron), ?1, ?2, ?3)",
+    params![name, longname, dual]).map_err(fail)?;
+  Ok(conn.last_insert_rowid())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn polyhedra() {
+    let conn = memory().unwrap();
+    assert_eq!(polyhedron(&conn, "x3o3o", "tetrahedron"), Ok(0));
+    assert_eq!(polyhedron(&conn, "x4o3o", "cube"), Ok(1));
+    assert_eq!(polyhedron(&conn, "o3o3x", "tetrahedron"), Ok(0));
+    assert_eq!(dual(&conn, "x4o3o", "dual x4o3o"), Ok(2));
+    let flags: Vec<bool> = conn.prepare("SELECT dual FROM Polyhedron ORDER BY id").unwrap()
+      .query_map([], |row| row.get(0)).unwrap().map(|f| f.unwrap()).collect();
+    assert_eq!(flags, vec![false, false, true]);
+  }
+}
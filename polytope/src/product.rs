@@ -0,0 +1,143 @@
+use ndarray::Array1;
+use petgraph::graph::NodeIndex;
+
+use crate::lattice::wythoffian;
+use crate::star::coincidences;
+use crate::{components, subgraph, CoxGraph, CoxGroup, Failed};
+
+/// A polytope given by its vertices and its faces
+/// faces[k] holds the k-faces, each as the sorted list of the indices of its vertices
+/// The polytope itself is the only face of the highest rank, the empty face is left out
+#[derive(Debug, Clone, Default)]
+pub struct Polytope {
+  pub verts: Vec<Array1<f64>>,
+  pub faces: Vec<Vec<Vec<usize>>>,
+}
+
+impl Polytope {
+  /// A single vertex in `dims` dimensions
+  pub fn point(dims: usize) -> Self {
+    Polytope {
+      verts: vec![Array1::zeros(dims)],
+      faces: vec![vec![vec![0]]],
+    }
+  }
+
+  /// Rank of the polytope, 0 for a point
+  pub fn rank(&self) -> usize {
+    self.faces.len() - 1
+  }
+
+  /// The direct product P×Q
+  /// The vertices are all pairs (p,q), with coordinates of p followed by those of q,
+  /// and the k-faces are all products of an i-face of P with a j-face of Q with i+j=k
+  /// e.g. polygon × segment = prism, polygon × polygon = duoprism
+  pub fn product(&self, other: &Polytope) -> Polytope {
+    let n = other.verts.len();
+    let mut verts = Vec::with_capacity(self.verts.len()*n);
+    for p in &self.verts {
+      for q in &other.verts {
+        verts.push(ndarray::concatenate![ndarray::Axis(0), *p, *q]);
+      }
+    }
+    let mut faces: Vec<Vec<Vec<usize>>> = vec![Vec::new(); self.rank() + other.rank() + 1];
+    for (i, fs) in self.faces.iter().enumerate() {
+      for (j, gs) in other.faces.iter().enumerate() {
+        for f in fs {
+          for g in gs {
+            let mut face: Vec<usize> = f.iter().flat_map(|a| g.iter().map(move |b| a*n + b)).collect();
+            face.sort();
+            faces[i+j].push(face);
+          }
+        }
+      }
+    }
+    Polytope { verts, faces }
+  }
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Splits the group into its irreducible factors, each with its nodes renumbered from 0
+  /// The factors are in the order of `components`
+  pub fn factors(&self) -> Vec<CoxGraph<T>> {
+    components(&self.graph).iter().map(|c| subgraph(&self.graph, c)).collect()
+  }
+}
+
+impl CoxGroup<f64> {
+  /// Builds the polytope of a diagram as the product of the polytopes of the components,
+  /// where the components with more than one node go through `wythoffian`
+  /// e.g. x4o x3o -> 4-3 duoprism, x6f2o -> 12-gon (times a point), x4o3o x -> cube prism
+  /// The coordinates use the same blocks as `determine_cartesian`, so the vertices are those of `CoxGroup::vertices`
+  /// Fails on diagrams whose faces collapse onto themselves, see `star::coincidences`
+  pub fn polytope(&self) -> Result<Polytope, Failed> {
+    let collapsed = coincidences(&self.graph)?;
+    if !collapsed.is_empty() {
+      for c in collapsed {
+        println!("Degenerate: {}", c);
+      }
+      return Err(Failed);
+    }
+    let mut tope: Option<Polytope> = None;
+    for factor in self.factors() {
+      let val = |i: usize| factor[NodeIndex::new(i)].val;
+      let p = match factor.node_count() {
+        1 if val(0) == 0.0 => Polytope::point(1),
+        1 => Polytope {
+          verts: vec![Array1::from(vec![val(0)/2f64]), Array1::from(vec![-val(0)/2f64])],
+          faces: vec![vec![vec![0], vec![1]], vec![vec![0, 1]]],
+        },
+        _ => wythoffian(&factor)?,
+      };
+      tope = Some(match tope {
+        Some(t) => t.product(&p),
+        None => p,
+      });
+    }
+    tope.ok_or(Failed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::parse::Scanner;
+
+  const EPS: f64 = 1e-9;
+
+  fn counts(s: &str) -> Vec<usize> {
+    let mut sc = Scanner::new(s);
+    let group = sc.parse_tope::<f64>().unwrap();
+    group.polytope().unwrap().faces.iter().map(|f| f.len()).collect()
+  }
+
+  #[test]
+  fn duoprism() {
+    assert_eq!(counts("x4o x3o"), vec![12, 24, 19, 7, 1]);
+    assert_eq!(counts("x4o2x3o"), vec![12, 24, 19, 7, 1]);
+    // pentagonal prism
+    assert_eq!(counts("x5o x"), vec![10, 15, 7, 1]);
+  }
+
+  #[test]
+  fn polygons() {
+    // truncated hexagon with two edge lengths, pentagram
+    assert_eq!(counts("x6f"), vec![12, 12, 1]);
+    assert_eq!(counts("x5/2o"), vec![5, 5, 1]);
+    // square with unit edges
+    let mut sc = Scanner::new("x4o");
+    let p = sc.parse_tope::<f64>().unwrap().polytope().unwrap();
+    let d = &p.verts[p.faces[1][0][0]] - &p.verts[p.faces[1][0][1]];
+    assert!((d.dot(&d) - 1.0).abs() < EPS);
+  }
+
+  #[test]
+  fn same_vertices() {
+    for s in ["x3o", "x4o x3o", "x5/2o", "x6f", "x4o3o x", "x3o3o x5o", "x o3x"] {
+      let mut sc = Scanner::new(s);
+      let group = sc.parse_tope::<f64>().unwrap();
+      let (p, v) = (group.polytope().unwrap().verts, group.vertices().unwrap());
+      assert_eq!(p.len(), v.len(), "{}", s);
+      assert!(p.iter().all(|a| v.iter().any(|b| a.abs_diff_eq(b, EPS))), "{}", s);
+    }
+  }
+}
@@ -0,0 +1,192 @@
+use exact::Expr;
+use petgraph::graph::NodeIndex;
+
+use crate::classify::{gram, signature};
+use crate::roots::{gcd, Cyc, CycFrac};
+use crate::snub::recognize;
+use crate::{is_face, subgraph, CoxGraph, CoxGroup, Failed};
+
+/// Exact radii of a Wythoffian polytope, see `CoxGroup::radii`
+/// e.g. √3/2 (cube), √(10+2√5)/4 (icosahedron), (1+√5)/2 (600-cell), 1/2
+#[derive(Debug, Clone)]
+pub struct Radii {
+  pub circumradius: Expr,
+  /// distance of the edge midpoints, for the edges of every ringed node
+  pub midradii: Vec<(NodeIndex, Expr)>,
+  /// distance of the facet centres, for every kind of facet with its diagram, e.g. x4o for the squares of x4o3x
+  pub facets: Vec<(String, Expr)>,
+}
+
+/// The squares of the radii of `Radii` as they come out of the Gram matrix, before taking roots
+/// e.g. 3/4, 1/2, x4o at 1/4 for the cube
+#[derive(Debug, Clone)]
+pub struct SquaredRadii {
+  pub circumradius: CycFrac,
+  pub midradii: Vec<(NodeIndex, CycFrac)>,
+  pub facets: Vec<(String, CycFrac)>,
+}
+
+impl Radii {
+  /// The radius of the sphere that touches all facets at their centres, if they are all at the same distance
+  pub fn inradius(&self) -> Option<&Expr> {
+    let (_, first) = self.facets.first()?;
+    if self.facets.iter().all(|(_, r)| r == first) { Some(first) } else { None }
+  }
+}
+
+/// The node values of a finite diagram as exact numbers, as found by `recognize`, like x, q, f or h,
+/// and the m of the ring Z[2cos(𝛑/m)] that holds them and all marks
+pub fn exact_values(graph: &CoxGraph<f64>) -> Result<(Vec<Cyc>, u32), Failed> {
+  let (_, zero, neg) = signature(&gram(graph));
+  if zero + neg > 0 {
+    println!("Only finite groups give polytopes with exact measures");
+    return Err(Failed);
+  }
+  let mut values = Vec::new();
+  for node in graph.node_weights() {
+    match recognize(node.val) {
+      Some(v) => values.push(v),
+      None => {
+        println!("No exact value for the node {} = {}", node.repr, node.val);
+        return Err(Failed);
+      }
+    }
+  }
+  let lcm = |a: u32, b: u32| a/gcd(a as i64, b as i64) as u32*b;
+  let m = graph.edge_weights().map(|e| Cyc::from_edge(e).m()).chain(values.iter().map(|v| v.m())).fold(1, lcm);
+  Ok((values, m))
+}
+
+/// √x as an exact expression, x in the smallest ring as by `descend`
+pub fn radical(x: CycFrac) -> Expr {
+  descend(x).to_expr().sqrt()
+}
+
+/// The number in the smallest of the rings that `recognize` knows, e.g. (2+√2)/2 instead of an expression in 2cos(𝛑/8)
+pub fn descend(x: CycFrac) -> CycFrac {
+  let m = x.num.m();
+  match recognize(x.num.to_f64()) {
+    Some(t) if (t.m() == 1 || m.is_multiple_of(t.m())) && t.lift(m) == x.num => CycFrac::new(t, x.den),
+    _ => x,
+  }
+}
+
+/// A⁻¹ for the Cartan-like matrix A = 2G of a finite diagram, i.e. A_ii = 2, A_ij = -2cos(𝛑/m_ij),
+/// by Gauss-Jordan elimination in Q(c) with c = 2cos(𝛑/m)
+/// The basis vectors are the dual basis G⁻¹N/2 of the normals (see `determine_cartesian`), so their inner products are A⁻¹/2
+pub fn cartan_inverse(graph: &CoxGraph<f64>, m: u32) -> Vec<Vec<CycFrac>> {
+  let n = graph.node_count();
+  let identity = |k: i64| -> Vec<Vec<CycFrac>> {
+    (0..n).map(|i| (0..n).map(|j| CycFrac::int(if i == j { k } else { 0 })).collect()).collect()
+  };
+  let (mut a, mut inv) = (identity(2), identity(1));
+  for e in graph.edge_indices() {
+    let (i, j) = graph.edge_endpoints(e).unwrap();
+    let c = CycFrac { num: -&Cyc::from_edge(&graph[e]).lift(m), den: 1 };
+    a[i.index()][j.index()] = c.clone();
+    a[j.index()][i.index()] = c;
+  }
+  for k in 0..n {
+    let p = (k..n).find(|i| !a[*i][k].is_zero()).expect("the matrix of a finite group is invertible");
+    a.swap(p, k);
+    inv.swap(p, k);
+    let scale = a[k][k].inverse().unwrap();
+    a[k] = a[k].iter().map(|x| x*&scale).collect();
+    inv[k] = inv[k].iter().map(|x| x*&scale).collect();
+    let (pivot, pivot_inv) = (a[k].clone(), inv[k].clone());
+    for i in (0..n).filter(|i| *i != k) {
+      let factor = a[i][k].clone();
+      if factor.is_zero() {
+        continue;
+      }
+      for (x, y) in a[i].iter_mut().zip(&pivot).chain(inv[i].iter_mut().zip(&pivot_inv)) {
+        *x = &*x - &(&factor*y);
+      }
+    }
+  }
+  inv
+}
+
+/// (1/2)vᵀA⁻¹v, the squared circumradius of the polytope with node values v, see `cartan_inverse`
+pub fn squared_radius(graph: &CoxGraph<f64>, values: &[Cyc], m: u32) -> CycFrac {
+  let inv = cartan_inverse(graph, m);
+  let v: Vec<CycFrac> = values.iter().map(|v| CycFrac { num: v.lift(m), den: 1 }).collect();
+  let dot = (0..v.len()).flat_map(|i| (0..v.len()).map(move |j| (i, j)))
+    .fold(CycFrac::int(0), |acc, (i, j)| &acc + &(&(&v[i]*&inv[i][j])*&v[j]));
+  &dot*&CycFrac::new(Cyc::int(1), 2)
+}
+
+impl CoxGroup<f64> {
+  /// Exact circumradius, midradii and facet radii of the Wythoffian polytope of a finite diagram,
+  /// computed from the node values and the Gram matrix of the mirrors, not from the vertices
+  /// The centre of the face of a set of nodes K is the seed projected onto the fixed space of W_K,
+  /// so its squared distance from the centre is R² - R_K², with R_K the circumradius of the face
+  /// Node values have to be recognizable by `recognize`, like x, q, f or h, the diagram may mix marks from different rings
+  /// e.g. x4o3o -> √3/2, √2/2, x4o at 1/2
+  pub fn radii(&self) -> Result<Radii, Failed> {
+    let squares = self.squared_radii()?;
+    Ok(Radii {
+      circumradius: radical(squares.circumradius),
+      midradii: squares.midradii.into_iter().map(|(i, r)| (i, radical(r))).collect(),
+      facets: squares.facets.into_iter().map(|(d, r)| (d, radical(r))).collect(),
+    })
+  }
+
+  /// The squares of the radii, see `CoxGroup::radii`
+  pub fn squared_radii(&self) -> Result<SquaredRadii, Failed> {
+    let graph = &self.graph;
+    let (values, m) = exact_values(graph)?;
+    let face = |nodes: &[usize]| squared_radius(&subgraph(graph, nodes), &nodes.iter().map(|i| values[*i].clone()).collect::<Vec<_>>(), m);
+    let all: Vec<usize> = (0..graph.node_count()).collect();
+    let r2 = face(&all);
+    let midradii = all.iter().filter(|i| !values[**i].is_zero()).map(|i| {
+      (NodeIndex::new(*i), &r2 - &face(&[*i]))
+    }).collect();
+    // a set of nodes gives a facet if every component of its diagram has a ringed node
+    let mut facets = Vec::new();
+    for j in &all {
+      let nodes: Vec<usize> = all.iter().cloned().filter(|i| i != j).collect();
+      if is_face(graph, &nodes, |i| !values[i].is_zero()) {
+        let facet = CoxGroup { graph: subgraph(graph, &nodes), polys: Vec::new() };
+        facets.push((facet.dynkin(), &r2 - &face(&nodes)));
+      }
+    }
+    Ok(SquaredRadii { circumradius: r2, midradii, facets })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  fn radii(s: &str) -> Radii {
+    group(s).radii().unwrap()
+  }
+
+  #[test]
+  fn exact_radii() {
+    for (s, r) in [("x3o3o", "√6/4"), ("x4o3o", "√3/2"), ("o4o3x", "√2/2"), ("x3o5o", "√(10+2√5)/4"),
+                   ("x3o3o5o", "(1+√5)/2"), ("x3o3o3o3o *c3o", "√6/3"), ("x4o", "√2/2"), ("x3o x", "√21/6")] {
+      assert_eq!(radii(s).circumradius.to_string(), r, "{}", s);
+    }
+    let cube = radii("x4o3o");
+    assert_eq!(cube.midradii[0].1.to_string(), "√2/2");
+    assert_eq!(cube.facets.iter().map(|(d, r)| format!("{} {}", d, r)).collect::<Vec<_>>(), vec!["x4o 1/2"]);
+    assert_eq!(cube.inradius().unwrap().to_string(), "1/2");
+    // cuboctahedron and small rhombicuboctahedron: facets at different distances
+    let sirco = radii("x4o3x");
+    assert_eq!(sirco.facets.len(), 3);
+    assert!(sirco.inradius().is_none());
+  }
+
+  #[test]
+  fn against_vertices() {
+    // star marks, other node values, and products mixing √2 and φ
+    for s in ["x5/2o5o", "x5/2o3o3o", "x3o5/2x", "x4o3q", "f3x4o", "x5o x4o", "x3x3o *a5/2*c", "x6o x5o", "x8o x", "o5/3x3o"] {
+      let group = group(s);
+      let v = &group.vertices().unwrap()[0];
+      assert!((group.radii().unwrap().circumradius.to_f64() - v.dot(v).sqrt()).abs() < 1e-9, "{}", s);
+    }
+  }
+}
@@ -0,0 +1,640 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use exact::enums::frac;
+use exact::Expr;
+use ndarray::{Array1, Array2};
+use ndarray_linalg::{Cholesky, UPLO};
+use petgraph::graph::NodeIndex;
+
+use crate::classify::{classify, gram, Kind};
+use crate::interval::Interval;
+use crate::{components, mark, CoxEdge, CoxGraph, CoxGroup};
+
+/// Tolerance for the sign of a nonzero number
+const EPS: f64 = 1e-9;
+
+/// Gives up on enumerating roots after this many, the group is then not finite
+const MAX_ROOTS: usize = 10_000;
+
+/// Exact number a_0 + a_1 c + a_2 c² + ... in Z[c], c = 2cos(𝛑/m), reduced modulo the minimal polynomial of c
+/// This covers the root coordinates of all finite groups: integers for the crystallographic ones,
+/// Z[φ] for H3 and H4 (c = φ for m = 5) and Z[2cos(𝛑/p)] for I2(p)
+/// Integers have m = 1, so they can be combined with numbers of any m
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cyc {
+  m: u32,
+  coefs: Vec<i64>,
+}
+
+/// Monic minimal polynomial of 2cos(𝛑/m), lowest coefficient first
+/// Its roots are 2cos(k𝛑/m) for 0 < k < m with gcd(k, 2m) = 1
+pub fn minimal_polynomial(m: u32) -> Vec<i64> {
+  let gcd = |mut a: u32, mut b: u32| {
+    while b != 0 {
+      (a, b) = (b, a % b);
+    }
+    a
+  };
+  let mut poly = vec![1f64];
+  for k in (1..m).filter(|k| gcd(*k, 2*m) == 1) {
+    let r = 2f64*(std::f64::consts::PI*k as f64/m as f64).cos();
+    // multiply by (x - r)
+    let mut next = vec![0f64; poly.len() + 1];
+    for (i, p) in poly.iter().enumerate() {
+      next[i + 1] += p;
+      next[i] -= p*r;
+    }
+    poly = next;
+  }
+  poly.iter().map(|p| p.round() as i64).collect()
+}
+
+impl Cyc {
+  pub fn int(k: i64) -> Cyc {
+    Cyc { m: 1, coefs: vec![k] }.reduced()
+  }
+
+  /// c = 2cos(𝛑/m)
+  pub fn gen(m: u32) -> Cyc {
+    Cyc { m, coefs: vec![0, 1] }.reduced()
+  }
+
+  /// 2cos(𝛑/m) for a mark m, which is an integer for m = 2 and 3
+  pub fn from_mark(m: u32) -> Cyc {
+    match m {
+      2 => Cyc::int(0),
+      3 => Cyc::int(1),
+      _ => Cyc::gen(m),
+    }
+  }
+
+  fn reduced(mut self) -> Cyc {
+    if self.m > 1 {
+      let p = minimal_polynomial(self.m);
+      let d = p.len() - 1;
+      while self.coefs.len() > d {
+        let top = self.coefs.pop().unwrap();
+        let n = self.coefs.len();
+        for (k, a) in p[..d].iter().enumerate() {
+          self.coefs[n - d + k] -= top*a;
+        }
+      }
+    }
+    while self.coefs.last() == Some(&0) {
+      self.coefs.pop();
+    }
+    if self.coefs.len() <= 1 {
+      self.m = 1;
+    }
+    self
+  }
+
+  /// The m of the result of combining a and b
+  fn join(a: &Cyc, b: &Cyc) -> u32 {
+    assert!(a.m == 1 || b.m == 1 || a.m == b.m, "cannot combine 2cos(𝛑/{}) and 2cos(𝛑/{})", a.m, b.m);
+    a.m.max(b.m)
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.coefs.is_empty()
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    let c = if self.m > 1 { 2f64*(std::f64::consts::PI/self.m as f64).cos() } else { 0f64 };
+    self.coefs.iter().rev().fold(0f64, |acc, a| acc*c + *a as f64)
+  }
+
+  /// An interval around the number, as `to_f64` but with the rounding bounded, which matters where the terms cancel
+  pub fn to_interval(&self) -> Interval {
+    let c = Interval::approx(if self.m > 1 { 2f64*(std::f64::consts::PI/self.m as f64).cos() } else { 0f64 });
+    self.coefs.iter().rev().fold(Interval::point(0f64), |acc, a| acc*c + Interval::approx(*a as f64))
+  }
+
+  pub fn is_positive(&self) -> bool {
+    !self.is_zero() && self.to_f64() > EPS
+  }
+
+  /// The m of c = 2cos(𝛑/m), 1 for integers
+  pub fn m(&self) -> u32 {
+    self.m
+  }
+
+  /// 2cos(d𝛑/p) for a mark p/d, the angle between the mirrors being d𝛑/p, using 2cos(d𝛑/p) = C_d(2cos(𝛑/p))
+  /// e.g. 5/2 -> φ-1, 3/2 -> -1
+  pub fn from_edge(edge: &CoxEdge) -> Cyc {
+    match edge {
+      CoxEdge::Int(m) => Cyc::from_mark(*m),
+      CoxEdge::Frac(f) => chebyshev(*f.denom().unwrap() as usize, &Cyc::gen(*f.numer().unwrap())),
+    }
+  }
+
+  /// The same number in Z[2cos(𝛑/m)] for a multiple m of its own m, using 2cos(k𝛑/m) = C_k(2cos(𝛑/m))
+  pub fn lift(&self, m: u32) -> Cyc {
+    if self.m == 1 || self.m == m {
+      return self.clone();
+    }
+    assert!(m.is_multiple_of(self.m), "cannot write 2cos(𝛑/{}) in terms of 2cos(𝛑/{})", self.m, m);
+    let c = chebyshev((m/self.m) as usize, &Cyc::gen(m));
+    self.coefs.iter().rev().fold(Cyc::int(0), |acc, a| &(&acc*&c) + &Cyc::int(*a))
+  }
+
+  /// Greatest common divisor of the coefficients, 0 for 0
+  pub fn content(&self) -> i64 {
+    self.coefs.iter().fold(0, |g, a| gcd(g, *a))
+  }
+
+  /// k times the number
+  pub fn scale(&self, k: i64) -> Cyc {
+    Cyc { m: self.m, coefs: self.coefs.iter().map(|a| a*k).collect() }.reduced()
+  }
+
+  /// The number divided by a divisor of its content
+  pub fn div_exact(&self, k: i64) -> Cyc {
+    Cyc { m: self.m, coefs: self.coefs.iter().map(|a| a/k).collect() }
+  }
+
+  /// Whether the number has more than one term, so it needs brackets in a product
+  pub fn is_sum(&self) -> bool {
+    self.coefs.iter().filter(|a| **a != 0).count() > 1
+  }
+
+  /// The integer the number is, if it is one
+  pub fn as_int(&self) -> Option<i64> {
+    match self.coefs.as_slice() {
+      [] => Some(0),
+      [a] => Some(*a),
+      _ => None,
+    }
+  }
+
+  /// The Galois conjugate that maps c = 2cos(𝛑/m) to 2cos(k𝛑/m), for k prime to 2m, see `minimal_polynomial`
+  pub fn conjugate(&self, k: u32) -> Cyc {
+    if self.m == 1 {
+      return self.clone();
+    }
+    let c = chebyshev(k as usize, &Cyc::gen(self.m));
+    self.coefs.iter().rev().fold(Cyc::int(0), |acc, a| &(&acc*&c) + &Cyc::int(*a))
+  }
+
+  /// The number as an exact expression, with c written out as √2, φ = (1+√5)/2 and √3 for m = 4, 5 and 6,
+  /// and as 2cos(𝛑/m) otherwise
+  pub fn to_expr(&self) -> Expr {
+    let c = match self.m {
+      1 | 2 => Expr::int(0),
+      3 => Expr::int(1),
+      4 => Expr::int(2).sqrt(),
+      5 => (Expr::int(1) + Expr::int(5).sqrt())*Expr::Val(frac(1, 2)),
+      6 => Expr::int(3).sqrt(),
+      m => Expr::int(2)*Expr::Cos { v: Box::new(Expr::Val(frac(1, m as i64))*Expr::pi()) },
+    };
+    self.coefs.iter().rev().fold(Expr::int(0), |acc, a| acc*c.clone() + Expr::int(*a))
+  }
+
+  /// 1/x as an element of Q(c), None for 0
+  /// Multiplication by x is a linear map on the basis 1, c, c², ..., and 1/x is the solution of x·y = 1, by Cramer's rule
+  pub fn inverse(&self) -> Option<CycFrac> {
+    if self.is_zero() {
+      return None;
+    }
+    let d = if self.m == 1 { 1 } else { minimal_polynomial(self.m).len() - 1 };
+    let c = if self.m == 1 { Cyc::int(1) } else { Cyc::gen(self.m) };
+    let mut columns: Vec<Vec<i128>> = Vec::new();
+    let mut power = self.clone();
+    for _ in 0..d {
+      let mut col: Vec<i128> = power.coefs.iter().map(|a| *a as i128).collect();
+      col.resize(d, 0);
+      columns.push(col);
+      power = &power*&c;
+    }
+    let matrix = |replace: Option<usize>| -> Vec<Vec<i128>> {
+      (0..d).map(|i| (0..d).map(|j| match replace {
+        Some(k) if k == j => if i == 0 { 1 } else { 0 },
+        _ => columns[j][i],
+      }).collect()).collect()
+    };
+    let det = determinant(matrix(None));
+    let coefs = (0..d).map(|k| determinant(matrix(Some(k))) as i64).collect();
+    Some(CycFrac::new(Cyc { m: self.m, coefs }.reduced(), det as i64))
+  }
+}
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+  let (mut a, mut b) = (a.abs(), b.abs());
+  while b != 0 {
+    (a, b) = (b, a % b);
+  }
+  a
+}
+
+/// C_k(x) with C_0 = 2, C_1 = x, C_(k+1) = x C_k - C_(k-1), so that C_k(2cos t) = 2cos(kt)
+fn chebyshev(k: usize, x: &Cyc) -> Cyc {
+  let (mut prev, mut cur) = (Cyc::int(2), x.clone());
+  if k == 0 {
+    return prev;
+  }
+  for _ in 1..k {
+    (prev, cur) = (cur.clone(), &(x*&cur) - &prev);
+  }
+  cur
+}
+
+/// Determinant of an integer matrix by fraction-free (Bareiss) elimination
+fn determinant(mut a: Vec<Vec<i128>>) -> i128 {
+  let n = a.len();
+  let (mut sign, mut prev) = (1, 1);
+  for k in 0..n {
+    match (k..n).find(|i| a[*i][k] != 0) {
+      Some(p) if p != k => {
+        a.swap(p, k);
+        sign = -sign;
+      },
+      Some(_) => {},
+      None => return 0,
+    }
+    for i in k+1..n {
+      for j in k+1..n {
+        a[i][j] = (a[i][j]*a[k][k] - a[i][k]*a[k][j])/prev;
+      }
+    }
+    prev = a[k][k];
+  }
+  if n == 0 { 1 } else { sign*a[n-1][n-1] }
+}
+
+/// Exact number num/den in Q(c), c = 2cos(𝛑/m), with num in Z[c] and den a positive integer sharing no factor with all of num
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CycFrac {
+  pub num: Cyc,
+  pub den: i64,
+}
+
+impl CycFrac {
+  pub fn new(num: Cyc, den: i64) -> CycFrac {
+    assert!(den != 0, "division by zero");
+    let g = gcd(num.content(), den)*den.signum();
+    CycFrac { num: num.div_exact(g), den: den/g }
+  }
+
+  pub fn int(k: i64) -> CycFrac {
+    CycFrac { num: Cyc::int(k), den: 1 }
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.num.is_zero()
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    self.num.to_f64()/self.den as f64
+  }
+
+  pub fn to_interval(&self) -> Interval {
+    self.num.to_interval()*Interval::approx(self.den as f64).inverse().unwrap()
+  }
+
+  /// The same number with c = 2cos(𝛑/m), see `Cyc::lift`
+  pub fn lift(&self, m: u32) -> CycFrac {
+    CycFrac { num: self.num.lift(m), den: self.den }
+  }
+
+  /// See `Cyc::conjugate`
+  pub fn conjugate(&self, k: u32) -> CycFrac {
+    CycFrac { num: self.num.conjugate(k), den: self.den }
+  }
+
+  /// The number as an exact expression, see `Cyc::to_expr`
+  pub fn to_expr(&self) -> Expr {
+    self.num.to_expr()*Expr::Val(frac(1, self.den))
+  }
+
+  /// None for 0
+  pub fn inverse(&self) -> Option<CycFrac> {
+    let inv = self.num.inverse()?;
+    Some(CycFrac::new(inv.num.scale(self.den), inv.den))
+  }
+}
+
+impl Add for &CycFrac {
+  type Output = CycFrac;
+  fn add(self, other: &CycFrac) -> CycFrac {
+    CycFrac::new(&self.num.scale(other.den) + &other.num.scale(self.den), self.den*other.den)
+  }
+}
+
+impl Neg for &CycFrac {
+  type Output = CycFrac;
+  fn neg(self) -> CycFrac {
+    CycFrac { num: -&self.num, den: self.den }
+  }
+}
+
+impl Sub for &CycFrac {
+  type Output = CycFrac;
+  fn sub(self, other: &CycFrac) -> CycFrac {
+    self + &-other
+  }
+}
+
+impl Mul for &CycFrac {
+  type Output = CycFrac;
+  fn mul(self, other: &CycFrac) -> CycFrac {
+    CycFrac::new(&self.num*&other.num, self.den*other.den)
+  }
+}
+
+impl fmt::Display for CycFrac {
+  /// e.g. 3/4, (2+φ)/4, √2/2
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match (self.den, self.num.is_sum()) {
+      (1, _) => write!(f, "{}", self.num),
+      (d, true) => write!(f, "({})/{}", self.num, d),
+      (d, false) => write!(f, "{}/{}", self.num, d),
+    }
+  }
+}
+
+impl Add for &Cyc {
+  type Output = Cyc;
+  fn add(self, other: &Cyc) -> Cyc {
+    let mut coefs = vec![0; self.coefs.len().max(other.coefs.len())];
+    for (i, a) in self.coefs.iter().enumerate() {
+      coefs[i] += a;
+    }
+    for (i, a) in other.coefs.iter().enumerate() {
+      coefs[i] += a;
+    }
+    Cyc { m: Cyc::join(self, other), coefs }.reduced()
+  }
+}
+
+impl Neg for &Cyc {
+  type Output = Cyc;
+  fn neg(self) -> Cyc {
+    Cyc { m: self.m, coefs: self.coefs.iter().map(|a| -a).collect() }
+  }
+}
+
+impl Sub for &Cyc {
+  type Output = Cyc;
+  fn sub(self, other: &Cyc) -> Cyc {
+    self + &-other
+  }
+}
+
+impl Mul for &Cyc {
+  type Output = Cyc;
+  fn mul(self, other: &Cyc) -> Cyc {
+    if self.is_zero() || other.is_zero() {
+      return Cyc::int(0);
+    }
+    let mut coefs = vec![0; self.coefs.len() + other.coefs.len() - 1];
+    for (i, a) in self.coefs.iter().enumerate() {
+      for (j, b) in other.coefs.iter().enumerate() {
+        coefs[i + j] += a*b;
+      }
+    }
+    Cyc { m: Cyc::join(self, other), coefs }.reduced()
+  }
+}
+
+impl fmt::Display for Cyc {
+  /// e.g. 1+2φ, 3√2, 2cos(𝛑/7)^2, 3·2cos(𝛑/7)
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.is_zero() {
+      return write!(f, "0");
+    }
+    let sym = match self.m {
+      4 => "√2".to_string(),
+      5 => "φ".to_string(),
+      6 => "√3".to_string(),
+      m => format!("2cos(𝛑/{})", m),
+    };
+    let mut first = true;
+    for (k, a) in self.coefs.iter().enumerate().filter(|(_, a)| **a != 0) {
+      let sign = if *a < 0 { "-" } else if first { "" } else { "+" };
+      let num = match (a.abs(), k) {
+        (1, 1..) => String::new(),
+        // 2·2cos(𝛑/7) and not 22cos(𝛑/7)
+        (a, 1..) if self.m > 6 => format!("{}·", a),
+        (a, _) => a.to_string(),
+      };
+      let power = match k {
+        0 => String::new(),
+        1 => sym.clone(),
+        _ => format!("{}^{}", sym, k),
+      };
+      write!(f, "{}{}{}", sign, num, power)?;
+      first = false;
+    }
+    Ok(())
+  }
+}
+
+/// Root system of a finite Coxeter group, with the roots written in the basis of simple roots
+/// Components with only marks 3, 4 and 6 get the crystallographic normalization,
+/// where for a mark 4 or 6 the node with the lower index has the longer root;
+/// the other components have unit roots
+#[derive(Debug, Clone)]
+pub struct RootSystem {
+  /// cartan[i][j] = <α_j, α_i^∨>, so that s_i(α_j) = α_j - cartan[i][j] α_i
+  pub cartan: Vec<Vec<Cyc>>,
+  /// squared lengths of the simple roots, relative within each component
+  pub lengths: Vec<f64>,
+  /// unit normals of the simple mirrors in Cartesian coordinates, one per row
+  pub simple: Array2<f64>,
+  /// positive roots, sorted by height
+  pub positive: Vec<Vec<Cyc>>,
+}
+
+impl RootSystem {
+  /// The negative roots, in the same order as the positive ones
+  pub fn negative(&self) -> Vec<Vec<Cyc>> {
+    self.positive.iter().map(|r| r.iter().map(|a| -a).collect()).collect()
+  }
+
+  /// Height: the sum of the coefficients
+  pub fn height(root: &[Cyc]) -> Cyc {
+    root.iter().fold(Cyc::int(0), |h, a| &h + a)
+  }
+
+  /// The root of largest height, only defined for irreducible groups
+  pub fn highest_root(&self) -> Option<&Vec<Cyc>> {
+    let support = |r: &Vec<Cyc>| r.iter().all(|a| !a.is_zero());
+    self.positive.last().filter(|r| support(r))
+  }
+
+  /// The root in Cartesian coordinates, with the lengths of `lengths`
+  pub fn cartesian(&self, root: &[Cyc]) -> Array1<f64> {
+    let mut v = Array1::<f64>::zeros(self.simple.ncols());
+    for (i, a) in root.iter().enumerate() {
+      v = v + &self.simple.row(i)*(a.to_f64()*self.lengths[i].sqrt());
+    }
+    v
+  }
+
+  /// Unit normals of all mirrors of the group, one for each positive root
+  pub fn normals(&self) -> Vec<Array1<f64>> {
+    self.positive.iter().map(|r| {
+      let v = self.cartesian(r);
+      let len = v.dot(&v).sqrt();
+      v/len
+    }).collect()
+  }
+}
+
+/// Enumerates the root system of a finite diagram with integer marks, None for infinite groups and for rational marks,
+/// whose mirrors are not a simple system
+pub fn root_system<T: Clone>(graph: &CoxGraph<T>) -> Option<RootSystem> {
+  if classify(graph).kind != Kind::Spherical || graph.edge_weights().any(|e| matches!(e, CoxEdge::Frac(_))) {
+    return None;
+  }
+  let n = graph.node_count();
+  let mut cartan = vec![vec![Cyc::int(0); n]; n];
+  let mut lengths = vec![1f64; n];
+  for (i, row) in cartan.iter_mut().enumerate() {
+    row[i] = Cyc::int(2);
+  }
+  for comp in components(graph) {
+    let marks: Vec<u32> = graph.edge_indices()
+      .filter(|e| comp.contains(&graph.edge_endpoints(*e).unwrap().0.index()))
+      .map(|e| match graph[e] { CoxEdge::Int(m) => m, CoxEdge::Frac(_) => unreachable!() })
+      .collect();
+    let crystallographic = marks.iter().all(|m| [3, 4, 6].contains(m));
+    // walk the component (a tree) from its first node to get the root lengths
+    let mut queue = VecDeque::from(vec![comp[0]]);
+    let mut seen = vec![comp[0]];
+    while let Some(i) = queue.pop_front() {
+      for j in graph.neighbors(NodeIndex::new(i)).map(|j| j.index()) {
+        let m = match mark(graph, i, j) { CoxEdge::Int(m) => m, CoxEdge::Frac(_) => unreachable!() };
+        if crystallographic {
+          let (long, short) = (i.min(j), i.max(j));
+          let ratio = match m { 4 => 2, 6 => 3, _ => 1 };
+          cartan[long][short] = Cyc::int(-1);
+          cartan[short][long] = Cyc::int(-ratio);
+          if !seen.contains(&j) {
+            lengths[j] = if j == short { lengths[i]/ratio as f64 } else { lengths[i]*ratio as f64 };
+          }
+        } else {
+          cartan[i][j] = -&Cyc::from_mark(m);
+        }
+        if !seen.contains(&j) {
+          seen.push(j);
+          queue.push_back(j);
+        }
+      }
+    }
+  }
+  let simple = gram(graph).cholesky(UPLO::Lower).ok()?;
+
+  let unit = |i: usize| -> Vec<Cyc> { (0..n).map(|j| Cyc::int(if i == j { 1 } else { 0 })).collect() };
+  let mut positive: Vec<Vec<Cyc>> = (0..n).map(unit).collect();
+  let mut i = 0;
+  while i < positive.len() {
+    for s in 0..n {
+      if positive[i] == unit(s) {
+        continue;
+      }
+      // s(β) = β - <β, α_s^∨> α_s stays positive
+      let pairing = positive[i].iter().zip(&cartan[s]).fold(Cyc::int(0), |acc, (b, a)| &acc + &(b*a));
+      if pairing.is_zero() {
+        continue;
+      }
+      let mut r = positive[i].clone();
+      r[s] = &r[s] - &pairing;
+      if !positive.contains(&r) {
+        positive.push(r);
+        if positive.len() > MAX_ROOTS {
+          return None;
+        }
+      }
+    }
+    i += 1;
+  }
+  positive.sort_by(|a, b| RootSystem::height(a).to_f64().partial_cmp(&RootSystem::height(b).to_f64()).unwrap());
+  Some(RootSystem { cartan, lengths, simple, positive })
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Root system of the group, None if it is infinite or has rational marks
+  pub fn roots(&self) -> Option<RootSystem> {
+    root_system(&self.graph)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::Scanner;
+
+  fn roots(s: &str) -> RootSystem {
+    let mut sc = Scanner::new(s);
+    sc.parse_tope::<f64>().unwrap().roots().unwrap()
+  }
+
+  fn ints(r: &[Cyc]) -> Vec<i64> {
+    r.iter().map(|a| a.to_f64().round() as i64).collect()
+  }
+
+  #[test]
+  fn cyclotomic() {
+    let phi = Cyc::gen(5);
+    assert_eq!(&phi*&phi, &phi + &Cyc::int(1));
+    assert_eq!((&phi + &Cyc::int(1)).to_string(), "1+φ");
+    let s2 = Cyc::gen(4);
+    assert_eq!(&s2*&s2, Cyc::int(2));
+    assert_eq!(minimal_polynomial(8), vec![2, 0, -4, 0, 1]);
+    assert_eq!(minimal_polynomial(7), vec![1, -2, -1, 1]);
+  }
+
+  #[test]
+  fn fractions() {
+    let phi = Cyc::gen(5);
+    assert_eq!(phi.inverse(), Some(CycFrac::new(&phi - &Cyc::int(1), 1)));
+    let s2 = CycFrac::new(Cyc::gen(4), 1);
+    assert_eq!(s2.inverse().unwrap().to_string(), "√2/2");
+    assert_eq!(&s2*&s2.inverse().unwrap(), CycFrac::int(1));
+    assert_eq!(Cyc::from_edge(&CoxEdge::Frac(fraction::GenericFraction::new(5u32, 2u32))), &phi - &Cyc::int(1));
+    // √2 = 2cos(𝛑/4) = (2cos(𝛑/8))² - 2
+    let c8 = Cyc::gen(8);
+    assert_eq!(Cyc::gen(4).lift(8), &(&c8*&c8) - &Cyc::int(2));
+    assert!((Cyc::gen(6).lift(30).to_f64() - 3f64.sqrt()).abs() < 1e-9);
+  }
+
+  #[test]
+  fn positive_roots() {
+    for (s, n) in [("x3o3o", 6), ("x4o3o", 9), ("x3o3o *b3o", 12), ("x3o3o3o3o *c3o", 36), ("x3o3o3o3o3o3o *c3o", 120),
+                   ("x3o4o3o", 24), ("x5o3o", 15), ("x5o3o3o", 60), ("x7o", 7), ("x6o", 6), ("x5o x3o", 8)] {
+      let r = roots(s);
+      assert_eq!(r.positive.len(), n, "{}", s);
+      assert_eq!(r.normals().len(), n);
+    }
+  }
+
+  #[test]
+  fn highest_roots() {
+    assert_eq!(ints(roots("x3o3o").highest_root().unwrap()), vec![1, 1, 1]);
+    assert_eq!(ints(roots("x4o3o").highest_root().unwrap()), vec![1, 2, 2]);
+    assert_eq!(ints(roots("x3o4o3o").highest_root().unwrap()), vec![2, 3, 4, 2]);
+    // height h-1 for E8
+    let e8 = roots("x3o3o3o3o3o3o *c3o");
+    assert_eq!(RootSystem::height(e8.highest_root().unwrap()), Cyc::int(29));
+    assert_eq!(roots("x5o x3o").highest_root(), None);
+    // icosahedral roots have coordinates in Z[φ]
+    let h3 = roots("x5o3o");
+    assert!(h3.positive.iter().flatten().all(|a| a.m == 1 || a.m == 5));
+  }
+
+  #[test]
+  fn normals() {
+    // mirrors of the cube: 3 coordinate planes and 6 diagonal planes, closed under the group
+    let r = roots("x4o3o");
+    let normals = r.normals();
+    for a in &normals {
+      for b in &normals {
+        let refl = b - &(a*(2f64*a.dot(b)));
+        assert!(normals.iter().any(|c| (c - &refl).iter().all(|x| x.abs() < 1e-9) || (c + &refl).iter().all(|x| x.abs() < 1e-9)));
+      }
+    }
+  }
+}
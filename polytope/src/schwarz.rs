@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use fraction::GenericFraction;
+use ndarray::Array1;
+use petgraph::graph::NodeIndex;
+
+use crate::classify::{gram, signature, CoxType};
+use crate::invariants::identify;
+use crate::{components, CoxEdge, CoxGraph, CoxGroup, CoxNode};
+
+/// Largest number of mirrors of a finite group of rank 4 (H4 has 60), more means the group is infinite
+const MAX_MIRRORS: usize = 60;
+
+/// A simplex with rational marks whose images under its reflection group cover the sphere a finite number of times
+/// e.g. the Schwarz triangle (5/2 3 2) of the great stellated dodecahedron covers it 7 times
+#[derive(Debug, Clone)]
+pub struct Kaleidoscope {
+  /// the diagram, all nodes unringed
+  pub graph: CoxGraph<f64>,
+  /// how many images of the simplex cover a generic point of the sphere
+  pub density: u32,
+  /// the finite group the mirrors generate
+  pub group: Vec<CoxType>,
+}
+
+impl Kaleidoscope {
+  pub fn rank(&self) -> usize {
+    self.graph.node_count()
+  }
+
+  /// The marks between all pairs of nodes, pairs in lexicographic order
+  pub fn marks(&self) -> Vec<CoxEdge> {
+    pairs(self.rank()).iter().map(|(i, j)| crate::mark(&self.graph, *i, *j)).collect()
+  }
+
+  /// The diagram as a group, to ring nodes of
+  pub fn group(&self) -> CoxGroup<f64> {
+    CoxGroup { graph: self.graph.clone(), polys: Vec::new() }
+  }
+}
+
+impl fmt::Display for Kaleidoscope {
+  /// Triangles as Schwarz symbols with the mark opposite to each node, e.g. (5/2 3 2) density 7, H3
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let marks: Vec<String> = if self.rank() == 3 {
+      [(1, 2), (0, 2), (0, 1)].iter().map(|(i, j)| crate::mark(&self.graph, *i, *j).to_string()).collect()
+    } else {
+      self.marks().iter().map(|m| m.to_string()).collect()
+    };
+    let group: Vec<String> = self.group.iter().map(|t| t.to_string()).collect();
+    write!(f, "({}) density {}, {}", marks.join(" "), self.density, group.join("×"))
+  }
+}
+
+/// All pairs i < j of n nodes
+fn pairs(n: usize) -> Vec<(usize, usize)> {
+  (0..n).flat_map(|i| (i+1..n).map(move |j| (i, j))).collect()
+}
+
+/// All marks p/d with 2 <= p <= max and 0 < d < p coprime, the angle between the mirrors is 𝛑d/p
+pub fn marks(max: u32) -> Vec<CoxEdge> {
+  let gcd = |mut a: u32, mut b: u32| {
+    while b != 0 {
+      (a, b) = (b, a % b);
+    }
+    a
+  };
+  let mut all = Vec::new();
+  for p in 2..=max {
+    for d in 1..p {
+      if gcd(p, d) != 1 {
+        continue;
+      }
+      all.push(if d == 1 { CoxEdge::Int(p) } else { CoxEdge::Frac(GenericFraction::new(p, d)) });
+    }
+  }
+  all
+}
+
+/// Sort key of a mark, by numerator and then denominator
+fn key(e: &CoxEdge) -> (u32, u32) {
+  match e {
+    CoxEdge::Int(p) => (*p, 1),
+    CoxEdge::Frac(f) => (*f.numer().unwrap(), *f.denom().unwrap()),
+  }
+}
+
+/// The diagram with n unringed nodes and the given marks on the pairs in lexicographic order
+fn simplex(n: usize, marks: &[CoxEdge]) -> CoxGraph<f64> {
+  let mut graph = CoxGraph::<f64>::default();
+  for i in 0..n {
+    graph.add_node(CoxNode {
+      name: (b'a' + i as u8) as char,
+      repr: "o".to_string(),
+      val: 0f64,
+      mats: Vec::new(),
+      cart_vec: None,
+    });
+  }
+  for ((i, j), e) in pairs(n).iter().zip(marks) {
+    if *e != CoxEdge::Int(2) {
+      graph.add_edge(NodeIndex::new(*i), NodeIndex::new(*j), *e);
+    }
+  }
+  graph
+}
+
+/// All roots of the group of a spherical diagram up to sign, as combinations of the simple roots,
+/// None if they do not close up within `MAX_MIRRORS`, so that the group is infinite
+fn roots(graph: &CoxGraph<f64>) -> Option<Vec<Array1<f64>>> {
+  let g = gram(graph);
+  let n = graph.node_count();
+  let round = |r: &Array1<f64>| -> Vec<i64> {
+    // a root and its negative are the same line
+    let sign = if r.iter().find(|x| x.abs() > 1e-9).map(|x| *x < 0f64).unwrap_or(false) { -1f64 } else { 1f64 };
+    r.iter().map(|x| (sign*x*1e6).round() as i64).collect()
+  };
+  let mut lines: HashSet<Vec<i64>> = HashSet::new();
+  let mut roots: Vec<Array1<f64>> = (0..n).map(|i| {
+    let mut e = Array1::zeros(n);
+    e[i] = 1f64;
+    e
+  }).collect();
+  for r in &roots {
+    lines.insert(round(r));
+  }
+  let mut k = 0;
+  while k < roots.len() {
+    for i in 0..n {
+      let mut r = roots[k].clone();
+      r[i] -= 2f64*g.row(i).dot(&roots[k]);
+      if lines.insert(round(&r)) {
+        roots.push(r);
+        if roots.len() > MAX_MIRRORS {
+          return None;
+        }
+      }
+    }
+    k += 1;
+  }
+  Some(roots)
+}
+
+/// Density of a finite kaleidoscope: the number of chambers of the group inside the simplex
+/// With a generic point p inside the simplex, the chamber wp is inside iff w⁻¹ maps every simple normal of the simplex
+/// to a root that is positive on p, so the elements are told apart by those images, as the group permutes the roots
+/// The simplex is convex, so its chambers are found by walking from the chamber of p through the walls of the chambers,
+/// the mirrors of the simple roots of the group with respect to p
+/// Everything is in the basis of the simple normals of the simplex
+fn density(graph: &CoxGraph<f64>, roots: &[Array1<f64>]) -> Option<u32> {
+  let g = gram(graph);
+  let n = graph.node_count();
+  let weights = Array1::from((0..n).map(|i| std::f64::consts::PI.powi(-(i as i32))).collect::<Vec<_>>());
+  // the value of a root on p
+  let value = |r: &Array1<f64>| r.dot(&weights);
+  let positive: Vec<Array1<f64>> = roots.iter().map(|r| if value(r) > 0f64 { r.clone() } else { -r }).collect();
+  let values: Vec<f64> = positive.iter().map(value).collect();
+  let gp: Vec<Array1<f64>> = positive.iter().map(|r| g.dot(r)).collect();
+  // a positive root r is simple unless it is r = s_a(r) + ca for some positive root a with c > 0 and s_a(r) positive
+  let simple: Vec<&Array1<f64>> = (0..positive.len()).filter(|i| {
+    !(0..positive.len()).any(|j| {
+      let c = positive[*i].dot(&gp[j]);
+      c > 1e-9 && values[*i] - 2f64*c*values[j] > 1e-9
+    })
+  }).map(|i| &positive[i]).collect();
+  if simple.len() != n {
+    return None;
+  }
+  // root 2i is positive[i], root 2i+1 its negative
+  let signed: Vec<Array1<f64>> = positive.iter().flat_map(|r| [r.clone(), -r]).collect();
+  let round = |r: &Array1<f64>| -> Vec<i64> { r.iter().map(|x| (x*1e6).round() as i64).collect() };
+  let index: HashMap<Vec<i64>, usize> = signed.iter().enumerate().map(|(i, r)| (round(r), i)).collect();
+  let perms: Vec<Vec<usize>> = simple.iter().map(|s| {
+    let gs = g.dot(*s);
+    signed.iter().map(|r| index.get(&round(&(r - &(*s*(2f64*gs.dot(r)))))).cloned()).collect::<Option<Vec<usize>>>()
+  }).collect::<Option<_>>()?;
+  // the images of the simple normals of the simplex, a byte each
+  let start: u64 = (0..n).fold(0, |acc, i| {
+    let e = Array1::from((0..n).map(|j| if i == j { 1f64 } else { 0f64 }).collect::<Vec<_>>());
+    acc | ((index[&round(&e)] as u64) << (8*i))
+  });
+  let mut seen: HashSet<u64> = HashSet::from([start]);
+  let mut queue = VecDeque::from(vec![start]);
+  while let Some(state) = queue.pop_front() {
+    for p in &perms {
+      let images = (0..n).map(|i| p[(state >> (8*i)) as usize & 0xff]);
+      if images.clone().all(|r| r % 2 == 0) {
+        let next = images.enumerate().fold(0u64, |acc, (i, r)| acc | ((r as u64) << (8*i)));
+        if seen.insert(next) {
+          queue.push_back(next);
+        }
+      }
+    }
+  }
+  Some(seen.len() as u32)
+}
+
+/// The kaleidoscope of the marks, if the simplex is spherical and the group finite
+fn kaleidoscope(n: usize, marks: &[CoxEdge]) -> Option<Kaleidoscope> {
+  let graph = simplex(n, marks);
+  let g = gram(&graph);
+  let (_, zero, neg) = signature(&g);
+  if zero + neg > 0 {
+    return None;
+  }
+  let roots = roots(&graph)?;
+  let density = density(&graph, &roots)?;
+  let group = identify(&roots, &g, None)?;
+  Some(Kaleidoscope { graph, density, group })
+}
+
+/// The marks of the triangle (p q r) on the pairs (0,1), (0,2), (1,2), p opposite node 0
+fn triangle_marks(t: &[CoxEdge; 3]) -> [CoxEdge; 3] {
+  [t[2], t[1], t[0]]
+}
+
+/// All Schwarz triangles (p q r) with numerators up to `max`, each once with p >= q >= r
+/// The non-dihedral ones all have numerators up to 5, a larger `max` adds more of the dihedral (p 2 2)
+/// e.g. (3 3 2), (5/2 5/2 5/2), (7/3 2 2)
+pub fn schwarz_triangles(max: u32) -> Vec<Kaleidoscope> {
+  let mut ms = marks(max);
+  ms.sort_by_key(|m| std::cmp::Reverse(key(m)));
+  let mut found = Vec::new();
+  for (a, p) in ms.iter().enumerate() {
+    for (b, q) in ms.iter().enumerate().skip(a) {
+      for r in ms.iter().skip(b) {
+        if let Some(k) = kaleidoscope(3, &triangle_marks(&[*p, *q, *r])) {
+          found.push(k);
+        }
+      }
+    }
+  }
+  found
+}
+
+/// All Goursat tetrahedra: 4 mirrors with rational marks (numerators 2 to 5) that generate an irreducible finite group
+/// Each is listed once up to relabelling the nodes, as the smallest of its labellings
+pub fn goursat_tetrahedra() -> Vec<Kaleidoscope> {
+  let ms = marks(5);
+  // faces have to be finite triangles themselves
+  let mut triangles: HashSet<[(u32, u32); 3]> = HashSet::new();
+  let mut list: Vec<[CoxEdge; 3]> = Vec::new();
+  for p in &ms {
+    for q in &ms {
+      for r in &ms {
+        if kaleidoscope(3, &[*p, *q, *r]).is_some() {
+          triangles.insert([key(p), key(q), key(r)]);
+          list.push([*p, *q, *r]);
+        }
+      }
+    }
+  }
+  let perms: Vec<Vec<usize>> = (0..4usize.pow(4))
+    .map(|c| (0..4).map(|k| c/4usize.pow(k) % 4).collect::<Vec<usize>>())
+    .filter(|p| (0..4).all(|i| p.contains(&i)))
+    .collect();
+  let edges = pairs(4);
+  let index = |i: usize, j: usize| edges.iter().position(|e| *e == (i.min(j), i.max(j))).unwrap();
+  let mut found = Vec::new();
+  // the face 012 first, then the marks to node 3, all in the order of `pairs`
+  for [m01, m02, m12] in &list {
+    for m03 in &ms {
+      for m13 in &ms {
+        for m23 in &ms {
+          let t = [*m01, *m02, *m03, *m12, *m13, *m23];
+          let keys: Vec<(u32, u32)> = t.iter().map(key).collect();
+          let faces_ok = [[0, 1, 3], [0, 2, 3], [1, 2, 3]].iter().all(|[i, j, k]| {
+            triangles.contains(&[keys[index(*i, *j)], keys[index(*i, *k)], keys[index(*j, *k)]])
+          });
+          let canonical = faces_ok && perms.iter().all(|p| {
+            let relabelled: Vec<(u32, u32)> = edges.iter().map(|(i, j)| keys[index(p[*i], p[*j])]).collect();
+            keys <= relabelled
+          });
+          if !canonical || components(&simplex(4, &t)).len() > 1 {
+            continue;
+          }
+          if let Some(k) = kaleidoscope(4, &t) {
+            if k.group.len() == 1 {
+              found.push(k);
+            }
+          }
+        }
+      }
+    }
+  }
+  found
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn triangles() {
+    let all = schwarz_triangles(5);
+    let find = |s: &str| all.iter().find(|k| k.to_string().starts_with(s)).map(|k| k.to_string());
+    assert_eq!(find("(5/2 3 2)"), Some("(5/2 3 2) density 7, H3".to_string()));
+    assert_eq!(find("(5 3 2)"), Some("(5 3 2) density 1, H3".to_string()));
+    assert_eq!(find("(3/2 3 3)"), Some("(3/2 3 3) density 2, A3".to_string()));
+    assert_eq!(find("(5/2 5/2 5/2)"), Some("(5/2 5/2 5/2) density 6, H3".to_string()));
+    // 5 tetrahedral, 7 octahedral and 32 icosahedral ones besides the (p 2 2)
+    assert_eq!(all.iter().filter(|k| k.group.len() == 1).count(), 44);
+    assert_eq!(find("(3 3 2)").map(|s| s.contains("A3")), Some(true));
+    // (p 2 2) for every p/d
+    assert_eq!(all.iter().filter(|k| k.group.len() == 2).count(), marks(5).len() - 1);
+    let dihedral = schwarz_triangles(7);
+    assert!(dihedral.iter().any(|k| k.to_string().starts_with("(7/3 2 2) density 3")));
+    // every triangle is spherical and its density fits the area of the triangle
+    for k in &all {
+      let area = k.marks().iter().map(|m| std::f64::consts::PI/f64::from(m)).sum::<f64>() - std::f64::consts::PI;
+      let order: u64 = crate::invariants::Invariants::from_types(&k.group).unwrap().order;
+      assert!((order as f64*area/(4f64*std::f64::consts::PI) - k.density as f64).abs() < 1e-9, "{}", k);
+    }
+  }
+
+  #[test]
+  fn tetrahedra() {
+    let all = goursat_tetrahedra();
+    let densities = |t: CoxType| {
+      let mut d: Vec<u32> = all.iter().filter(|k| k.group == vec![t]).map(|k| k.density).collect();
+      d.sort();
+      d.dedup();
+      d
+    };
+    // the regular star polychora have densities 4, 6, 20, 66, 76 and 191
+    let h4 = densities(CoxType::H(4));
+    assert!([1, 4, 6, 20, 66, 76, 191].iter().all(|d| h4.contains(d)), "{:?}", h4);
+    assert_eq!(densities(CoxType::A(4))[0], 1);
+    assert!(all.iter().all(|k| k.rank() == 4 && k.density >= 1));
+  }
+}
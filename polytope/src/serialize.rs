@@ -0,0 +1,194 @@
+use std::cmp::Ordering;
+
+use petgraph::graph::NodeIndex;
+
+use crate::{components, mark, CoxGraph, CoxGroup};
+
+/// One way of writing a connected diagram: the nodes in order of appearance,
+/// split into the main chain and the branch chains that start from an earlier node
+#[derive(Debug, Clone)]
+struct Layout {
+  order: Vec<usize>,
+  chains: Vec<(Option<usize>, Vec<usize>)>,
+}
+
+impl Layout {
+  fn letter(&self, node: usize) -> char {
+    let pos = self.order.iter().position(|n| *n == node).unwrap();
+    (b'a' + pos as u8) as char
+  }
+}
+
+/// Sorting key and Dynkin string of a layout
+type Rendered = ((usize, Vec<f64>, Vec<(bool, String)>), String);
+
+/// Writes the layout as a Dynkin string, and returns the sorting key for choosing between layouts:
+/// (number of branches, marks with the larger marks first, unringed nodes last, string)
+fn render<T: Clone>(graph: &CoxGraph<T>, layout: &Layout) -> Rendered {
+  let node = |i: usize| {
+    let r = &graph[NodeIndex::new(i)].repr;
+    if r.chars().count() == 1 && r.chars().all(char::is_alphabetic) { r.clone() } else { format!("({})", r) }
+  };
+  let mut s = String::new();
+  let mut marks: Vec<f64> = Vec::new();
+  let mut branches = 0;
+  let mut tree: Vec<(usize, usize)> = Vec::new();
+  for (from, chain) in &layout.chains {
+    let mut prev = match from {
+      Some(f) => {
+        branches += 1;
+        s.push_str(&format!(" *{}", layout.letter(*f)));
+        Some(*f)
+      },
+      None => None,
+    };
+    for n in chain {
+      if let Some(p) = prev {
+        let e = mark(graph, p, *n);
+        marks.push(-f64::from(&e));
+        s.push_str(&e.to_string());
+        tree.push((p.min(*n), p.max(*n)));
+      }
+      s.push_str(&node(*n));
+      prev = Some(*n);
+    }
+  }
+  // edges that are not on any chain close a cycle: *i<edge>*j
+  let mut extra: Vec<(char, char, String)> = Vec::new();
+  for e in graph.edge_indices() {
+    let (a, b) = graph.edge_endpoints(e).unwrap();
+    let (a, b) = (a.index().min(b.index()), a.index().max(b.index()));
+    if layout.order.contains(&a) && !tree.contains(&(a, b)) {
+      let (la, lb) = (layout.letter(a), layout.letter(b));
+      extra.push((la.min(lb), la.max(lb), graph[e].to_string()));
+    }
+  }
+  extra.sort();
+  for (a, b, e) in extra {
+    branches += 1;
+    s.push_str(&format!(" *{}{}*{}", a, e, b));
+  }
+  let reprs = layout.order.iter().map(|n| {
+    let r = graph[NodeIndex::new(*n)].repr.clone();
+    (r == "o", r)
+  }).collect();
+  ((branches, marks, reprs), s)
+}
+
+/// Enumerates all layouts of the component that only end a chain when the last node has no unwritten neighbours,
+/// and keeps the best one according to `render`
+fn layouts<T: Clone>(graph: &CoxGraph<T>, layout: &mut Layout, best: &mut Option<Rendered>, size: usize) {
+  let unplaced = |layout: &Layout, i: usize| -> Vec<usize> {
+    let mut ns: Vec<usize> = graph.neighbors(NodeIndex::new(i)).map(|n| n.index()).filter(|n| !layout.order.contains(n)).collect();
+    ns.sort();
+    ns.dedup();
+    ns
+  };
+  let last = *layout.chains.last().unwrap().1.last().unwrap();
+  let next = unplaced(layout, last);
+  if !next.is_empty() {
+    // continue the current chain
+    for n in next {
+      layout.order.push(n);
+      layout.chains.last_mut().unwrap().1.push(n);
+      layouts(graph, layout, best, size);
+      layout.chains.last_mut().unwrap().1.pop();
+      layout.order.pop();
+    }
+    return;
+  }
+  if layout.order.len() == size {
+    let candidate = render(graph, layout);
+    let better = match best {
+      Some(b) => compare(&candidate, b) == Ordering::Less,
+      None => true,
+    };
+    if better {
+      *best = Some(candidate);
+    }
+    return;
+  }
+  // start a new branch from any written node
+  for f in layout.order.clone() {
+    for n in unplaced(layout, f) {
+      layout.order.push(n);
+      layout.chains.push((Some(f), vec![n]));
+      layouts(graph, layout, best, size);
+      layout.chains.pop();
+      layout.order.pop();
+    }
+  }
+}
+
+fn compare(a: &Rendered, b: &Rendered) -> Ordering {
+  let ((ba, ma, ra), sa) = a;
+  let ((bb, mb, rb), sb) = b;
+  ba.cmp(bb)
+    .then(ma.partial_cmp(mb).unwrap_or(Ordering::Equal))
+    .then(ra.cmp(rb))
+    .then(sa.cmp(sb))
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Writes the diagram as a canonical Dynkin string, which `Scanner::parse_tope` reads back into an isomorphic diagram
+  /// Every component is written as a main chain with branches " *<index><edge><node>..." and cycle closures " *<index><edge>*<index>",
+  /// choosing among all ways of writing it the one with
+  /// 1. the fewest branches
+  /// 2. the larger marks first, like in bracket notation
+  /// 3. ringed nodes first
+  ///
+  /// The components are sorted and separated by spaces, so the string does not depend on the node order
+  /// e.g. o3o4x -> x4o3o, o3o3x -> x3o3o, x3o x4o -> x4o x3o
+  pub fn dynkin(&self) -> String {
+    let mut comps: Vec<Rendered> = Vec::new();
+    for comp in components(&self.graph) {
+      let mut best = None;
+      for start in &comp {
+        let mut layout = Layout { order: vec![*start], chains: vec![(None, vec![*start])] };
+        layouts(&self.graph, &mut layout, &mut best, comp.len());
+      }
+      comps.push(best.unwrap());
+    }
+    comps.sort_by(compare);
+    comps.into_iter().map(|c| c.1).collect::<Vec<_>>().join(" ")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::Scanner;
+  use petgraph::algo::is_isomorphic_matching;
+
+  fn canonical(s: &str) -> String {
+    let mut sc = Scanner::new(s);
+    sc.parse_tope::<f64>().unwrap().dynkin()
+  }
+
+  #[test]
+  fn dynkin_canonical() {
+    assert_eq!(canonical("o3o4x"), "x4o3o");
+    assert_eq!(canonical("x3o4o"), "o4o3x");
+    assert_eq!(canonical("o3o3x"), "x3o3o");
+    assert_eq!(canonical("x3o x4o"), "x4o x3o");
+    assert_eq!(canonical("x10o5/2x"), "x10o5/2x");
+    assert_eq!(canonical("o3o3o3o *c3o"), canonical("o3o3o *b3o3o"));
+  }
+
+  #[test]
+  fn dynkin_round_trip() {
+    for s in ["x3o3o *b3o", "o3x3o3o *b3o3o", "x3o3o3o *a", "x4o3o *b3o *c3x", "x3o3o *a3*c x5/2o", "x3o3o3o3o *c3o",
+              "x4(-x)3o", "(f+f)3o3x", "x3(2)"] {
+      let mut sc = Scanner::new(s);
+      let g = sc.parse_tope::<f64>().unwrap();
+      let d = g.dynkin();
+      let mut sc2 = Scanner::new(&d);
+      let g2 = sc2.parse_tope::<f64>().unwrap();
+      assert!(is_isomorphic_matching(&g.graph, &g2.graph, |a, b| a.repr == b.repr, |a, b| a == b), "{} -> {}", s, d);
+      assert_eq!(g2.dynkin(), d);
+    }
+    let g = Scanner::new("x3(2x-q)").parse_tope::<f64>().unwrap();
+    assert!((g.graph[NodeIndex::new(1)].val - (2f64 - 2f64.sqrt())).abs() < 1e-12);
+    assert!(Scanner::new("x3(x+)").parse_tope::<f64>().is_err());
+  }
+}
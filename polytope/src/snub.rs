@@ -0,0 +1,534 @@
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use exact::enums::frac;
+use exact::Expr;
+use ndarray::{Array1, Array2};
+use ndarray_linalg::Solve;
+use petgraph::graph::NodeIndex;
+
+use crate::classify::{gram, signature};
+use crate::interval::{newton, Interval};
+use crate::lattice::wythoffian;
+use crate::orbit::{normals, PointSet, MAX_ORBIT};
+use crate::product::Polytope;
+use crate::radius::radical;
+use crate::roots::{gcd, Cyc, CycFrac};
+use crate::{determine_cartesian, mark, CoxGraph, CoxGroup, Failed};
+
+/// Newton steps smaller than this end the iteration
+const EPS: f64 = 1e-12;
+
+/// Most Newton steps before giving up
+const MAX_STEPS: usize = 200;
+
+impl Polytope {
+  /// Alternation: keeps every other vertex of a polytope whose edges form a bipartite graph, starting with vertex 0
+  /// Every k-face keeps its remaining vertices if there are enough of them for a k-face (so squares become digons and vanish),
+  /// and every removed vertex leaves a (k-1)-face in every k-face it was in, spanned by its neighbours in there
+  /// e.g. truncated octahedron -> icosahedron (with unequal edges), omnitruncated cube -> snub cube
+  pub fn alternate(&self) -> Result<Polytope, Failed> {
+    let n = self.verts.len();
+    let rank = self.rank();
+    if rank < 2 {
+      println!("Only polytopes of rank 2 and up can be alternated");
+      return Err(Failed);
+    }
+    let mut adjacent: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for e in &self.faces[1] {
+      adjacent[e[0]].push(e[1]);
+      adjacent[e[1]].push(e[0]);
+    }
+    let mut color: Vec<Option<bool>> = vec![None; n];
+    color[0] = Some(true);
+    let mut queue = VecDeque::from(vec![0]);
+    while let Some(v) = queue.pop_front() {
+      for w in &adjacent[v] {
+        match color[*w] {
+          None => {
+            color[*w] = color[v].map(|c| !c);
+            queue.push_back(*w);
+          },
+          Some(c) if Some(c) == color[v] => {
+            println!("The edges are not bipartite, cannot alternate");
+            return Err(Failed);
+          },
+          _ => {},
+        }
+      }
+    }
+    let kept: Vec<usize> = (0..n).filter(|v| color[*v] == Some(true)).collect();
+    let mut index = vec![usize::MAX; n];
+    for (i, v) in kept.iter().enumerate() {
+      index[*v] = i;
+    }
+    let renumber = |vs: &mut dyn Iterator<Item = &usize>| -> Vec<usize> {
+      let mut f: Vec<usize> = vs.filter(|v| color[**v] == Some(true)).map(|v| index[*v]).collect();
+      f.sort();
+      f
+    };
+
+    let mut faces: Vec<Vec<Vec<usize>>> = vec![Vec::new(); rank + 1];
+    let mut seen: Vec<HashSet<Vec<usize>>> = vec![HashSet::new(); rank + 1];
+    let mut add = |k: usize, f: Vec<usize>, faces: &mut Vec<Vec<Vec<usize>>>| {
+      if f.len() > k && seen[k].insert(f.clone()) {
+        faces[k].push(f);
+      }
+    };
+    faces[0] = (0..kept.len()).map(|i| vec![i]).collect();
+    for k in 2..=rank {
+      for f in &self.faces[k] {
+        add(k, renumber(&mut f.iter()), &mut faces);
+      }
+    }
+    for v in (0..n).filter(|v| color[*v] == Some(false)) {
+      for k in 2..=rank {
+        for f in self.faces[k].iter().filter(|f| f.binary_search(&v).is_ok()) {
+          let nbrs = renumber(&mut adjacent[v].iter().filter(|w| f.binary_search(w).is_ok()));
+          add(k - 1, nbrs, &mut faces);
+        }
+      }
+    }
+    Ok(Polytope {
+      verts: kept.iter().map(|v| self.verts[*v].clone()).collect(),
+      faces,
+    })
+  }
+}
+
+/// Distance of the iterated ratio of values to the root that the interval Newton iteration starts from
+const ROOT_RADIUS: f64 = 1e-6;
+
+/// A number with a rigorous bound on its distance to the true value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Certified {
+  pub value: f64,
+  pub error: f64,
+}
+
+impl fmt::Display for Certified {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} ± {:e}", self.value, self.error)
+  }
+}
+
+/// Recognizes x as a small element of Z[2cos(𝛑/m)] for one of the quadratic rings m = 4, 5, 6 (√2, φ, √3) or the integers
+pub fn recognize(x: f64) -> Option<Cyc> {
+  if (x - x.round()).abs() < 1e-9 {
+    return Some(Cyc::int(x.round() as i64));
+  }
+  for m in [5, 4, 6] {
+    let c = Cyc::gen(m);
+    let cf = c.to_f64();
+    for b in (1..=24).flat_map(|b| [b, -b]) {
+      let a = (x - b as f64*cf).round();
+      if (x - a - b as f64*cf).abs() < 1e-9 {
+        return Some(&Cyc::int(a as i64) + &(&Cyc::int(b) * &c));
+      }
+    }
+  }
+  None
+}
+
+/// A real root of a polynomial with integer coefficients
+#[derive(Debug, Clone)]
+pub struct Algebraic {
+  /// coefficients, lowest first, e.g. [-1, -1, -1, 1] for x³-x²-x-1 with the tribonacci constant as root
+  pub polynomial: Vec<i64>,
+  /// an interval with the root and no other root of the polynomial
+  pub interval: Interval,
+  /// the root by radicals, if there is a real formula for it
+  pub expr: Option<Expr>,
+}
+
+/// Result of `CoxGroup::snub`: the values of the snub nodes that make all edges 1, and the polytope with those values
+#[derive(Debug, Clone)]
+pub struct Snub {
+  /// for every node marked s: the value, and its exact form if it can be written with radicals
+  /// The error is infinite if the values could not be solved for exactly, see `CoxGroup::snub`
+  pub values: Vec<(NodeIndex, Certified, Option<Expr>)>,
+  /// for three s nodes: the nodes a and b, and the ratio of their values a/b, which all values are solved from
+  pub ratio: Option<(NodeIndex, NodeIndex, Algebraic)>,
+  pub polytope: Polytope,
+}
+
+/// Polynomial with coefficients in Q(2cos(𝛑/m)), lowest first
+type Poly = Vec<CycFrac>;
+
+fn poly_add(a: &Poly, b: &Poly) -> Poly {
+  let zero = CycFrac::int(0);
+  (0..a.len().max(b.len())).map(|k| a.get(k).unwrap_or(&zero) + b.get(k).unwrap_or(&zero)).collect()
+}
+
+fn poly_mul(a: &Poly, b: &Poly) -> Poly {
+  let mut c = vec![CycFrac::int(0); (a.len() + b.len()).saturating_sub(1)];
+  for (i, x) in a.iter().enumerate() {
+    for (j, y) in b.iter().enumerate() {
+      c[i + j] = &c[i + j] + &(x*y);
+    }
+  }
+  c
+}
+
+fn poly_eval(a: &Poly, x: &CycFrac) -> CycFrac {
+  a.iter().rev().fold(CycFrac::int(0), |acc, c| &(&acc*x) + c)
+}
+
+/// a/(x - s) for a root s of a, by synthetic division
+fn deflate(a: &Poly, s: &CycFrac) -> Poly {
+  let mut quotient = vec![CycFrac::int(0); a.len() - 1];
+  let mut carry = CycFrac::int(0);
+  for k in (1..a.len()).rev() {
+    carry = &a[k] + &(&carry*s);
+    quotient[k - 1] = carry.clone();
+  }
+  quotient
+}
+
+/// The polynomial of the ratio x = a/b of the values of a snub of three nodes a, b and c, all s
+/// The edges from the seed v to its images under the rotations r_i r_j have |v - r_i r_j v|² = v_i² + v_j² + c_ij v_i v_j,
+/// c_ij = 2cos(𝛑/m_ij), by the law of cosines. With z = c/b, equating the edges of ab, bc and ac gives the two conics
+/// x² + px = z² + qz and 1 + px = z² + rxz (p = c_ab, q = c_bc, r = c_ac), whose difference x² - 1 + (rx - q)z = 0
+/// is linear in z, and putting z from there into the second one leaves
+/// P(x) = (1-x²)² + rx(1-x²)(rx-q) - (1+px)(rx-q)²
+/// e.g. for s3s4s P(x) = -(x+1)(x³-x²-x-1), which has the tribonacci constant as root
+fn eliminate(p: &CycFrac, q: &CycFrac, r: &CycFrac) -> Poly {
+  let (zero, one) = (CycFrac::int(0), CycFrac::int(1));
+  let square = vec![one.clone(), zero.clone(), -&one];
+  let line = vec![-q, r.clone()];
+  let first = poly_mul(&square, &square);
+  let second = poly_mul(&vec![zero, r.clone()], &poly_mul(&square, &line));
+  let third = poly_mul(&vec![one, p.clone()], &poly_mul(&line, &line));
+  let mut poly = poly_add(&poly_add(&first, &second), &third.iter().map(|a| -a).collect());
+  while poly.last().is_some_and(|a| a.is_zero()) {
+    poly.pop();
+  }
+  poly
+}
+
+/// The product of the distinct Galois conjugates of a polynomial over Q(2cos(𝛑/m)), scaled to coprime integer coefficients
+/// It has the roots of the polynomial and rational coefficients, e.g. x⁶-2x⁵-x⁴+x³+2x²+x-1 from x³-x²-x-φ
+fn norm(a: &Poly, m: u32) -> Option<Vec<i64>> {
+  let mut conjugates: Vec<Poly> = Vec::new();
+  for k in (1..m.max(2)).filter(|k| gcd(*k as i64, 2*m as i64) == 1) {
+    let c: Poly = a.iter().map(|x| x.conjugate(k)).collect();
+    if !conjugates.iter().any(|d| d.iter().zip(&c).all(|(x, y)| (x - y).is_zero())) {
+      conjugates.push(c);
+    }
+  }
+  let product = conjugates.iter().fold(vec![CycFrac::int(1)], |acc, c| poly_mul(&acc, c));
+  let den = product.iter().fold(1, |l, x| l/gcd(l, x.den)*x.den);
+  let ints: Vec<i64> = product.iter().map(|x| Some(x.num.as_int()?*(den/x.den))).collect::<Option<_>>()?;
+  let g = ints.iter().fold(0, |g, a| gcd(g, *a))*ints.last()?.signum();
+  Some(ints.iter().map(|a| a/g).collect())
+}
+
+/// The root near x0 of a polynomial of degree 1, 2 or 3 by radicals: the quadratic formula, or Cardano's formula
+/// x = -b/3 + ∛(-Q/2 + √Δ) + ∛(-Q/2 - √Δ) for x³ + bx² + cx + d, with Δ = Q²/4 + P³/27, P = c - b²/3, Q = 2b³/27 - bc/3 + d
+/// None for higher degrees, and for cubics with three real roots (Δ < 0), which need complex cube roots
+fn radicals(poly: &Poly, x0: f64) -> Option<Expr> {
+  let lead = poly.last()?.inverse()?;
+  let monic: Vec<CycFrac> = poly.iter().map(|a| a*&lead).collect();
+  let f = |k: i64, n: i64| CycFrac::new(Cyc::int(k), n);
+  let x = match monic.as_slice() {
+    [c0, _] => (-c0).to_expr(),
+    [c0, c1, _] => {
+      let disc = &(c1*c1) - &(&f(4, 1)*c0);
+      if disc.to_f64() < 0f64 {
+        return None;
+      }
+      let (minus, root) = ((-c1).to_expr(), radical(disc));
+      let half = Expr::Val(frac(1, 2));
+      let near = |e: &Expr| (e.to_f64() - x0).abs();
+      let (x, y) = ((minus.clone() + root.clone())*half.clone(), (minus - root)*half);
+      if near(&x) < near(&y) { x } else { y }
+    },
+    [d, c, b, _] => {
+      let p = c - &(&(b*b)*&f(1, 3));
+      let q = &(&(&(&(b*b)*b)*&f(2, 27)) - &(&(b*c)*&f(1, 3))) + d;
+      let delta = &(&(&q*&q)*&f(1, 4)) + &(&(&(&p*&p)*&p)*&f(1, 27));
+      if delta.to_f64() <= 0f64 {
+        return None;
+      }
+      let (half, root) = ((-&(&q*&f(1, 2))).to_expr(), radical(delta));
+      (-&(b*&f(1, 3))).to_expr() + (half.clone() + root.clone()).root(3) + (half - root).root(3)
+    },
+    _ => return None,
+  };
+  if (x.to_f64() - x0).abs() < 1e-9 { Some(x) } else { None }
+}
+
+/// The nodes a and b with their ratio a/b, and the value of every node, see `solve`
+type Solution = (NodeIndex, NodeIndex, Algebraic, Vec<(Certified, Option<Expr>)>);
+
+/// Exact values of a snub of three nodes, all s, from the values found by iteration, see `eliminate`
+/// The middle node b of the diagram is tried first, so the ratio is the tribonacci constant for s3s4s,
+/// and roots 0 and ±1 that are not the ratio are divided out of P
+/// If P vanishes, as for s3s3s where the conics share the line x + z + 1 = 0, the ratio is 1 if q = r
+/// The ratio is enclosed by interval Newton on the norm of P, and b = ±1/√(1 + px + x²), a = xb and c = zb are
+/// evaluated in interval arithmetic, and exactly if x has a formula by radicals
+fn solve(graph: &CoxGraph<f64>, values: &Array1<f64>) -> Option<Solution> {
+  let marks: Vec<Vec<Cyc>> = (0..3).map(|i| (0..3).map(|j| Cyc::from_edge(&mark(graph, i, j))).collect()).collect();
+  let m = marks.iter().flatten().map(|c| c.m()).fold(1, |l, m| l/gcd(l as i64, m as i64) as u32*m);
+  let c = |i: usize, j: usize| CycFrac { num: marks[i][j].lift(m), den: 1 };
+  let mut middles: Vec<usize> = (0..3).collect();
+  middles.sort_by_key(|j| Reverse((0..3).filter(|i| i != j && !marks[*i][*j].is_zero()).count()));
+  for j in middles {
+    let (i, k) = match j { 0 => (1, 2), 1 => (0, 2), _ => (0, 1) };
+    if values[j].abs() < 1e-9 {
+      continue;
+    }
+    let (p, q, r) = (c(i, j), c(j, k), c(i, k));
+    let (x0, z0) = (values[i]/values[j], values[k]/values[j]);
+    let mut poly = eliminate(&p, &q, &r);
+    if poly.is_empty() {
+      if !(&q - &r).is_zero() || (x0 - 1f64).abs() > 1e-9 {
+        continue;
+      }
+      poly = vec![CycFrac::int(-1), CycFrac::int(1)];
+    }
+    for s in [0, 1, -1].map(CycFrac::int) {
+      while poly.len() > 2 && poly_eval(&poly, &s).is_zero() {
+        poly = if (x0 - s.to_f64()).abs() < 1e-9 { vec![-&s, CycFrac::int(1)] } else { deflate(&poly, &s) };
+      }
+    }
+    let polynomial = norm(&poly, m)?;
+    let x = match newton(&polynomial.iter().map(|a| *a as f64).collect::<Vec<_>>(), x0, ROOT_RADIUS) {
+      Some(x) => x,
+      None => continue,
+    };
+    // the values in interval arithmetic
+    let [pi, qi, ri] = [&p, &q, &r].map(|a| Interval::approx(a.to_f64()));
+    let one = Interval::point(1f64);
+    let sign = Interval::point(values[j].signum());
+    let bi = sign*(one + pi*x + x*x).sqrt()?.inverse()?;
+    let line = ri*x - qi;
+    // z from the line, or from the second conic z² + rxz - (1 + px) = 0 where rx - q = 0
+    let branch = (2f64*z0 + r.to_f64()*x0).signum();
+    let zi = match line.inverse() {
+      Some(inv) => (one - x*x)*inv,
+      None => (-(ri*x) + Interval::point(branch)*(ri*ri*x*x + Interval::point(4f64)*(one + pi*x)).sqrt()?)*Interval::point(0.5),
+    };
+    let intervals = [(i, x*bi), (j, bi), (k, zi*bi)];
+    // the exact values, in Q(2cos(𝛑/m)) under a square root if x is there
+    let expr = radicals(&poly, x0);
+    let exprs: Option<Vec<Expr>> = expr.as_ref().map(|xe| {
+      let half = Expr::Val(frac(1, 2));
+      let (b, z) = if poly.len() == 2 {
+        let xc = &(&-&poly[0]*&poly[1].inverse().unwrap());
+        let b = radical((&(&CycFrac::int(1) + &(&p*xc)) + &(xc*xc)).inverse().unwrap());
+        let line = &(&r*xc) - &q;
+        let z = match line.inverse() {
+          Some(inv) => (&(&CycFrac::int(1) - &(xc*xc))*&inv).to_expr(),
+          None => {
+            let disc = radical(&(&(&(&r*&r)*xc)*xc) + &(&CycFrac::int(4)*&(&CycFrac::int(1) + &(&p*xc))));
+            ((-&(&r*xc)).to_expr() + Expr::int(branch as i64)*disc)*half
+          },
+        };
+        (b, z)
+      } else {
+        let base = Expr::int(1) + p.to_expr()*xe.clone() + xe.clone()*xe.clone();
+        let line = r.to_expr()*xe.clone() - q.to_expr();
+        let (b, z) = (base.pow(frac(-1, 2)), (Expr::int(1) - xe.clone()*xe.clone())*line.recip());
+        (b, z)
+      };
+      let b = Expr::int(values[j].signum() as i64)*b;
+      vec![xe.clone()*b.clone(), b.clone(), z*b]
+    });
+    let mut solved = vec![(Certified { value: 0f64, error: f64::INFINITY }, None); 3];
+    for (l, (node, v)) in intervals.iter().enumerate() {
+      if (v.mid() - values[*node]).abs() > 1e-9 {
+        return None;
+      }
+      let e = exprs.as_ref().map(|es| es[l].clone()).filter(|e| (e.to_f64() - v.mid()).abs() < 1e-9);
+      solved[*node] = (Certified { value: v.mid(), error: v.radius() }, e);
+    }
+    let ratio = Algebraic { polynomial, interval: x, expr };
+    return Some((NodeIndex::new(i), NodeIndex::new(j), ratio, solved));
+  }
+  None
+}
+
+impl CoxGroup<f64> {
+  /// Snub polytope of a diagram with s nodes, e.g. s3s4s
+  /// The vertices are the alternated vertices of the polytope with the s nodes ringed,
+  /// and the values of the s nodes are solved for (Gauss-Newton, starting from 1) such that all edges get length 1
+  /// Other ringed nodes keep their value, so they fix the scale
+  /// For three s nodes the values are then solved for exactly, with rigorous error bounds, see `solve`;
+  /// otherwise, or if that fails, as at the double roots where vertices meet, the error is infinite
+  /// Fails if the edges cannot be alternated or cannot all be made equal
+  pub fn snub(&self) -> Result<Snub, Failed> {
+    let mut symm = self.graph.clone();
+    let n = symm.node_count();
+    let unknowns: Vec<usize> = (0..n).filter(|i| symm[NodeIndex::new(*i)].repr == "s").collect();
+    let (_, zero, neg) = signature(&gram(&symm));
+    if unknowns.is_empty() || zero + neg > 0 {
+      println!("Snubs need a finite group with s nodes");
+      return Err(Failed);
+    }
+    determine_cartesian(&mut symm)?;
+    let normals = normals(&symm);
+    let dims = normals[0].len();
+    // seed = basis·values
+    let mut basis = Array2::<f64>::zeros((dims, n));
+    for i in 0..n {
+      basis.column_mut(i).assign(symm[NodeIndex::new(i)].cart_vec.as_ref().unwrap());
+    }
+    let mut values = Array1::from(symm.node_weights().map(|node| node.val).collect::<Vec<_>>());
+
+    // the group elements that map the seed to each vertex
+    let reflections: Vec<Array2<f64>> = normals.iter().map(|m| {
+      Array2::eye(dims) - &(m.clone().insert_axis(ndarray::Axis(1)).dot(&m.clone().insert_axis(ndarray::Axis(0)))*2f64)
+    }).collect();
+    let seed = basis.dot(&values);
+    let mut points = PointSet::default();
+    points.insert(seed);
+    let mut elements = vec![Array2::<f64>::eye(dims)];
+    let mut queue = VecDeque::from(vec![0]);
+    while let Some(i) = queue.pop_front() {
+      for r in &reflections {
+        let g = r.dot(&elements[i]);
+        let (j, new) = points.insert(g.dot(&points.points[0]));
+        if new {
+          elements.push(g);
+          queue.push_back(j);
+          if points.len() > MAX_ORBIT {
+            return Err(Failed);
+          }
+        }
+      }
+    }
+    let full = wythoffian(&symm)?;
+    let alternated = full.alternate()?;
+    let original: Vec<usize> = alternated.verts.iter().map(|v| points.find(v).unwrap()).collect();
+
+    // one equation |p - g p|² = 1 per neighbour g p of the seed, a quadratic form in the values
+    let mut forms: Vec<Array2<f64>> = Vec::new();
+    for e in alternated.faces[1].iter().filter(|e| e[0] == 0) {
+      let d = Array2::<f64>::eye(dims) - &elements[original[e[1]]];
+      let m = d.dot(&basis);
+      let q = m.t().dot(&m);
+      if !forms.iter().any(|f| f.abs_diff_eq(&q, 1e-9)) {
+        forms.push(q);
+      }
+    }
+    let residual = |v: &Array1<f64>| Array1::from(forms.iter().map(|q| v.dot(&q.dot(v)) - 1f64).collect::<Vec<_>>());
+    let mut step = f64::MAX;
+    for _ in 0..MAX_STEPS {
+      let r = residual(&values);
+      let mut jac = Array2::<f64>::zeros((forms.len(), unknowns.len()));
+      for (i, q) in forms.iter().enumerate() {
+        let grad = q.dot(&values)*2f64;
+        for (k, u) in unknowns.iter().enumerate() {
+          jac[[i, k]] = grad[*u];
+        }
+      }
+      let delta = match jac.t().dot(&jac).solve(&-jac.t().dot(&r)) {
+        Ok(d) => d,
+        Err(_) => {
+          println!("Singular system while equalizing the edges");
+          return Err(Failed);
+        }
+      };
+      for (k, u) in unknowns.iter().enumerate() {
+        values[*u] += delta[k];
+      }
+      let last = step;
+      step = delta.iter().fold(0f64, |m, d| m.max(d.abs()));
+      if step < EPS || (step >= last && step < 1e-9) {
+        break;
+      }
+    }
+    let r = residual(&values);
+    if r.iter().any(|x| x.abs() > 1e-9) {
+      println!("The edges cannot all be made equal, residual {}", r);
+      return Err(Failed);
+    }
+    let exact = if unknowns.len() == 3 && n == 3 { solve(&self.graph, &values) } else { None };
+    let (ratio, solved) = match exact {
+      Some((a, b, ratio, solved)) => (Some((a, b, ratio)), solved),
+      None => (None, unknowns.iter().map(|u| (Certified { value: values[*u], error: f64::INFINITY }, None)).collect()),
+    };
+    for (u, (c, _)) in unknowns.iter().zip(&solved) {
+      values[*u] = c.value;
+    }
+    let seed = basis.dot(&values);
+    Ok(Snub {
+      values: unknowns.iter().zip(solved).map(|(u, (c, e))| (NodeIndex::new(*u), c, e)).collect(),
+      ratio,
+      polytope: Polytope {
+        verts: original.iter().map(|i| elements[*i].dot(&seed)).collect(),
+        faces: alternated.faces,
+      },
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::Scanner;
+
+  fn snub(s: &str) -> Snub {
+    let mut sc = Scanner::new(s);
+    sc.parse_tope::<f64>().unwrap().snub().unwrap()
+  }
+
+  fn check(s: &str, counts: Vec<usize>, radius: f64) {
+    let snub = snub(s);
+    let p = &snub.polytope;
+    assert_eq!(p.faces.iter().map(|f| f.len()).collect::<Vec<_>>(), counts, "{}", s);
+    for e in &p.faces[1] {
+      let d = &p.verts[e[0]] - &p.verts[e[1]];
+      assert!((d.dot(&d) - 1f64).abs() < 1e-9, "{}", s);
+    }
+    assert!((p.verts[0].dot(&p.verts[0]).sqrt() - radius).abs() < 1e-9, "{}", s);
+  }
+
+  #[test]
+  fn snubs() {
+    // icosahedron, snub cube, snub dodecahedron
+    check("s3s3s", vec![12, 30, 20, 1], ((5f64 + 5f64.sqrt())/8f64).sqrt());
+    check("s3s4s", vec![24, 60, 38, 1], 1.3437133737446);
+    check("s3s5s", vec![60, 150, 92, 1], 2.1558373751156);
+  }
+
+  #[test]
+  fn alternation() {
+    let mut sc = Scanner::new("x3x3x");
+    let truncated_octahedron = sc.parse_tope::<f64>().unwrap().polytope().unwrap();
+    let a = truncated_octahedron.alternate().unwrap();
+    assert_eq!(a.faces.iter().map(|f| f.len()).collect::<Vec<_>>(), vec![12, 30, 20, 1]);
+    // odd cycles cannot be alternated
+    let mut sc = Scanner::new("x3o3o");
+    assert!(sc.parse_tope::<f64>().unwrap().polytope().unwrap().alternate().is_err());
+  }
+
+  #[test]
+  fn exact_values() {
+    // the snub cube needs the tribonacci constant, which is in none of the rings
+    let s = snub("s3s4s");
+    let (a, b, ratio) = s.ratio.unwrap();
+    assert_eq!((a.index(), b.index()), (0, 1));
+    assert_eq!(ratio.polynomial, vec![-1, -1, -1, 1]);
+    assert!(ratio.interval.contains(1.839286755214161) && ratio.interval.radius() < 1e-12);
+    assert_eq!(ratio.expr.unwrap().to_string(), "(1+∛(19+3√33)+∛(19-3√33))/3");
+    for (_, c, e) in &s.values {
+      assert!(c.error < 1e-12);
+      assert!((e.as_ref().unwrap().to_f64() - c.value).abs() < 1e-12);
+    }
+    // the snub dodecahedron: a cubic over Q(φ), so a sextic over Q
+    let (_, _, ratio) = snub("s3s5s").ratio.unwrap();
+    assert_eq!(ratio.polynomial, vec![-1, 1, 2, 1, -1, -2, 1]);
+    assert!(ratio.expr.is_some());
+    // the icosahedron as s3s3s, with the golden ratio for a/b
+    let s = snub("s3s3s");
+    assert_eq!(s.ratio.unwrap().2.polynomial, vec![-1, -1, 1]);
+    let exact: Vec<String> = s.values.iter().map(|(_, _, e)| e.as_ref().unwrap().to_string()).collect();
+    assert_eq!(exact, vec!["√2/2", "(√10-√2)/4", "√2/2"]);
+    assert_eq!(recognize((1f64 + 5f64.sqrt())/2f64), Some(Cyc::gen(5)));
+    assert_eq!(recognize(3f64 - 2f64*2f64.sqrt()), Some(&Cyc::int(3) - &(&Cyc::int(2)*&Cyc::gen(4))));
+    assert_eq!(recognize(1.839286755214161), None);
+  }
+}
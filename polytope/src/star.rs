@@ -0,0 +1,227 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use ndarray::{Array1, Array2};
+use ndarray_linalg::{Determinant, Solve};
+use petgraph::graph::NodeIndex;
+
+use crate::orbit::{normals, orbit, seed};
+use crate::product::Polytope;
+use crate::{determine_cartesian, subgraph, CoxEdge, CoxGraph, CoxGroup, Failed};
+
+/// Tolerance for points on a flag cone's boundary and faces through the centre
+const EPS: f64 = 1e-9;
+
+/// Whether the mark is p/q with q > 1, the mirrors then wind around more than once
+pub fn is_star(edge: &CoxEdge) -> bool {
+  match edge {
+    CoxEdge::Int(_) => false,
+    CoxEdge::Frac(f) => *f.denom().unwrap() > 1,
+  }
+}
+
+/// A face of the construction whose vertices land on top of each other,
+/// because the seed lies on a mirror of the group of the face that is not one of its unringed mirrors
+/// e.g. x3/2x: the hexagon goes around a triangle twice
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coincidence {
+  /// the nodes that generate the face
+  pub nodes: Vec<NodeIndex>,
+  /// number of vertices the face has abstractly, |W_K|/|W_(K∩J)| where J are the unringed nodes
+  pub expected: usize,
+  /// number of distinct vertices it has in space
+  pub found: usize,
+}
+
+impl fmt::Display for Coincidence {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let nodes: Vec<String> = self.nodes.iter().map(|i| i.index().to_string()).collect();
+    write!(f, "the face of nodes {} has {} vertices instead of {}", nodes.join(" "), self.found, self.expected)
+  }
+}
+
+/// Coincidences among the faces generated by sets of nodes with a star mark between them
+/// Diagrams with integer marks only never have any, since the seed then lies on exactly the unringed mirrors
+pub fn coincidences(graph: &CoxGraph<f64>) -> Result<Vec<Coincidence>, Failed> {
+  if !graph.edge_weights().any(is_star) {
+    return Ok(Vec::new());
+  }
+  let mut symm = graph.clone();
+  let n = symm.node_count();
+  determine_cartesian(&mut symm)?;
+  let normals = normals(&symm);
+  let seed = seed(&symm);
+  // a point on none of the mirrors, its orbit under a subgroup is as large as the subgroup
+  let generic = symm.node_weights().enumerate().fold(Array1::zeros(seed.len()), |acc, (i, node)| {
+    acc + node.cart_vec.as_ref().unwrap()*std::f64::consts::PI.powi(-(i as i32))
+  });
+  let order = |nodes: &[usize], p: &Array1<f64>| -> Result<usize, Failed> {
+    let ns: Vec<Array1<f64>> = nodes.iter().map(|i| normals[*i].clone()).collect();
+    match orbit(p.clone(), &ns) {
+      Some(set) => Ok(set.len()),
+      None => Err(Failed),
+    }
+  };
+  let mut found = Vec::new();
+  for mask in 1u32..(1 << n) {
+    let nodes: Vec<usize> = (0..n).filter(|i| mask & (1 << i) != 0).collect();
+    if !subgraph(&symm, &nodes).edge_weights().any(is_star) {
+      continue;
+    }
+    let unringed: Vec<usize> = nodes.iter().cloned().filter(|i| symm[NodeIndex::new(*i)].val == 0f64).collect();
+    let expected = order(&nodes, &generic)?/order(&unringed, &generic)?;
+    let actual = order(&nodes, &seed)?;
+    if actual < expected {
+      found.push(Coincidence {
+        nodes: nodes.iter().map(|i| NodeIndex::new(*i)).collect(),
+        expected,
+        found: actual,
+      });
+    }
+  }
+  Ok(found)
+}
+
+/// Centroid of the vertices of a face
+fn centroid(verts: &[Array1<f64>], face: &[usize]) -> Array1<f64> {
+  face.iter().fold(Array1::zeros(verts[0].len()), |acc, v| acc + &verts[*v])/(face.len() as f64)
+}
+
+impl Polytope {
+  /// Density: how many times the boundary winds around the centre, which is the origin
+  /// Every flag (vertex, edge, ..., facet) spans a cone from the origin through the centroids of its faces;
+  /// flags are coloured alternately, and the cones that contain a generic direction are counted with colour and handedness
+  /// None if the polytope does not fill its space, is not orientable, has a face through the centre,
+  /// or has a ridge in more than two facets
+  /// e.g. pentagram -> 2, great dodecahedron -> 3, convex polytopes -> 1
+  pub fn density(&self) -> Option<u32> {
+    let rank = self.rank();
+    if rank == 0 || self.verts[0].len() != rank {
+      return None;
+    }
+    let hasse = self.hasse();
+    // all flags, ranks 0 to rank-1
+    let mut flags: Vec<Vec<usize>> = (0..self.faces[0].len()).map(|v| vec![v]).collect();
+    for k in 1..rank {
+      flags = flags.iter().flat_map(|f| {
+        hasse.sup[k-1][f[k-1]].iter().map(move |g| {
+          let mut f = f.clone();
+          f.push(*g);
+          f
+        })
+      }).collect();
+    }
+    let index: HashMap<Vec<usize>, usize> = flags.iter().cloned().enumerate().map(|(i, f)| (f, i)).collect();
+    // the flag that differs from f only in rank k
+    let neighbour = |f: &Vec<usize>, k: usize| -> Option<usize> {
+      let options: Vec<usize> = if k == 0 {
+        hasse.sub[1][f[1]].clone()
+      } else if k == rank - 1 {
+        hasse.sup[k-1][f[k-1]].clone()
+      } else {
+        hasse.sup[k-1][f[k-1]].iter().cloned().filter(|g| hasse.sub[k+1][f[k+1]].contains(g)).collect()
+      };
+      if options.len() != 2 {
+        return None;
+      }
+      let mut g = f.clone();
+      g[k] = if options[0] == f[k] { options[1] } else { options[0] };
+      index.get(&g).cloned()
+    };
+    let mut color: Vec<Option<bool>> = vec![None; flags.len()];
+    for start in 0..flags.len() {
+      if color[start].is_some() {
+        continue;
+      }
+      color[start] = Some(true);
+      let mut queue = VecDeque::from(vec![start]);
+      while let Some(i) = queue.pop_front() {
+        for k in 0..rank {
+          let j = neighbour(&flags[i], k)?;
+          match color[j] {
+            None => {
+              color[j] = color[i].map(|c| !c);
+              queue.push_back(j);
+            },
+            Some(c) if Some(c) == color[i] => return None,
+            _ => {},
+          }
+        }
+      }
+    }
+    let cones: Vec<(Array2<f64>, f64)> = flags.iter().zip(color.iter()).map(|(f, c)| {
+      let mut m = Array2::<f64>::zeros((rank, rank));
+      for (k, face) in f.iter().enumerate() {
+        m.column_mut(k).assign(&centroid(&self.verts, &self.faces[k][*face]));
+      }
+      let sign = if *c == Some(true) { 1f64 } else { -1f64 };
+      let det = m.det().unwrap_or(0f64);
+      (m, sign*det.signum()*if det.abs() < EPS { 0f64 } else { 1f64 })
+    }).collect();
+    if cones.iter().any(|(_, s)| *s == 0f64) {
+      return None;
+    }
+    // a direction on a cone boundary is tried again with the next one
+    'direction: for t in 1..20 {
+      let d = Array1::from((0..rank).map(|i| ((t*(i + 1)) as f64*std::f64::consts::SQRT_2).fract() - 0.5).collect::<Vec<_>>());
+      let mut total = 0f64;
+      for (m, s) in &cones {
+        let l = m.solve(&d).ok()?;
+        if l.iter().any(|x| x.abs() < EPS) {
+          continue 'direction;
+        }
+        if l.iter().all(|x| *x > 0f64) {
+          total += s;
+        }
+      }
+      return Some(total.abs().round() as u32);
+    }
+    None
+  }
+}
+
+impl CoxGroup<f64> {
+  /// Coincidences of the Wythoffian polytope of the diagram, see `coincidences`
+  pub fn coincidences(&self) -> Result<Vec<Coincidence>, Failed> {
+    coincidences(&self.graph)
+  }
+
+  /// Density of the polytope of the diagram, None where `Polytope::density` is undefined
+  /// e.g. x5/2o -> 2, o5/2o5x -> 3, x5/2o5o -> 3
+  pub fn density(&self) -> Result<Option<u32>, Failed> {
+    Ok(self.polytope()?.density())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  #[test]
+  fn densities() {
+    for (s, d) in [("x5o", Some(1)), ("x5/2o", Some(2)), ("x7/3o", Some(3)), ("x4o3o", Some(1)), ("x5/2o x", Some(2)),
+                   ("x5/2o5o", Some(3)), ("x5/2o3o", Some(7)), ("x3o5/2o", Some(7)), ("o5/2x5o", Some(3)),
+                   ("x3o3o5o", Some(1)), ("x5/2o5o3o", Some(4))] {
+      assert_eq!(group(s).density(), Ok(d), "{}", s);
+    }
+  }
+
+  #[test]
+  fn star_counts() {
+    for (s, counts) in [("x5/2o5o", vec![12, 30, 12, 1]), ("x3/2o3o", vec![4, 6, 4, 1]), ("x5/2o3o3o", vec![600, 1200, 720, 120, 1])] {
+      let p = group(s).polytope().unwrap();
+      assert_eq!(p.faces.iter().map(|f| f.len()).collect::<Vec<_>>(), counts, "{}", s);
+    }
+  }
+
+  #[test]
+  fn degenerate() {
+    let c = group("x3/2x").coincidences().unwrap();
+    assert_eq!(c, vec![Coincidence { nodes: vec![NodeIndex::new(0), NodeIndex::new(1)], expected: 6, found: 3 }]);
+    assert_eq!(c[0].to_string(), "the face of nodes 0 1 has 3 vertices instead of 6");
+    assert!(group("x3/2x").polytope().is_err());
+    assert!(group("x5/2o3o").coincidences().unwrap().is_empty());
+    assert!(!group("x3/2x3o").coincidences().unwrap().is_empty());
+  }
+}
@@ -0,0 +1,506 @@
+use std::collections::{HashSet, VecDeque};
+use std::f64::consts::PI;
+use std::fmt;
+
+use ndarray::Array1;
+use rusqlite::Connection;
+
+use crate::lattice::{wythoffian, Hasse};
+use crate::orbit::{normals, reflect, PointSet};
+use crate::polydb;
+use crate::product::Polytope;
+use crate::schwarz::{schwarz_triangles, Kaleidoscope};
+use crate::star::{coincidences, Coincidence};
+use crate::{determine_cartesian, CoxGraph, CoxGroup, Failed};
+
+/// Tolerance for edge lengths and circumradii
+const EPS: f64 = 1e-6;
+
+/// A uniform polyhedron with unit edges, as found by `uniform_polyhedra`
+#[derive(Debug, Clone)]
+pub struct Uniform {
+  /// as written in `NAMES` if it is there, else that of the construction, e.g. 5/2 | 2 3
+  pub wythoff: String,
+  /// the diagram it was built from, e.g. o3o5/2x or s3s5s, None for the great dirhombicosidodecahedron
+  pub dynkin: Option<String>,
+  /// None for the polyhedra with faces through the centre
+  pub density: Option<u32>,
+  /// number of vertices, edges and faces
+  pub counts: [usize; 3],
+  /// the faces as (n, d) for {n/d} with d <= n/2, and how many there are, e.g. [((3, 1), 20), ((5, 2), 12)]
+  pub faces: Vec<((usize, usize), usize)>,
+  pub circumradius: f64,
+  /// the long name as in the `Polyhedron` table of polydb.sqlite3, if known
+  pub name: Option<String>,
+  pub polytope: Polytope,
+}
+
+impl Uniform {
+  /// The faces as text, e.g. 20{3}+12{5/2}
+  pub fn face_string(&self) -> String {
+    face_string(&self.faces)
+  }
+}
+
+impl fmt::Display for Uniform {
+  /// e.g. 5/2 | 2 3: 12 vertices, 30 edges, 20{3}, great icosahedron
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: {} vertices, {} edges, {}", self.wythoff, self.counts[0], self.counts[1], self.face_string())?;
+    match &self.name {
+      Some(name) => write!(f, ", {}", name),
+      None => Ok(()),
+    }
+  }
+}
+
+/// {n} or {n/d}
+fn polygon_symbol(n: usize, d: usize) -> String {
+  if d == 1 { format!("{{{}}}", n) } else { format!("{{{}/{}}}", n, d) }
+}
+
+fn face_string(faces: &[((usize, usize), usize)]) -> String {
+  faces.iter().map(|((n, d), c)| format!("{}{}", c, polygon_symbol(*n, *d))).collect::<Vec<_>>().join("+")
+}
+
+/// The 75 uniform polyhedra in the order of their U numbers, with their Wythoff symbols as usually written,
+/// their faces and their densities as listed by Coxeter, Longuet-Higgins and Miller
+/// A polyhedron is looked up by its symbol, in any order on either side of the bar, or else by its faces if no other has the same,
+/// e.g. the icosahedron and the great icosahedron both have 20{3}, and the three snubs with 80{3}+12{5/2} differ only in their symbols
+const NAMES: [(&str, &str, Option<u32>, &str); 75] = [
+  ("3 | 2 3", "4{3}", Some(1), "tetrahedron"),
+  ("2 3 | 3", "4{3}+4{6}", Some(1), "truncated tetrahedron"),
+  ("3/2 3 | 3", "8{3}+4{6}", None, "octahemioctahedron"),
+  ("3/2 3 | 2", "4{3}+3{4}", None, "tetrahemihexahedron"),
+  ("4 | 2 3", "8{3}", Some(1), "octahedron"),
+  ("3 | 2 4", "6{4}", Some(1), "cube"),
+  ("2 | 3 4", "8{3}+6{4}", Some(1), "cuboctahedron"),
+  ("2 4 | 3", "6{4}+8{6}", Some(1), "truncated octahedron"),
+  ("2 3 | 4", "8{3}+6{8}", Some(1), "truncated cube"),
+  ("3 4 | 2", "8{3}+18{4}", Some(1), "rhombicuboctahedron"),
+  ("2 3 4 |", "12{4}+8{6}+6{8}", Some(1), "great rhombicuboctahedron"),
+  ("| 2 3 4", "32{3}+6{4}", Some(1), "snub cube"),
+  ("3/2 4 | 4", "8{3}+6{4}+6{8}", Some(2), "small cubicuboctahedron"),
+  ("3 4 | 4/3", "8{3}+6{4}+6{8/3}", Some(4), "great cubicuboctahedron"),
+  ("4/3 4 | 3", "6{4}+4{6}", None, "cubohemioctahedron"),
+  ("4/3 3 4 |", "8{6}+6{8}+6{8/3}", Some(4), "cubitruncated cuboctahedron"),
+  ("3/2 4 | 2", "8{3}+18{4}", Some(5), "uniform great rhombicuboctahedron"),
+  ("2 4 (3/2 4/2) |", "12{4}+6{8}", None, "small rhombihexahedron"),
+  ("2 3 | 4/3", "8{3}+6{8/3}", Some(7), "stellated truncated hexahedron"),
+  ("4/3 2 3 |", "12{4}+8{6}+6{8/3}", Some(1), "great truncated cuboctahedron"),
+  ("4/3 2 (3/2 4/2) |", "12{4}+6{8/3}", None, "great rhombihexahedron"),
+  ("5 | 2 3", "20{3}", Some(1), "icosahedron"),
+  ("3 | 2 5", "12{5}", Some(1), "dodecahedron"),
+  ("2 | 3 5", "20{3}+12{5}", Some(1), "icosidodecahedron"),
+  ("2 5 | 3", "12{5}+20{6}", Some(1), "truncated icosahedron"),
+  ("2 3 | 5", "20{3}+12{10}", Some(1), "truncated dodecahedron"),
+  ("3 5 | 2", "20{3}+30{4}+12{5}", Some(1), "rhombicosidodecahedron"),
+  ("2 3 5 |", "30{4}+20{6}+12{10}", Some(1), "great rhombicosidodecahedron"),
+  ("| 2 3 5", "80{3}+12{5}", Some(1), "snub dodecahedron"),
+  ("3 | 5/2 3", "20{3}+12{5/2}", Some(2), "small ditrigonal icosidodecahedron"),
+  ("5/2 3 | 3", "20{3}+12{5/2}+20{6}", Some(2), "small icosicosidodecahedron"),
+  ("| 5/2 3 3", "100{3}+12{5/2}", Some(2), "small snub icosicosidodecahedron"),
+  ("3/2 5 | 5", "20{3}+12{5}+12{10}", Some(2), "small dodecicosidodecahedron"),
+  ("5 | 2 5/2", "12{5/2}", Some(3), "small stellated dodecahedron"),
+  ("5/2 | 2 5", "12{5}", Some(3), "great dodecahedron"),
+  ("2 | 5/2 5", "12{5}+12{5/2}", Some(3), "dodecadodecahedron"),
+  ("2 5/2 | 5", "12{5/2}+12{10}", Some(3), "truncated great dodecahedron"),
+  ("5/2 5 | 2", "30{4}+12{5}+12{5/2}", Some(3), "rhombidodecadodecahedron"),
+  ("2 5/2 5 |", "30{4}+12{10}", None, "small rhombidodecahedron"),
+  ("| 2 5/2 5", "60{3}+12{5}+12{5/2}", Some(3), "snub dodecadodecahedron"),
+  ("3 | 5/3 5", "12{5}+12{5/2}", Some(4), "ditrigonal dodecadodecahedron"),
+  ("3 5 | 5/3", "20{3}+12{5}+12{10/3}", Some(4), "great ditrigonal dodecicosidodecahedron"),
+  ("5/3 3 | 5", "20{3}+12{5/2}+12{10}", Some(4), "small ditrigonal dodecicosidodecahedron"),
+  ("5/3 5 | 3", "12{5}+12{5/2}+20{6}", Some(4), "icosidodecadodecahedron"),
+  ("5/3 3 5 |", "20{6}+12{10}+12{10/3}", Some(4), "icositruncated dodecadodecahedron"),
+  ("| 5/3 3 5", "80{3}+12{5}+12{5/2}", Some(4), "snub icosidodecadodecahedron"),
+  ("3/2 | 3 5", "20{3}+12{5}", Some(6), "great ditrigonal icosidodecahedron"),
+  ("3/2 5 | 3", "20{3}+12{5}+20{6}", Some(6), "great icosicosidodecahedron"),
+  ("3/2 3 | 5", "20{3}+6{10}", None, "small icosihemidodecahedron"),
+  ("3/2 3 5 |", "20{6}+12{10}", None, "small dodecicosahedron"),
+  ("5/4 5 | 5", "12{5}+6{10}", None, "small dodecahemidodecahedron"),
+  ("3 | 2 5/2", "12{5/2}", Some(7), "great stellated dodecahedron"),
+  ("5/2 | 2 3", "20{3}", Some(7), "great icosahedron"),
+  ("2 | 5/2 3", "20{3}+12{5/2}", Some(7), "great icosidodecahedron"),
+  ("2 5/2 | 3", "12{5/2}+20{6}", Some(7), "great truncated icosahedron"),
+  ("2 5/2 3 |", "30{4}+20{6}", None, "rhombicosahedron"),
+  ("| 2 5/2 3", "80{3}+12{5/2}", Some(7), "great snub icosidodecahedron"),
+  ("2 5 | 5/3", "12{5}+12{10/3}", Some(9), "small stellated truncated dodecahedron"),
+  ("5/3 2 5 |", "30{4}+12{10}+12{10/3}", Some(3), "truncated dodecadodecahedron"),
+  ("| 5/3 2 5", "60{3}+12{5}+12{5/2}", Some(9), "inverted snub dodecadodecahedron"),
+  ("5/2 3 | 5/3", "20{3}+12{5/2}+12{10/3}", Some(10), "great dodecicosidodecahedron"),
+  ("5/3 5/2 | 3", "12{5/2}+10{6}", None, "small dodecahemicosahedron"),
+  ("5/3 5/2 3 |", "20{6}+12{10/3}", None, "great dodecicosahedron"),
+  ("| 5/3 5/2 3", "80{3}+24{5/2}", Some(10), "great snub dodecicosidodecahedron"),
+  ("5/4 5 | 3", "12{5}+10{6}", None, "great dodecahemicosahedron"),
+  ("2 3 | 5/3", "20{3}+12{10/3}", Some(13), "great stellated truncated dodecahedron"),
+  ("5/3 3 | 2", "20{3}+30{4}+12{5/2}", Some(13), "uniform great rhombicosidodecahedron"),
+  ("5/3 2 3 |", "30{4}+20{6}+12{10/3}", Some(13), "great truncated icosidodecahedron"),
+  ("| 5/3 2 3", "80{3}+12{5/2}", Some(13), "great inverted snub icosidodecahedron"),
+  ("5/3 5/2 | 5/3", "12{5/2}+6{10/3}", None, "great dodecahemidodecahedron"),
+  ("3/2 3 | 5/3", "20{3}+6{10/3}", None, "great icosihemidodecahedron"),
+  ("| 3/2 3/2 5/2", "100{3}+12{5/2}", Some(38), "small retrosnub icosicosidodecahedron"),
+  ("2 5/3 (3/2 5/4) |", "30{4}+12{10/3}", None, "great rhombidodecahedron"),
+  ("| 3/2 5/3 2", "80{3}+12{5/2}", Some(37), "great retrosnub icosidodecahedron"),
+  ("| 3/2 5/3 3 5/2", "40{3}+60{4}+24{5/2}", None, "great dirhombicosidodecahedron"),
+];
+
+/// Adjective of the n/d-gon in the names of prisms and antiprisms, e.g. pentagonal, pentagrammic
+fn polygon_adjective(n: usize, d: usize) -> String {
+  let gonal = match n {
+    3 => "triangular",
+    4 => "square",
+    5 => "pentagonal",
+    6 => "hexagonal",
+    7 => "heptagonal",
+    8 => "octagonal",
+    9 => "enneagonal",
+    10 => "decagonal",
+    12 => "dodecagonal",
+    20 => "icosagonal",
+    _ => return polygon_symbol(n, d),
+  };
+  if d == 1 { gonal.to_string() } else { gonal.replace("gonal", "grammic") }
+}
+
+/// The numbers on either side of the bar of a Wythoff symbol, each side sorted,
+/// e.g. 5/2 | 2 3 and 5/2 | 3 2 -> (["5/2"], ["2", "3"])
+fn sides(wythoff: &str) -> (Vec<&str>, Vec<&str>) {
+  fn sorted(s: &str) -> Vec<&str> {
+    let mut v: Vec<&str> = s.split_whitespace().collect();
+    v.sort();
+    v
+  }
+  let (left, right) = wythoff.split_once('|').unwrap_or((wythoff, ""));
+  (sorted(left), sorted(right))
+}
+
+/// The entry of `NAMES` with these faces and the same symbol, or the only one with these faces
+fn lookup(wythoff: &str, faces: &str) -> Option<&'static (&'static str, &'static str, Option<u32>, &'static str)> {
+  let same: Vec<_> = NAMES.iter().filter(|(_, f, _, _)| *f == faces).collect();
+  match same.iter().find(|(w, _, _, _)| sides(w) == sides(wythoff)) {
+    Some(entry) => Some(entry),
+    None if same.len() == 1 => Some(same[0]),
+    None => None,
+  }
+}
+
+/// The name of a prism or antiprism
+/// The antiprisms of n/d with d > n/2 are the crossed ones, their density is n-d instead of d
+fn prismatic(verts: usize, faces: &[((usize, usize), usize)], density: Option<u32>) -> Option<String> {
+  match faces {
+    [((4, 1), _), ((n, d), 2)] | [((n, d), 2), ((4, 1), _)] if verts == 2*n => Some(format!("{} prism", polygon_adjective(*n, *d))),
+    [((3, 1), _), ((n, d), 2)] | [((n, d), 2), ((3, 1), _)] if verts == 2*n => {
+      let crossed = if density == Some(*d as u32) { "" } else { "crossed " };
+      Some(format!("{} {}antiprism", polygon_adjective(*n, *d), crossed))
+    },
+    _ => None,
+  }
+}
+
+/// Type {n/d} of a 2-face of a polyhedron, from the angles its edges subtend at its centre
+pub fn polygram(p: &Polytope, edges: &[Vec<usize>], face: &[usize]) -> (usize, usize) {
+  let n = face.len();
+  let centre = face.iter().fold(Array1::<f64>::zeros(p.verts[0].len()), |acc, v| acc + &p.verts[*v])/(n as f64);
+  let cross = |a: &Array1<f64>, b: &Array1<f64>| Array1::from(vec![a[1]*b[2] - a[2]*b[1], a[2]*b[0] - a[0]*b[2], a[0]*b[1] - a[1]*b[0]]);
+  let mut total = 0f64;
+  for e in edges {
+    let (a, b) = (&p.verts[e[0]] - &centre, &p.verts[e[1]] - &centre);
+    let c = cross(&a, &b);
+    total += c.dot(&c).sqrt().atan2(a.dot(&b));
+  }
+  (n, (total/(2f64*PI)).round() as usize)
+}
+
+/// Whether the faces form a proper polyhedron: all vertices distinct, every edge in exactly two faces,
+/// and the faces around every vertex in a single cycle
+/// Wythoff's construction on some triangles gives edges in four faces, e.g. 5/4 | 5/4 3/2 has the faces of both the icosahedron and the great dodecahedron
+fn is_polyhedron(p: &Polytope, hasse: &Hasse) -> bool {
+  let mut set = PointSet::default();
+  if p.verts.iter().any(|v| !set.insert(v.clone()).1) || hasse.sup[1].iter().any(|fs| fs.len() != 2) {
+    return false;
+  }
+  (0..p.verts.len()).all(|v| {
+    // walk around the vertex from edge to edge through the faces
+    let edges = &hasse.sup[0][v];
+    let mut seen = vec![edges[0]];
+    let mut queue = VecDeque::from(vec![edges[0]]);
+    while let Some(e) = queue.pop_front() {
+      for f in &hasse.sup[1][e] {
+        for g in hasse.sub[2][*f].iter().filter(|g| edges.contains(g)) {
+          if !seen.contains(g) {
+            seen.push(*g);
+            queue.push_back(*g);
+          }
+        }
+      }
+    }
+    seen.len() == edges.len()
+  })
+}
+
+/// How the mirrors of the diagram permute the vertices, None if the vertices are not symmetric under them
+fn vertex_perms(graph: &CoxGraph<f64>, verts: &[Array1<f64>]) -> Option<Vec<Vec<usize>>> {
+  let mut symm = graph.clone();
+  determine_cartesian(&mut symm).ok()?;
+  let mut set = PointSet::default();
+  for v in verts {
+    set.insert(v.clone());
+  }
+  normals(&symm).iter().map(|n| verts.iter().map(|v| set.find(&reflect(v, n))).collect()).collect()
+}
+
+/// All images of a face, as sorted vertex lists
+fn face_orbit(face: &[usize], perms: &[Vec<usize>]) -> HashSet<Vec<usize>> {
+  let mut orbit: HashSet<Vec<usize>> = HashSet::from([face.to_vec()]);
+  let mut queue = VecDeque::from(vec![face.to_vec()]);
+  while let Some(f) = queue.pop_front() {
+    for perm in perms {
+      let mut g: Vec<usize> = f.iter().map(|v| perm[*v]).collect();
+      g.sort();
+      if orbit.insert(g.clone()) {
+        queue.push_back(g);
+      }
+    }
+  }
+  orbit
+}
+
+/// The Wythoffian polyhedron of a diagram without its 2-faces that collapse onto a polygon covered twice,
+/// e.g. x4x3/2x, whose hexagons fall onto triangles, gives the small rhombihexahedron with only its squares and octagons
+/// Unlike `CoxGroup::polytope` this keeps coinciding vertices, which is how the hemipolyhedra come out
+fn wythoff_polyhedron(group: &CoxGroup<f64>) -> Option<Polytope> {
+  let collapsed: Vec<Coincidence> = coincidences(&group.graph).ok()?.into_iter().filter(|c| c.nodes.len() == 2).collect();
+  let mut p = wythoffian(&group.graph).ok()?;
+  if collapsed.is_empty() {
+    return Some(p);
+  }
+  let perms = vertex_perms(&group.graph, &p.verts)?;
+  let mut dropped: HashSet<Vec<usize>> = HashSet::new();
+  for c in collapsed {
+    // the face through vertex 0 and its images in the two mirrors
+    let corner: Vec<usize> = c.nodes.iter().map(|i| perms[i.index()][0]).collect();
+    let face = p.faces[2].iter().find(|f| f.contains(&0) && corner.iter().all(|v| f.contains(v)))?;
+    dropped.extend(face_orbit(face, &perms));
+  }
+  p.faces[2].retain(|f| !dropped.contains(f));
+  Some(p)
+}
+
+/// The snub polyhedron of a diagram, None if there is none
+/// Double roots, where pairs of vertices meet like for | 4/3 4 3, cannot be enclosed by interval Newton and have no error bound
+fn snub_polyhedron(group: &CoxGroup<f64>) -> Option<Polytope> {
+  let snub = group.snub().ok()?;
+  if snub.values.iter().any(|(_, c, _)| c.error > 1e-9) {
+    return None;
+  }
+  Some(snub.polytope)
+}
+
+/// The diagram of a triangle with the nodes in `mask` ringed, or with all nodes snub
+/// Snub nodes start off with distinct values, so the iteration does not start on a mirror of the star groups
+fn ring(k: &Kaleidoscope, mask: u32, snub: bool) -> CoxGroup<f64> {
+  let mut group = k.group();
+  for (i, node) in group.graph.node_weights_mut().enumerate() {
+    if snub {
+      node.repr = "s".to_string();
+      node.val = 1f64 + PI.powi(-(i as i32 + 1));
+    } else if mask & (1 << i) != 0 {
+      node.repr = "x".to_string();
+      node.val = 1f64;
+    }
+  }
+  group
+}
+
+/// Regular {n/d} polygons with unit edges among the vertices, each as a sorted vertex list
+/// They are the cycles in which the vertices k steps apart are at the distance sin(𝛑kd/n)/sin(𝛑d/n) of a regular polygon
+fn regular_polygons(verts: &[Array1<f64>], n: usize, d: usize) -> Vec<Vec<usize>> {
+  let chord = |k: usize| (PI*(k*d) as f64/n as f64).sin().abs()/(PI*d as f64/n as f64).sin();
+  let dist = |i: usize, j: usize| (&verts[i] - &verts[j]).dot(&(&verts[i] - &verts[j])).sqrt();
+  let mut found: HashSet<Vec<usize>> = HashSet::new();
+  let mut polygons = Vec::new();
+  // paths starting at their smallest vertex
+  for start in 0..verts.len() {
+    let mut paths: Vec<Vec<usize>> = vec![vec![start]];
+    for _ in 1..n {
+      paths = paths.iter().flat_map(|path| {
+        (start+1..verts.len())
+          .filter(|v| !path.contains(v) && path.iter().rev().enumerate().all(|(k, w)| (dist(*v, *w) - chord(k + 1)).abs() < EPS))
+          .map(|v| {
+            let mut p = path.clone();
+            p.push(v);
+            p
+          }).collect::<Vec<_>>()
+      }).collect();
+    }
+    for mut p in paths {
+      p.sort();
+      if found.insert(p.clone()) {
+        polygons.push(p);
+      }
+    }
+  }
+  polygons
+}
+
+/// The great dirhombicosidodecahedron | 3/2 5/3 3 5/2, the one uniform polyhedron that Wythoff's construction on a triangle misses
+/// Its vertices are those of the snub | 5/3 5/2 3, its edges all pairs of them at distance 1,
+/// and its faces the regular {3}, {4} and {5/2} on those edges with the most symmetry:
+/// of every kind the ones in the smallest orbits, so the triangles around the 3-fold axes and not the snub triangles
+fn great_dirhombicosidodecahedron(triangle: &Kaleidoscope) -> Option<Polytope> {
+  let group = ring(triangle, 0, true);
+  let verts = snub_polyhedron(&group)?.verts;
+  let perms = vertex_perms(&group.graph, &verts)?;
+  let n = verts.len();
+  let edges: Vec<Vec<usize>> = (0..n).flat_map(|i| (i+1..n).map(move |j| vec![i, j]))
+    .filter(|e| ((&verts[e[0]] - &verts[e[1]]).dot(&(&verts[e[0]] - &verts[e[1]])) - 1f64).abs() < EPS)
+    .collect();
+  let mut faces = Vec::new();
+  for (k, d) in [(3, 1), (4, 1), (5, 2)] {
+    let mut orbits: Vec<HashSet<Vec<usize>>> = Vec::new();
+    for f in regular_polygons(&verts, k, d) {
+      if !orbits.iter().any(|o| o.contains(&f)) {
+        orbits.push(face_orbit(&f, &perms));
+      }
+    }
+    let smallest = orbits.iter().map(|o| o.len()).min()?;
+    for o in orbits.into_iter().filter(|o| o.len() == smallest) {
+      let mut o: Vec<Vec<usize>> = o.into_iter().collect();
+      o.sort();
+      faces.extend(o);
+    }
+  }
+  Some(Polytope {
+    verts,
+    faces: vec![(0..n).map(|i| vec![i]).collect(), edges, faces, vec![(0..n).collect()]],
+  })
+}
+
+/// Counts the faces of a polyhedron by type and looks up its name and symbol, None if it is not a proper polyhedron
+fn uniform(wythoff: String, dynkin: Option<String>, p: Polytope) -> Option<Uniform> {
+  if p.rank() != 3 {
+    return None;
+  }
+  let hasse = p.hasse();
+  if !is_polyhedron(&p, &hasse) {
+    return None;
+  }
+  let mut faces: Vec<((usize, usize), usize)> = Vec::new();
+  for (f, subs) in hasse.faces[2].iter().zip(&hasse.sub[2]) {
+    let edges: Vec<Vec<usize>> = subs.iter().map(|e| hasse.faces[1][*e].clone()).collect();
+    let t = polygram(&p, &edges, f);
+    match faces.iter_mut().find(|(s, _)| *s == t) {
+      Some((_, c)) => *c += 1,
+      None => faces.push((t, 1)),
+    }
+  }
+  faces.sort();
+  let counts = [p.faces[0].len(), p.faces[1].len(), p.faces[2].len()];
+  let density = p.density();
+  let (wythoff, name) = match lookup(&wythoff, &face_string(&faces)) {
+    Some((w, _, _, name)) => (w.to_string(), Some(name.to_string())),
+    None => (wythoff, prismatic(counts[0], &faces, density)),
+  };
+  Some(Uniform {
+    wythoff,
+    dynkin,
+    density,
+    counts,
+    name,
+    faces,
+    circumradius: p.verts[0].dot(&p.verts[0]).sqrt(),
+    polytope: p,
+  })
+}
+
+/// All uniform polyhedra from Wythoff's construction on the Schwarz triangles with numerators up to `max`,
+/// including the prisms and antiprisms of {n/d} with n <= `max`; `max` = 5 gives all 75 and the prisms up to the decagrams
+/// Every triangle gets its 7 ringings and the snub, where
+/// - faces that collapse onto a polygon covered twice are left out (the small rhombihexahedron and its six relatives)
+/// - vertices that coincide are taken once (the hemipolyhedra)
+/// - results with edges in more than two faces, and snubs with coinciding vertices, are dropped
+///
+/// and the great dirhombicosidodecahedron is added, see `great_dirhombicosidodecahedron`
+/// A polyhedron found more than once (same faces and circumradius) keeps its first construction,
+/// on the triangle of the lowest density, and not a snub if possible, unless only a later one has a name,
+/// as the great icosahedron, which first comes out as 5 | 3/2 3, can only be told from the icosahedron by its symbol
+/// Snubs come in one of their two mirror images
+pub fn uniform_polyhedra(max: u32) -> Vec<Uniform> {
+  let mut triangles = schwarz_triangles(max);
+  triangles.sort_by_key(|k| k.density);
+  let mut candidates: Vec<Uniform> = Vec::new();
+  for snub in [false, true] {
+    for k in &triangles {
+      for mask in if snub { 0..1 } else { 1..8 } {
+        let group = ring(k, mask, snub);
+        let p = if snub { snub_polyhedron(&group) } else { wythoff_polyhedron(&group) };
+        if let Some(u) = p.and_then(|p| uniform(group.wythoff().unwrap_or_default(), Some(group.dynkin()), p)) {
+          candidates.push(u);
+        }
+      }
+    }
+  }
+  let special = triangles.iter().find(|k| k.to_string().starts_with("(5/3 5/2 3)"));
+  if let Some(p) = special.and_then(great_dirhombicosidodecahedron) {
+    candidates.extend(uniform("| 3/2 5/3 3 5/2".to_string(), None, p));
+  }
+  let mut found: Vec<Uniform> = Vec::new();
+  for u in candidates {
+    match found.iter_mut().find(|f| f.faces == u.faces && f.counts == u.counts && (f.circumradius - u.circumradius).abs() < EPS) {
+      Some(f) if f.name.is_none() && u.name.is_some() => *f = u,
+      Some(_) => {},
+      None => found.push(u),
+    }
+  }
+  found
+}
+
+/// Adds the named polyhedra to the `Polyhedron` table of polydb.sqlite3, with their diagram as short name, and gives their ids
+/// The ones already there by their long name keep their row, e.g. the cube, which is C100
+pub fn save(conn: &Connection, all: &[Uniform]) -> Result<Vec<i64>, Failed> {
+  all.iter().filter_map(|u| u.name.as_ref().map(|name| (u, name)))
+    .map(|(u, name)| polydb::polyhedron(conn, u.dynkin.as_deref().unwrap_or(&u.wythoff), name))
+    .collect()
+}
+
+impl CoxGroup<f64> {
+  /// The polyhedron of a diagram of rank 3 as a uniform polyhedron with its name, None if its edges are not all as long
+  /// or it is not a proper polyhedron, e.g. x3o5o -> icosahedron, x5/2o5o -> small stellated dodecahedron
+  pub fn uniform(&self) -> Option<Uniform> {
+    if self.graph.node_count() != 3 || self.is_uniform() != Ok(true) {
+      return None;
+    }
+    uniform(self.wythoff().unwrap_or_default(), Some(self.dynkin()), wythoff_polyhedron(self)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn all_uniform() {
+    let all = uniform_polyhedra(5);
+    let named: Vec<&Uniform> = all.iter().filter(|u| u.name.is_some()).collect();
+    assert_eq!(named.len(), all.len());
+    let prismatic = all.iter().filter(|u| u.name.as_ref().unwrap().ends_with("prism")).count();
+    assert_eq!((all.len() - prismatic, prismatic), (75, 12));
+    let mut names: Vec<&String> = all.iter().map(|u| u.name.as_ref().unwrap()).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), all.len());
+    let find = |name: &str| all.iter().find(|u| u.name.as_deref() == Some(name)).unwrap();
+    assert_eq!(find("great icosahedron").to_string(), "5/2 | 2 3: 12 vertices, 30 edges, 20{3}, great icosahedron");
+    assert_eq!(find("great stellated dodecahedron").wythoff, "3 | 2 5/2");
+    for (w, _, density, name) in NAMES {
+      let u = find(name);
+      assert_eq!((u.wythoff.as_str(), u.density), (w, density), "{}", name);
+    }
+    let gidrid = find("great dirhombicosidodecahedron");
+    assert_eq!((gidrid.counts, gidrid.face_string()), ([60, 240, 124], "40{3}+60{4}+24{5/2}".to_string()));
+    assert_eq!(find("pentagrammic crossed antiprism").density, Some(3));
+    let conn = polydb::memory().unwrap();
+    let ids = save(&conn, &all).unwrap();
+    assert_eq!((ids.len(), save(&conn, &all).unwrap()), (87, ids));
+  }
+}
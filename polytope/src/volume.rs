@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use exact::Expr;
+use ndarray::Array1;
+use petgraph::graph::NodeIndex;
+
+use crate::figure::affine_basis;
+use crate::interval::Interval;
+use crate::product::Polytope;
+use crate::radius::{exact_values, radical, squared_radius};
+use crate::roots::{Cyc, CycFrac};
+use crate::snub::Certified;
+use crate::{is_face, subgraph, CoxGraph, CoxGroup, Failed};
+
+/// Slack when grouping facets by content, on top of their error bounds
+const EPS: f64 = 1e-9;
+
+/// Contents of a polytope, see `Polytope::content`
+#[derive(Debug, Clone)]
+pub struct Content {
+  /// the n-volume, for a polytope of rank n
+  pub volume: Certified,
+  /// the (n-1)-content of the boundary, the sum of the contents of all facets
+  pub boundary: Certified,
+  /// number of vertices of a kind of facet, its content, and how many facets there are of this kind
+  pub facets: Vec<(usize, Certified, usize)>,
+  /// the volume and boundary content as exact numbers, for diagrams with recognized node values, see `CoxGroup::content`
+  pub exact: Option<(Expr, Expr)>,
+}
+
+/// The interval of the matrix determinant, by Gaussian elimination, None if a pivot may be 0
+fn determinant(mut m: Vec<Vec<Interval>>) -> Option<Interval> {
+  let n = m.len();
+  let mut det = Interval::point(1f64);
+  for k in 0..n {
+    det = det*m[k][k];
+    let inv = m[k][k].inverse()?;
+    let pivot = m[k].clone();
+    for row in m.iter_mut().skip(k+1) {
+      let factor = row[k]*inv;
+      for (x, p) in row.iter_mut().zip(&pivot).skip(k) {
+        *x = *x - factor**p;
+      }
+    }
+  }
+  Some(det)
+}
+
+/// Gram determinant of the vectors
+fn gram_determinant(vectors: &[Vec<Interval>]) -> Option<Interval> {
+  let dot = |a: &[Interval], b: &[Interval]| a.iter().zip(b).fold(Interval::point(0f64), |acc, (x, y)| acc + *x**y);
+  determinant(vectors.iter().map(|a| vectors.iter().map(|b| dot(a, b)).collect()).collect())
+}
+
+/// Distance from p to the affine span of the points, as √(Γ(d_1, ..., d_k, p - q)/Γ(d_1, ..., d_k)) with Γ the Gram determinant
+/// and d_i the directions from the first point q to the points that add a dimension, see `affine_basis`
+/// None if the Gram determinants cannot be told from 0
+fn distance(p: &Array1<f64>, points: &[&Array1<f64>]) -> Option<Interval> {
+  let (_, spanning) = affine_basis(points);
+  let direction = |q: &Array1<f64>| -> Vec<Interval> {
+    q.iter().zip(points[0].iter()).map(|(x, y)| Interval::point(*x) - Interval::point(*y)).collect()
+  };
+  let mut vectors: Vec<Vec<Interval>> = spanning.iter().map(|i| direction(points[*i])).collect();
+  let base = gram_determinant(&vectors)?;
+  vectors.push(direction(p));
+  let with = gram_determinant(&vectors)?;
+  // a point in the span has Γ = 0, which the interval may cross
+  (with.intersect(&Interval::new(0f64, f64::INFINITY))*base.inverse()?).sqrt()
+}
+
+impl Polytope {
+  /// Contents of all faces: contents[k][i] is the k-dimensional content of the k-face i, 1 for vertices and the length for edges
+  /// A k-face is cut into pyramids with apex at the centroid of its vertices, one on every facet of the face,
+  /// so the content is the sum of height·content/k over its facets
+  /// For star faces the pyramids overlap and parts get counted as often as they are covered, e.g. the middle of the pentagram twice;
+  /// facets through the centre, like the hexagons of the octahemioctahedron, add nothing to the volume
+  /// Everything is done in interval arithmetic, so the error bounds hold for the polytope with exactly these coordinates;
+  /// the error is infinite where a height cannot be bounded, as for faces that are not flat
+  pub fn contents(&self) -> Vec<Vec<Certified>> {
+    self.enclosures().iter().map(|level| level.iter().map(certified).collect()).collect()
+  }
+
+  /// The contents of all faces as intervals, see `contents`
+  fn enclosures(&self) -> Vec<Vec<Interval>> {
+    let hasse = self.hasse();
+    let mut contents = vec![vec![Interval::point(1f64); self.verts.len()]];
+    for k in 1..=self.rank() {
+      let level = hasse.faces[k].iter().zip(&hasse.sub[k]).map(|(face, subs)| {
+        let centre = face.iter().fold(Array1::<f64>::zeros(self.verts[0].len()), |acc, v| acc + &self.verts[*v])/(face.len() as f64);
+        let per = Interval::point(k as f64).inverse().unwrap();
+        subs.iter().fold(Interval::point(0f64), |acc, g| {
+          let points: Vec<&Array1<f64>> = hasse.faces[k-1][*g].iter().map(|v| &self.verts[*v]).collect();
+          let h = distance(&centre, &points).unwrap_or(Interval::new(0f64, f64::INFINITY));
+          acc + h*contents[k-1][*g]*per
+        })
+      }).collect();
+      contents.push(level);
+    }
+    contents
+  }
+
+  /// The n-volume of a polytope of rank n, measured in its own affine span, see `contents`
+  /// e.g. 1 for the cube, √2/12 for the tetrahedron, (25/4)(2+√5) for the 600-cell, all with unit edges
+  pub fn volume(&self) -> Certified {
+    self.contents().pop().unwrap()[0]
+  }
+
+  /// Volume, boundary content, and the contents of the facets by kind, where facets are alike if they have
+  /// the same number of vertices and the same content
+  pub fn content(&self) -> Content {
+    let contents = self.enclosures();
+    let rank = self.rank();
+    let volume = certified(&contents[rank][0]);
+    let mut facets: Vec<(usize, Certified, usize)> = Vec::new();
+    let mut boundary = Interval::point(0f64);
+    if rank > 0 {
+      for (facet, c) in self.faces[rank - 1].iter().zip(&contents[rank - 1]) {
+        boundary = boundary + *c;
+        let c = &certified(c);
+        let kind = facets.iter_mut().find(|(n, d, _)| *n == facet.len() && (d.value - c.value).abs() <= d.error + c.error + EPS);
+        match kind {
+          Some((_, _, count)) => *count += 1,
+          None => facets.push((facet.len(), *c, 1)),
+        }
+      }
+    }
+    facets.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.value.partial_cmp(&b.1.value).unwrap()));
+    Content { volume, boundary: certified(&boundary), facets, exact: None }
+  }
+}
+
+/// The midpoint and radius of an interval
+fn certified(x: &Interval) -> Certified {
+  Certified { value: x.mid(), error: x.radius() }
+}
+
+/// An interval around x, from 1/x if the terms of x cancel so much that its own interval is wide,
+/// as for the heights of star polytopes, e.g. 22070-9870√5 has the inverse (22070+9870√5)/400 that does not cancel
+fn interval(x: &CycFrac) -> Interval {
+  let direct = x.to_interval();
+  if direct.radius() <= 1e-12*direct.mid().abs() {
+    return direct;
+  }
+  match x.inverse().and_then(|y| y.to_interval().inverse()) {
+    Some(i) if i.radius() < direct.radius() => i,
+    _ => direct,
+  }
+}
+
+/// The sum of √x over the terms, in interval arithmetic
+fn enclose(terms: &[CycFrac]) -> Certified {
+  certified(&terms.iter().fold(Interval::point(0f64), |acc, x| {
+    acc + interval(x).intersect(&Interval::new(0f64, f64::INFINITY)).sqrt().unwrap()
+  }))
+}
+
+/// The content of a face as the sum of the square roots of the terms
+fn exact(terms: &[CycFrac]) -> Expr {
+  terms.iter().fold(Expr::int(0), |acc, x| acc + radical(x.clone()))
+}
+
+/// Exact contents of the faces of a Wythoffian polytope, by the same pyramids as `Polytope::contents`,
+/// with the heights from the exact squared circumradii R_K² of the faces (see `CoxGroup::radii`):
+/// the centre of a facet K' of the face K is at height √(R_K² - R_K'²) above it
+/// A content is kept as terms x with the content Σ√x, one for every chain of faces down to a vertex
+struct Exact<'a> {
+  graph: &'a CoxGraph<f64>,
+  values: Vec<Cyc>,
+  m: u32,
+  terms: HashMap<Vec<usize>, Vec<CycFrac>>,
+}
+
+impl<'a> Exact<'a> {
+  fn values(&self, nodes: &[usize]) -> Vec<Cyc> {
+    nodes.iter().map(|i| self.values[*i].clone()).collect()
+  }
+
+  fn ringed(&self, i: usize) -> bool {
+    !self.values[i].is_zero()
+  }
+
+  /// Order of the group of the nodes
+  fn order(&self, nodes: &[usize]) -> Option<i64> {
+    if nodes.is_empty() {
+      return Some(1);
+    }
+    let group = CoxGroup { graph: subgraph(self.graph, nodes), polys: Vec::new() };
+    Some(group.invariants()?.order as i64)
+  }
+
+  /// Number of vertices of the face of the nodes, the order of its group over that of its unringed nodes
+  fn vertices(&self, nodes: &[usize]) -> Option<usize> {
+    let unringed: Vec<usize> = nodes.iter().cloned().filter(|i| !self.ringed(*i)).collect();
+    Some((self.order(nodes)?/self.order(&unringed)?) as usize)
+  }
+
+  /// Number of facets of the face of `nodes` that are faces of `sub`, which leaves out the node j:
+  /// the order of its group over that of the stabilizer of such a facet, the group of `sub` and of j if j is unringed and apart
+  fn count(&self, nodes: &[usize], sub: &[usize], j: usize) -> Option<i64> {
+    let apart = !self.ringed(j) && sub.iter().all(|i| self.graph.find_edge(NodeIndex::new(*i), NodeIndex::new(j)).is_none());
+    Some(self.order(nodes)?/self.order(sub)?/if apart { 2 } else { 1 })
+  }
+
+  fn terms(&mut self, nodes: &[usize]) -> Option<Vec<CycFrac>> {
+    if nodes.is_empty() {
+      return Some(vec![CycFrac::int(1)]);
+    }
+    if let Some(t) = self.terms.get(nodes) {
+      return Some(t.clone());
+    }
+    let k = nodes.len() as i64;
+    let r2 = squared_radius(&subgraph(self.graph, nodes), &self.values(nodes), self.m);
+    let mut terms = Vec::new();
+    for j in nodes {
+      let sub: Vec<usize> = nodes.iter().cloned().filter(|i| i != j).collect();
+      if !is_face(self.graph, &sub, |i| self.ringed(i)) {
+        continue;
+      }
+      let n = self.count(nodes, &sub, *j)?;
+      let h2 = &r2 - &squared_radius(&subgraph(self.graph, &sub), &self.values(&sub), self.m);
+      // (n·h/k)² for every term of the facet
+      let factor = &h2*&CycFrac::new(Cyc::int(n*n), k*k);
+      terms.extend(self.terms(&sub)?.iter().map(|t| t*&factor));
+    }
+    self.terms.insert(nodes.to_vec(), terms.clone());
+    Some(terms)
+  }
+}
+
+impl CoxGroup<f64> {
+  /// Contents of the polytope of the diagram, see `Polytope::content`
+  /// If the node values are recognized, as by `CoxGroup::radii`, the contents are exact and the error bounds come from
+  /// evaluating them in interval arithmetic; otherwise, or if the group orders of the faces are not known or the exact numbers
+  /// get too large, they are measured from the vertices
+  /// e.g. x3o5o -> (15+5√5)/12, boundary 5√3
+  pub fn content(&self) -> Result<Content, Failed> {
+    let exact = exact_values(&self.graph).ok().and_then(|(values, m)| self.exact_content(values, m));
+    match exact {
+      Some(c) => Ok(c),
+      None => Ok(self.polytope()?.content()),
+    }
+  }
+
+  fn exact_content(&self, values: Vec<Cyc>, m: u32) -> Option<Content> {
+    let mut e = Exact { graph: &self.graph, values, m, terms: HashMap::new() };
+    let all: Vec<usize> = (0..self.graph.node_count()).collect();
+    let volume = e.terms(&all)?;
+    let mut boundary = Vec::new();
+    let mut kinds: Vec<(usize, Expr, Vec<CycFrac>, usize)> = Vec::new();
+    for j in &all {
+      let sub: Vec<usize> = all.iter().cloned().filter(|i| i != j).collect();
+      if !is_face(e.graph, &sub, |i| e.ringed(i)) {
+        continue;
+      }
+      let n = e.count(&all, &sub, *j)? as usize;
+      let (verts, terms) = (e.vertices(&sub)?, e.terms(&sub)?);
+      let c = exact(&terms);
+      let square = CycFrac::int((n*n) as i64);
+      boundary.extend(terms.iter().map(|t| t*&square));
+      match kinds.iter_mut().find(|(v, d, _, _)| *v == verts && *d == c) {
+        Some((_, _, _, count)) => *count += n,
+        None => kinds.push((verts, c, terms, n)),
+      }
+    }
+    let mut facets: Vec<(usize, Certified, usize)> = kinds.iter().map(|(v, _, t, n)| (*v, enclose(t), *n)).collect();
+    facets.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.value.partial_cmp(&b.1.value).unwrap()));
+    let (v, b) = (exact(&volume), exact(&boundary));
+    if v.overflowed() || b.overflowed() {
+      return None;
+    }
+    Some(Content {
+      volume: enclose(&volume),
+      boundary: enclose(&boundary),
+      facets,
+      exact: Some((v, b)),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse::group;
+
+  fn content(s: &str) -> Content {
+    group(s).content().unwrap()
+  }
+
+  fn close(c: &Certified, x: f64) -> bool {
+    (c.value - x).abs() <= c.error.max(1e-12) && c.error < 1e-9*x.max(1f64)
+  }
+
+  #[test]
+  fn volumes() {
+    let s5 = 5f64.sqrt();
+    for (s, v) in [("x4o3o", 1f64), ("x3o3o", 2f64.sqrt()/12f64), ("x3o5o", 5f64*(3f64 + s5)/12f64), ("x5o3o", (15f64 + 7f64*s5)/4f64),
+                   ("x4o3o3o", 1f64), ("x3o3o3o", s5/96f64), ("x3o4o3o", 2f64), ("x3o3o5o", 25f64*(2f64 + s5)/4f64),
+                   ("x4o x3o", 3f64.sqrt()/4f64), ("x6o", 3f64*3f64.sqrt()/2f64), ("x5/2o3o3o", 75f64/(47f64*s5 + 105f64))] {
+      let c = content(s);
+      assert!(close(&c.volume, v), "{} {} {}", s, c.volume, v);
+    }
+  }
+
+  #[test]
+  fn exact_contents() {
+    for (s, v, b) in [("x3o3o", "√2/12", "√3"), ("x3o5o", "(15+5√5)/12", "5√3"), ("x5o3o", "(15+7√5)/4", "3√(25+10√5)"),
+                      ("x3o3o5o", "(50+25√5)/4", "50√2"), ("x4o x3o", "√3/4", "3+√3")] {
+      let (volume, boundary) = content(s).exact.unwrap();
+      assert_eq!((volume.to_string(), boundary.to_string()), (v.to_string(), b.to_string()), "{}", s);
+    }
+    // measured from the vertices in interval arithmetic, which bounds the rounding but not the error of the coordinates
+    let p = group("x3o3o5o").polytope().unwrap().content();
+    assert!(p.exact.is_none() && p.volume.error < 1e-10, "{}", p.volume);
+    assert!((p.volume.value - 25f64*(2f64 + 5f64.sqrt())/4f64).abs() < 1e-10);
+  }
+
+  #[test]
+  fn boundaries() {
+    let cube = content("x4o3o");
+    assert!(close(&cube.boundary, 6f64));
+    // small rhombicuboctahedron: 8 triangles and 18 squares
+    let sirco = content("x4o3x");
+    assert_eq!(sirco.facets.iter().map(|(n, _, count)| (*n, *count)).collect::<Vec<_>>(), vec![(3, 8), (4, 18)]);
+    assert!(close(&sirco.facets[0].1, 3f64.sqrt()/4f64));
+    assert!(close(&sirco.boundary, 18f64 + 2f64*3f64.sqrt()));
+    // tesseract: 8 cubes
+    let tes = content("x4o3o3o");
+    assert_eq!(tes.facets.len(), 1);
+    assert!(close(&tes.boundary, 8f64));
+  }
+
+  #[test]
+  fn star_content() {
+    // pentagram: five triangles from the centre, so the pentagon in the middle counts twice
+    let t = std::f64::consts::PI/5f64;
+    let r = (2f64*t).cos()/(2f64*(2f64*t).sin());
+    assert!(close(&content("x5/2o").volume, 5f64*r/2f64));
+  }
+}
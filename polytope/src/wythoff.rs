@@ -0,0 +1,184 @@
+use std::fmt::Debug;
+
+use fraction::GenericFraction;
+use petgraph::graph::NodeIndex;
+
+use crate::{mark, CoxEdge, CoxGraph, CoxGroup, CoxNode};
+use crate::parse::{Scanner, SyntaxError};
+
+/// Wythoff symbols describe a polyhedron by the Schwarz triangle (p q r) it is built from
+/// and the position of the bar, which tells where the seed point lies:
+///
+/// p | q r  -> seed on the corner with angle π/p: the mirror opposite that corner is ringed
+/// p q | r  -> seed on the side opposite the corner with angle π/r: that mirror is unringed
+/// p q r |  -> seed inside the triangle: all mirrors ringed
+/// | p q r  -> snub: alternated omnitruncate
+///
+/// The triangle is converted to a diagram with nodes a, b, c, where node a is the mirror opposite
+/// corner p, b opposite q and c opposite r. Two mirrors meet in the corner that is not opposite either, so
+/// b-c = p, a-c = q, a-b = r
+/// e.g.
+/// 3 | 2 4   -> x4o3o (cube)
+/// 2 3 | 4   -> x4x3o (truncated cube)
+/// 2 | 3 4   -> o4x3o (cuboctahedron)
+/// | 2 3 4   -> s4s3s (snub cube)
+impl Scanner {
+  pub fn parse_wythoff<T: Debug + Clone + From<f64>>(&mut self) -> Result<CoxGroup<T>, SyntaxError> {
+    let mut marks: Vec<CoxEdge> = Vec::new();
+    let mut bar: Option<usize> = None;
+    loop {
+      while self.take(&' ') {}
+      match self.peek() {
+        None => break,
+        Some('|') => {
+          self.pop();
+          if bar.is_some() {
+            return Err(SyntaxError::new_parse_error(format!("Second bar at position {}", self.cursor())));
+          }
+          bar = Some(marks.len());
+        },
+        Some(c) if c.is_ascii_digit() => {
+          marks.push(self.read_mark()?);
+        },
+        Some(c) => {
+          return Err(SyntaxError::new_parse_error(format!("Not part of a Wythoff symbol at {}: found {}", self.cursor(), c)));
+        },
+      }
+    }
+    if marks.len() != 3 {
+      return Err(SyntaxError::new_parse_error(format!("Wythoff symbol needs 3 numbers, found {}", marks.len())));
+    }
+    let bar = match bar {
+      Some(b) => b,
+      None => return Err(SyntaxError::new_parse_error("Wythoff symbol without a bar".to_string())),
+    };
+
+    // which of the nodes a,b,c are ringed, see the table above
+    let reprs = match bar {
+      0 => ['s', 's', 's'],
+      1 => ['x', 'o', 'o'],
+      2 => ['x', 'x', 'o'],
+      _ => ['x', 'x', 'x'],
+    };
+    let mut graph = CoxGraph::<T>::default();
+    for (i, r) in reprs.iter().enumerate() {
+      graph.add_node(CoxNode::<T> {
+        name: (b'a' + i as u8) as char,
+        repr: r.to_string(),
+        val: self.eval::<T>(r)?,
+        mats: Vec::new(),
+        cart_vec: None,
+      });
+    }
+    let [p, q, r] = [marks[0], marks[1], marks[2]];
+    for (i, j, e) in [(1, 2, p), (0, 2, q), (0, 1, r)] {
+      if e != CoxEdge::Int(2) {
+        graph.add_edge(NodeIndex::new(i), NodeIndex::new(j), e);
+      }
+    }
+    Ok(CoxGroup { graph, polys: Vec::new() })
+  }
+
+  /// Reads a mark with possibly multiple digits, e.g. 3, 10, 5/2
+  /// Fractions that are whole numbers are returned as `CoxEdge::Int`
+  /// Marks of 1 or less, like 0, 1 or 3/3, are not angles between mirrors and give an error
+  pub(crate) fn read_mark(&mut self) -> Result<CoxEdge, SyntaxError> {
+    let p = self.read_number()?;
+    let q = if self.take(&'/') { self.read_number()? } else { 1 };
+    if q == 0 {
+      return Err(SyntaxError::new_parse_error(format!("Zero denominator at {}", self.cursor())));
+    }
+    if p <= q {
+      return Err(SyntaxError::new_parse_error(format!("Mark {}/{} not above 1 at {}", p, q, self.cursor())));
+    }
+    let f = GenericFraction::<u32>::new(p, q);
+    match f.denom() {
+      Some(1) => Ok(CoxEdge::Int(*f.numer().unwrap())),
+      _ => Ok(CoxEdge::Frac(f)),
+    }
+  }
+
+  /// Reads a whole number, numbers that do not fit in a u32 give an error
+  pub(crate) fn read_number(&mut self) -> Result<u32, SyntaxError> {
+    let mut n: Option<u32> = None;
+    while let Some(d) = self.transform(|c| c.to_digit(10)) {
+      n = Some(n.unwrap_or(0).checked_mul(10).and_then(|n| n.checked_add(d)).ok_or_else(|| {
+        SyntaxError::new_parse_error(format!("Number too large at {}", self.cursor()))
+      })?);
+    }
+    n.ok_or_else(|| SyntaxError::new_parse_error(format!("Not a numerical at {}", self.cursor())))
+  }
+}
+
+impl<T: Clone> CoxGroup<T> {
+  /// Returns the Wythoff symbol of a 3-node diagram, the inverse of `Scanner::parse_wythoff`
+  /// None if the diagram does not have 3 nodes, has no ringed nodes, or mixes snub and non-snub nodes
+  pub fn wythoff(&self) -> Option<String> {
+    if self.graph.node_count() != 3 {
+      return None;
+    }
+    let reprs: Vec<&str> = self.graph.node_weights().map(|n| n.repr.as_str()).collect();
+    let snubs = reprs.iter().filter(|r| **r == "s").count();
+    let ringed: Vec<usize> = (0..3).filter(|i| reprs[*i] != "o").collect();
+    let m = |i, j| mark(&self.graph, i, j);
+    match (snubs, ringed.as_slice()) {
+      (3, _) => Some(format!("| {} {} {}", m(1, 2), m(0, 2), m(0, 1))),
+      (0, [i]) => {
+        let (j, k) = others(*i);
+        Some(format!("{} | {} {}", m(j, k), m(*i, k), m(*i, j)))
+      },
+      (0, [i, j]) => {
+        let k = 3 - i - j;
+        Some(format!("{} {} | {}", m(*j, k), m(*i, k), m(*i, *j)))
+      },
+      (0, [_, _, _]) => Some(format!("{} {} {} |", m(1, 2), m(0, 2), m(0, 1))),
+      _ => None,
+    }
+  }
+}
+
+/// The two nodes of a triangle other than `i`, in increasing order
+fn others(i: usize) -> (usize, usize) {
+  match i {
+    0 => (1, 2),
+    1 => (0, 2),
+    _ => (0, 1),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn round_trip(s: &str) -> Option<String> {
+    let mut sc = Scanner::new(s);
+    let group = sc.parse_wythoff::<f64>().unwrap();
+    group.wythoff()
+  }
+
+  #[test]
+  fn wythoff_round_trip() {
+    for s in ["3 | 2 5", "2 3 | 5/2", "| 2 3 5", "2 3 4 |", "5/3 | 2 3"] {
+      assert_eq!(round_trip(s).as_deref(), Some(s));
+    }
+    assert_eq!(round_trip("|2 3 5").as_deref(), Some("| 2 3 5"));
+  }
+
+  #[test]
+  fn wythoff_graph() {
+    // 3 | 2 4 is the cube x4o3o
+    let mut sc = Scanner::new("3 | 2 4");
+    let g = sc.parse_wythoff::<f64>().unwrap();
+    assert_eq!(g.graph.edge_count(), 2);
+    assert_eq!(mark(&g.graph, 0, 1), CoxEdge::Int(4));
+    assert_eq!(mark(&g.graph, 1, 2), CoxEdge::Int(3));
+    assert_eq!(g.graph[NodeIndex::new(0)].repr, "x");
+    assert!(Scanner::new("3 2 4").parse_wythoff::<f64>().is_err());
+    assert!(Scanner::new("3 | 2 | 4").parse_wythoff::<f64>().is_err());
+    assert!(Scanner::new("3 | 1 4").parse_wythoff::<f64>().is_err());
+    assert!(Scanner::new("0 | 2 4").parse_wythoff::<f64>().is_err());
+    // marks too large for a u32, in Wythoff symbols and in diagrams
+    assert!(Scanner::new("3 | 2 99999999999").parse_wythoff::<f64>().is_err());
+    assert!(Scanner::new("x4294967296o").parse_tope::<f64>().is_err());
+  }
+}